@@ -355,6 +355,32 @@ public static class ArrayHelpers
         throw new Exception("Unsupported type: " + typeof(T) + ". Supported types are Half, float, double, and Apache.Arrow.Array.");
     }
 
+    internal static RecordBatch VectorsToRecordBatch(IList<float[]> vectors)
+    {
+        // One row per vector, in a single fixed-size list column
+        if (vectors.Count == 0)
+        {
+            throw new ArgumentException("At least one vector is required.");
+        }
+        var dimension = vectors[0].Length;
+        var builder = new FloatArray.Builder();
+        foreach (var vector in vectors)
+        {
+            if (vector.Length != dimension)
+            {
+                throw new ArgumentException("All vectors must have the same length.");
+            }
+            builder.AppendRange(vector);
+        }
+        var floatArray = builder.Build();
+
+        var vectorType = new FixedSizeListType(new Field("item", FloatType.Default, nullable: true), dimension);
+        var vectorArray = new FixedSizeListArray(new ArrayData(vectorType, vectors.Count, 0, 0,
+            [ArrowBuffer.Empty], [floatArray.Data]));
+        var schema = new Schema([new Field("vector", vectorType, nullable: false)], new Dictionary<string, string>());
+        return new RecordBatch(schema, [vectorArray], vectors.Count);
+    }
+
     internal static IList<RecordBatch> ArrowTableToRecordBatch(Apache.Arrow.Table data)
     {
         // Extract the schema from the table
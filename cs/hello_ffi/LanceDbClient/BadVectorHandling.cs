@@ -4,6 +4,7 @@
     {
         Error=1,
         Drop=2,
-        Fill=3
+        Fill=3,
+        Null=4
     }
 }
@@ -0,0 +1,19 @@
+using Apache.Arrow;
+
+namespace LanceDbClient;
+
+/// <summary>
+/// What a delete would remove, without deleting anything.
+/// </summary>
+public class DeletePreview
+{
+    /// <summary>
+    /// The number of rows the delete would remove.
+    /// </summary>
+    public ulong MatchingRows { get; set; } = 0;
+
+    /// <summary>
+    /// Up to the requested number of the matching rows.
+    /// </summary>
+    public List<RecordBatch> Sample { get; set; } = new();
+}
@@ -11,6 +11,9 @@ static partial class Ffi
     //private const string DllName = "../../../../../../rust/target/debug/liblance_sync_client.so";
     // Use this one for Docker 
     private const string DllName = "liblance_sync_client.so";
+
+    // Record batches sent to query callbacks use the Arrow IPC file format (2 selects the stream format)
+    internal const uint IpcFileFormat = 1;
     
     /* Delegate types */
     
@@ -33,12 +36,36 @@ static partial class Ffi
     [UnmanagedFunctionPointer(CallingConvention.Cdecl)]
     internal delegate void UpdateCalback(ulong updatedRows);
 
+    [UnmanagedFunctionPointer(CallingConvention.Cdecl)]
+    internal delegate void MergeStatsCallback(ulong inserted, long updated, ulong deleted);
+
     [UnmanagedFunctionPointer(CallingConvention.Cdecl)]
     internal delegate void TableIndexEntryCallback(string name, uint indexType, string[] columns, ulong columnsLength);
 
     [UnmanagedFunctionPointer(CallingConvention.Cdecl)]
     internal delegate void IndexStatisticsCallback(uint indexType, uint metricType, ulong numIndexedRows, ulong numIndices, ulong numIndexRows);
 
+    [UnmanagedFunctionPointer(CallingConvention.Cdecl)]
+    internal delegate void MaintenanceStatusCallback(long lastRunMs, long nextCheckMs, ulong runs, string? error);
+
+    // Writes textCount * dimension floats into vectors; returns FALSE if embedding failed
+    [UnmanagedFunctionPointer(CallingConvention.Cdecl)]
+    internal unsafe delegate bool EmbeddingCallback(IntPtr* texts, ulong textCount, float* vectors, ulong dimension);
+
+    // Writes one score per candidate row into scores; returns FALSE if reranking failed
+    [UnmanagedFunctionPointer(CallingConvention.Cdecl)]
+    internal unsafe delegate bool RerankCallback(string? queryText, byte* candidates, ulong candidatesLength, float* scores, ulong rowCount);
+
+    [UnmanagedFunctionPointer(CallingConvention.Cdecl)]
+    internal delegate void RetryCallback(long tableHandle, uint attempt, ulong delayMs, string error);
+
+    [UnmanagedFunctionPointer(CallingConvention.Cdecl)]
+    internal delegate void DurabilityCallback(long tableHandle, ulong rows, string? error);
+
+    // This function returns TRUE to continue the import, or FALSE to cancel it
+    [UnmanagedFunctionPointer(CallingConvention.Cdecl)]
+    internal delegate bool ImportProgressCallback(ulong rows, long totalRows);
+
     /* FFI functions */
     [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
     internal static extern void connect(string uri, ulong numStorageOptions, string[]? storageOptions, ResultCallback onResult);
@@ -69,17 +96,21 @@ static partial class Ffi
 
     [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
     [UnmanagedCallConv(CallConvs = new Type[] { typeof(System.Runtime.CompilerServices.CallConvCdecl) })]
-    internal static extern unsafe void add_record_batch(long connectionId, long TableId, byte* data, ulong len, uint write_mode, ResultCallback onResult);
+    internal static extern unsafe void add_record_batch(long connectionId, long TableId, byte* data, ulong len, uint write_mode, bool autoCast,
+        uint badVectorHandling, float fillValue, ResultCallback onResult);
     
     [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
     internal static extern void count_rows(long connectionHandle, long tableHandle, string? filter, ResultCallback onResult);
     
     [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
-    internal static extern unsafe void merge_insert_with_record_batch(long connectionId, long tableId, string[] columns, ulong columnsLength, bool when_not_matched_insert_all,
-        string? where_clause, string? when_not_matched_by_source_delete, byte* data, ulong batch_len, ResultCallback onResult);
+    internal static extern unsafe void merge_insert_with_record_batch(long connectionId, long tableId, string[] columns, ulong columnsLength,
+        bool whenMatchedUpdateAll, string? whenMatchedUpdateCondition, bool whenNotMatchedInsertAll,
+        bool whenNotMatchedBySourceDelete, string? whenNotMatchedBySourceDeleteFilter, byte* data, ulong batch_len,
+        uint badVectorHandling, float fillValue, bool predictStats, MergeStatsCallback? statsCallback, ResultCallback onResult);
     
     [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
-    internal static extern void delete_rows(long connectionHandle, long tableHandle, string? filter, ResultCallback onResult);
+    internal static extern void delete_rows(long connectionHandle, long tableHandle, string? filter, bool deleteAll,
+        bool dryRun, ulong sampleLimit, BlobCallback? sampleCallback, uint ipcFormat, ResultCallback onResult);
     
     [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
     internal static extern void optimize_table(long connectionHandle, long tableHandle,
@@ -89,14 +120,20 @@ static partial class Ffi
     [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
     internal static extern void query(long connectionHandle, long tableHandle, BlobCallback onRecBatch, 
         ResultCallback onResult, ulong limit, string? whereClause, bool withRowId,
-        string[] columns, ulong columnsLength, string? fullTextSearch, uint batchSize);
+        string[] columns, ulong columnsLength, string? fullTextSearch, uint batchSize, uint ipcFormat,
+        ulong offset, string? pageToken, StringCallback? nextPageCallback,
+        string[]? projectionAliases, string[]? projectionExpressions, ulong projectionsLength,
+        uint reranker, float rerankerParam, string? rerankerName);
     
     [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
     internal static extern unsafe void vector_query(long connectionHandle, long tableHandle, BlobCallback onRecBatch, 
         ResultCallback onResult, ulong limit, string? whereClause, bool withRowId,
         string[] columns, ulong columnsLength, uint vectorType, byte* vectorBlob,
         ulong vectorBlogLength, ulong numElements, uint metric, ulong nProbes, uint refineFactor,
-        uint batchSize, float distanceRangeMin, float distanceRangeMax);
+        uint batchSize, float distanceRangeMin, float distanceRangeMax, uint ipcFormat,
+        ulong offset, string? pageToken, StringCallback? nextPageCallback,
+        string[]? projectionAliases, string[]? projectionExpressions, ulong projectionsLength,
+        bool postfilter, bool fastSearch, uint reranker, float rerankerParam, string? rerankerName);
     
     [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
     internal static extern unsafe void explain_vector_query(long connectionHandle, long tableHandle, 
@@ -106,7 +143,7 @@ static partial class Ffi
         uint vectorType, byte* vectorBlob,
         ulong vectorBlogLength, ulong numElements,
         uint metric, ulong nProbes, uint refineFactor,
-        float distanceRangeMin, float distanceRangeMax);
+        float distanceRangeMin, float distanceRangeMax, bool postfilter, bool fastSearch);
     
     [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
     internal static extern void explain_query(long connectionHandle, long tableHandle, ulong limit, 
@@ -127,7 +164,10 @@ static partial class Ffi
     internal static extern void create_index(long connectionHandle, long tableHandle, string columnName, uint metric, uint numPartitions, uint numSubVectors, bool replace, ResultCallback onResult);
     
     [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
-    internal static extern void update_rows(long connectionHandle, long tableHandle, string[] updates, ulong updatesLength, string? where, ResultCallback onResult, UpdateCalback updateCalback);
+    internal static extern void update_rows(long connectionHandle, long tableHandle, string[] columns, string[] expressions, ulong updatesLength, string? where, ResultCallback onResult, UpdateCalback updateCalback);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void update_sql(long connectionHandle, long tableHandle, string sql, ResultCallback onResult, UpdateCalback updateCalback);
     
     [LibraryImport(DllName)]
     [UnmanagedCallConv(CallConvs = new Type[] { typeof(System.Runtime.CompilerServices.CallConvCdecl) })]
@@ -139,6 +179,157 @@ static partial class Ffi
     [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
     internal static extern void get_index_statistics(long connectionHandle, long tableHandle, string indexName, IndexStatisticsCallback callback, ResultCallback onResult);
     
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void register_maintenance(long connectionHandle, long tableHandle, ulong checkIntervalSeconds,
+        long maxFragments, long pruneOlderThanSeconds, long maxUnindexedRows, ResultCallback onResult);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void deregister_maintenance(long maintenanceHandle, ResultCallback onResult);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void get_maintenance_status(long maintenanceHandle, MaintenanceStatusCallback callback, ResultCallback onResult);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void add_columns(long connectionHandle, long tableHandle, string[] names, string[] expressions,
+        ulong columnsLength, BlobCallback? schemaCallback, ResultCallback onResult);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern unsafe void add_null_columns(long connectionHandle, long tableHandle, byte* schema, ulong schemaLength,
+        BlobCallback? schemaCallback, ResultCallback onResult);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern unsafe void alter_columns(long connectionHandle, long tableHandle, string[] paths, string?[]? renames,
+        int[]? nullable, ulong columnsLength, byte* castSchema, ulong castSchemaLength, BlobCallback? schemaCallback,
+        ResultCallback onResult);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void drop_columns(long connectionHandle, long tableHandle, string[] columns, ulong columnsLength,
+        BlobCallback? schemaCallback, ResultCallback onResult);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void get_table_schema(long connectionHandle, long tableHandle, BlobCallback schemaCallback, ResultCallback onResult);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void register_embedding_function(string name, ulong dimension, EmbeddingCallback callback, ResultCallback onResult);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void bind_embedding_column(long connectionHandle, long tableHandle, string sourceColumn,
+        string vectorColumn, string functionName, ResultCallback onResult);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void text_vector_query(long connectionHandle, long tableHandle, BlobCallback onRecBatch,
+        ResultCallback onResult, ulong limit, string? whereClause, bool withRowId,
+        string[]? columns, ulong columnsLength, string queryText, string? vectorColumn,
+        uint metric, ulong nProbes, uint refineFactor, uint batchSize,
+        float distanceRangeMin, float distanceRangeMax, uint ipcFormat);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void begin_write(long connectionHandle, long tableHandle, uint writeMode, ResultCallback onResult);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern unsafe void write_batch(long sessionHandle, byte* data, ulong len, ResultCallback onResult);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void commit_write(long sessionHandle, ResultCallback onResult);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void abort_write(long sessionHandle, ResultCallback onResult);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern unsafe void add_record_batch_array(long connectionHandle, long tableHandle,
+        Apache.Arrow.C.CArrowArray* array, Apache.Arrow.C.CArrowSchema* schema, uint writeMode, bool autoCast,
+        uint badVectorHandling, float fillValue, ResultCallback onResult);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern unsafe void add_record_batch_stream(long connectionHandle, long tableHandle,
+        Apache.Arrow.C.CArrowArrayStream* stream, uint writeMode, bool autoCast, ResultCallback onResult);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern unsafe void query_stream(long connectionHandle, long tableHandle,
+        Apache.Arrow.C.CArrowArrayStream* outStream, ResultCallback onResult, ulong limit, string? whereClause,
+        bool withRowId, string[]? columns, ulong columnsLength, string? fullTextSearch, uint batchSize);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern unsafe void vector_query_stream(long connectionHandle, long tableHandle,
+        Apache.Arrow.C.CArrowArrayStream* outStream, ResultCallback onResult, ulong limit, string? whereClause,
+        bool withRowId, string[]? columns, ulong columnsLength, uint vectorType, byte* vectorBlob,
+        ulong vectorBlobLength, ulong numElements, uint metric, ulong nProbes, uint refineFactor, uint batchSize,
+        float distanceRangeMin, float distanceRangeMax, bool postfilter, bool fastSearch);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void import_file(long connectionHandle, string tableName, string path, uint format,
+        byte csvDelimiter, bool csvHasHeader, ulong inferSchemaMaxRecords, uint batchSize, uint writeMode,
+        bool createTable, ImportProgressCallback? progressCallback, ResultCallback onResult);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void export_query_to_file(long connectionHandle, long tableHandle, string path, uint format,
+        uint compression, ulong rowGroupSize, ResultCallback onResult, ulong limit, string? whereClause,
+        bool withRowId, string[]? columns, ulong columnsLength, string? fullTextSearch, uint batchSize);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern unsafe void export_vector_query_to_file(long connectionHandle, long tableHandle, string path,
+        uint format, uint compression, ulong rowGroupSize, ResultCallback onResult, ulong limit, string? whereClause,
+        bool withRowId, string[]? columns, ulong columnsLength, uint vectorType, byte* vectorBlob,
+        ulong vectorBlobLength, ulong numElements, uint metric, ulong nProbes, uint refineFactor, uint batchSize,
+        float distanceRangeMin, float distanceRangeMax, bool postfilter, bool fastSearch);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void add_json_rows(long connectionHandle, long tableHandle, string json, uint writeMode,
+        uint badVectorHandling, float fillValue, ResultCallback onResult);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void enable_write_buffer(long connectionHandle, long tableHandle, ulong maxRows, ulong maxBytes,
+        ulong flushIntervalMs, DurabilityCallback? durabilityCallback, ResultCallback onResult);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void disable_write_buffer(long connectionHandle, long tableHandle, ResultCallback onResult);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void flush_write_buffer(long connectionHandle, long tableHandle, ResultCallback onResult);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void set_retry_policy(long connectionHandle, long tableHandle, uint maxAttempts,
+        ulong initialBackoffMs, ulong maxBackoffMs, double jitter, RetryCallback? retryCallback, ResultCallback onResult);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void open_query(long connectionHandle, long tableHandle, ResultCallback onResult,
+        ulong limit, string? whereClause, bool withRowId, string[]? columns, ulong columnsLength,
+        string? fullTextSearch, uint batchSize, uint ipcFormat, ulong offset, string? pageToken,
+        StringCallback? nextPageCallback, string[]? projectionAliases, string[]? projectionExpressions,
+        ulong projectionsLength);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern unsafe void open_vector_query(long connectionHandle, long tableHandle, ResultCallback onResult,
+        ulong limit, string? whereClause, bool withRowId, string[]? columns, ulong columnsLength,
+        uint vectorType, byte* vectorBlob, ulong vectorBlobLength, ulong numElements, uint metric, ulong nProbes,
+        uint refineFactor, uint batchSize, float distanceRangeMin, float distanceRangeMax, uint ipcFormat,
+        ulong offset, string? pageToken, StringCallback? nextPageCallback,
+        string[]? projectionAliases, string[]? projectionExpressions, ulong projectionsLength,
+        bool postfilter, bool fastSearch);
+    
+    // The batch is passed to batchCallback on the calling thread; the result is 1 for a batch, 0 at the end
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void next_batch(long cursorHandle, BlobCallback batchCallback, ResultCallback onResult);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void close_cursor(long cursorHandle, ResultCallback onResult);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern unsafe void hybrid_query(long connectionHandle, long tableHandle, BlobCallback onRecBatch,
+        ResultCallback onResult, ulong limit, string? whereClause, bool withRowId,
+        string[]? columns, ulong columnsLength, uint vectorType, byte* vectorBlob,
+        ulong vectorBlobLength, ulong numElements, uint metric, ulong nProbes, string fullTextSearch,
+        uint reranker, float rerankerParam, string? rerankerName, uint batchSize, uint ipcFormat);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void register_reranker(string name, RerankCallback callback, ResultCallback onResult);
+    
+    [DllImport(DllName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern unsafe void batch_vector_query(long connectionHandle, long tableHandle, BlobCallback onRecBatch,
+        ResultCallback onResult, byte* queries, ulong queriesLength, ulong limit, string? whereClause,
+        bool withRowId, string[]? columns, ulong columnsLength, string? vectorColumn, uint metric,
+        ulong nProbes, uint refineFactor, uint concurrency, uint batchSize, uint ipcFormat);
+    
     internal static byte[] SerializeSchemaOnly(Schema schema)
     {
         using var ms = new MemoryStream();
@@ -0,0 +1,105 @@
+using System.Runtime.InteropServices;
+using Apache.Arrow;
+
+namespace LanceDbClient;
+
+/// <summary>
+/// Embedding functions and rerankers written in C#, registered by name so that tables and queries
+/// can use them. Registrations are shared by every connection in the process.
+/// </summary>
+public static class HostFunctions
+{
+    // LanceDB keeps calling registered functions, so their delegates must not be collected
+    private static readonly Dictionary<string, Delegate> Registered = new();
+
+    /// <summary>
+    /// Register an embedding function, replacing any function already registered with that name.
+    /// Bind it to a table's columns with <c>Table.BindEmbeddingColumn</c>.
+    /// </summary>
+    /// <param name="name">The name to register the function under.</param>
+    /// <param name="dimension">The number of elements in each vector.</param>
+    /// <param name="embed">Returns one vector for each text.</param>
+    /// <exception cref="Exception">If registration fails</exception>
+    public static unsafe void RegisterEmbeddingFunction(string name, int dimension,
+        Func<IReadOnlyList<string>, float[][]> embed)
+    {
+        Ffi.EmbeddingCallback callback = (texts, textCount, vectors, vectorDimension) =>
+        {
+            try
+            {
+                var input = new List<string>((int)textCount);
+                for (var i = 0; i < (int)textCount; i++)
+                {
+                    input.Add(Marshal.PtrToStringUTF8(texts[i]) ?? "");
+                }
+                var output = embed(input);
+                if (output.Length != input.Count) return false;
+                var destination = new Span<float>(vectors, (int)(textCount * vectorDimension));
+                for (var i = 0; i < output.Length; i++)
+                {
+                    if (output[i].Length != (int)vectorDimension) return false;
+                    output[i].CopyTo(destination.Slice(i * (int)vectorDimension));
+                }
+                return true;
+            }
+            catch (Exception)
+            {
+                return false;
+            }
+        };
+        Exception? exception = null;
+        Ffi.register_embedding_function(name, (ulong)dimension, callback, (code, message) =>
+        {
+            if (code < 0 && message != null)
+            {
+                exception = new Exception("Failed to register the embedding function: " + message);
+            }
+        });
+        if (exception != null) throw exception;
+        lock (Registered)
+        {
+            Registered["embedding:" + name] = callback;
+        }
+    }
+
+    /// <summary>
+    /// Register a reranker, replacing any reranker already registered with that name. Hybrid queries
+    /// use it with <c>RerankNative(NativeReranker.Host, hostRerankerName: name)</c>.
+    /// </summary>
+    /// <param name="name">The name to register the reranker under.</param>
+    /// <param name="rerank">Receives the query text (null if there is none) and the candidate rows, and
+    /// returns one score per row, higher being more relevant.</param>
+    /// <exception cref="Exception">If registration fails</exception>
+    public static unsafe void RegisterReranker(string name, Func<string?, RecordBatch, float[]> rerank)
+    {
+        Ffi.RerankCallback callback = (queryText, candidates, candidatesLength, scores, rowCount) =>
+        {
+            try
+            {
+                var batchBytes = new byte[candidatesLength];
+                Marshal.Copy((IntPtr)candidates, batchBytes, 0, (int)candidatesLength);
+                var result = rerank(queryText, Ffi.DeserializeRecordBatch(batchBytes));
+                if (result.Length != (int)rowCount) return false;
+                result.CopyTo(new Span<float>(scores, (int)rowCount));
+                return true;
+            }
+            catch (Exception)
+            {
+                return false;
+            }
+        };
+        Exception? exception = null;
+        Ffi.register_reranker(name, callback, (code, message) =>
+        {
+            if (code < 0 && message != null)
+            {
+                exception = new Exception("Failed to register the reranker: " + message);
+            }
+        });
+        if (exception != null) throw exception;
+        lock (Registered)
+        {
+            Registered["reranker:" + name] = callback;
+        }
+    }
+}
@@ -1,9 +1,14 @@
+using System.Runtime.InteropServices;
 using Apache.Arrow;
 
 namespace LanceDbClient;
 
 public class HybridQueryBuilder : VectorQueryBuilder, ILanceHybridQueryBuilder
 {
+    private NativeReranker? _nativeReranker;
+    private float _nativeRerankerParam = Single.NaN;
+    private string? _hostRerankerName;
+
     internal HybridQueryBuilder(long connectionId, long tableId) : base(connectionId, tableId)
     {
         // Defaults
@@ -25,6 +30,10 @@ public class HybridQueryBuilder : VectorQueryBuilder, ILanceHybridQueryBuilder
         {
             throw new Exception("FullTextSearch and VectorData must be set before calling ToBatches");
         }
+        if (_nativeReranker != null)
+        {
+            return NativeHybridQuery(batchSize);
+        }
         if (Reranker == null)
         {
             throw new Exception("Reranker must be set before calling ToBatches");
@@ -62,6 +71,14 @@ public class HybridQueryBuilder : VectorQueryBuilder, ILanceHybridQueryBuilder
         {
             throw new Exception("FullTextSearch and VectorData must be set before calling ToBatches");
         }
+        if (_nativeReranker != null)
+        {
+            foreach (var batch in await Task.Run(() => NativeHybridQuery(batchSize), token))
+            {
+                yield return batch;
+            }
+            yield break;
+        }
         if (Reranker == null)
         {
             throw new Exception("Reranker must be set before calling ToBatches");
@@ -95,6 +112,65 @@ public class HybridQueryBuilder : VectorQueryBuilder, ILanceHybridQueryBuilder
         }
     }
 
+    /// <summary>
+    /// Run both searches and fuse their results inside LanceDB, instead of with a C# reranker.
+    /// </summary>
+    /// <param name="reranker">The reranker to fuse the results with.</param>
+    /// <param name="param">For RRF, k (60 if NaN); for a linear combination, the vector search's weight
+    /// from 0 to 1 (0.7 if NaN).</param>
+    /// <param name="hostRerankerName">For <c>NativeReranker.Host</c>, the name the reranker was registered under.</param>
+    /// <returns>The query builder to continue building.</returns>
+    public ILanceHybridQueryBuilder RerankNative(NativeReranker reranker = NativeReranker.Rrf, float param = Single.NaN,
+        string? hostRerankerName = null)
+    {
+        _nativeReranker = reranker;
+        _nativeRerankerParam = param;
+        _hostRerankerName = hostRerankerName;
+        return this;
+    }
+
+    /// <summary>
+    /// Hybrid results are fused all at once, so they can't be read through a cursor.
+    /// </summary>
+    public override QueryCursor ToCursor(int batchSize = 0)
+    {
+        throw new Exception("Hybrid queries can't be read through a cursor; use ToBatches.");
+    }
+
+    private unsafe List<RecordBatch> NativeHybridQuery(int batchSize)
+    {
+        var result = new List<RecordBatch>();
+        Exception? exception = null;
+        string[]? selectColumns = null;
+        if (SelectColumnsList.Count > 0)
+        {
+            selectColumns = SelectColumnsList.ToArray();
+        }
+
+        fixed (byte* b = VectorData!.Data)
+        {
+            Ffi.hybrid_query(ConnectionId, TableId, (bytes, len) =>
+                {
+                    var batchBytes = new byte[len];
+                    Marshal.Copy((IntPtr)bytes, batchBytes, 0, (int)len);
+                    result.Add(Ffi.DeserializeRecordBatch(batchBytes));
+                    return true;
+                }, (code, message) =>
+                {
+                    if (code < 0 && message != null)
+                    {
+                        exception = new Exception("Failed to run the hybrid query: " + message);
+                    }
+                }, LimitCount, WhereSql, WithRowIdent, selectColumns, (ulong)SelectColumnsList.Count,
+                (uint)VectorData.DataType, b, (ulong)VectorData.Data.Length, VectorData.Length,
+                (uint)DistanceMetric, (ulong)NumProbes, FullTextSearch!, (uint)_nativeReranker!.Value,
+                _nativeRerankerParam, _hostRerankerName, (uint)batchSize, Ffi.IpcFileFormat);
+        }
+
+        if (exception != null) throw exception;
+        return result;
+    }
+
     public new ILanceHybridQueryBuilder Metric(Metric metric = LanceDbClient.Metric.L2)
     {
         DistanceMetric = metric;
@@ -5,5 +5,6 @@
         ILanceHybridQueryBuilder Metric(Metric metric = LanceDbClient.Metric.L2);
         ILanceHybridQueryBuilder NProbes(int nProbes);
         ILanceHybridQueryBuilder RefineFactor(int refineFactor);
+        ILanceHybridQueryBuilder RerankNative(NativeReranker reranker = NativeReranker.Rrf, float param = float.NaN, string? hostRerankerName = null);
     }
 }
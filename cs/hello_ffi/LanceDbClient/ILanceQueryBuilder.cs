@@ -25,5 +25,8 @@ namespace LanceDbClient
         
         IEnumerable<RecordBatch> ToBatches(int batchSize); 
         IAsyncEnumerable<RecordBatch> ToBatchesAsync(int batchSize, CancellationToken token = default); 
+
+        QueryCursor ToCursor(int batchSize = 0);
+        Task<QueryCursor> ToCursorAsync(int batchSize = 0, CancellationToken token = default);
     }
 }
@@ -34,9 +34,17 @@ namespace LanceDbClient
 
         ulong UpdateSql(IDictionary<string, string> updates, string? whereClause = null);
         Task<ulong> UpdateSqlAsync(IDictionary<string, string> updates, string? whereClause = null, CancellationToken token = default);
+        ulong UpdateSql(string sql);
+        Task<ulong> UpdateSqlAsync(string sql, CancellationToken token = default);
 
-        void Delete(string whereClause);
-        Task DeleteAsync(string whereClause, CancellationToken token = default);
+        ulong Delete(string whereClause);
+        Task<ulong> DeleteAsync(string whereClause, CancellationToken token = default);
+
+        ulong DeleteAll();
+        Task<ulong> DeleteAllAsync(CancellationToken token = default);
+
+        DeletePreview PreviewDelete(string? whereClause, ulong sampleLimit = 10);
+        Task<DeletePreview> PreviewDeleteAsync(string? whereClause, ulong sampleLimit = 10, CancellationToken token = default);
 
         void Close();
         Task CloseAsync(CancellationToken cancellationToken = default);
@@ -61,5 +69,24 @@ namespace LanceDbClient
         
         IndexStatistics GetIndexStatistics(string indexName);
         Task<IndexStatistics> GetIndexStatisticsAsync(string columnName, CancellationToken token = default);
+
+        void SetRetryPolicy(int maxAttempts, TimeSpan? initialBackoff = null, TimeSpan? maxBackoff = null, double jitter = 0.0, Action<int, TimeSpan, string>? onRetry = null);
+        Task SetRetryPolicyAsync(int maxAttempts, TimeSpan? initialBackoff = null, TimeSpan? maxBackoff = null, double jitter = 0.0, Action<int, TimeSpan, string>? onRetry = null, CancellationToken token = default);
+
+        void EnableWriteBuffer(ulong maxRows = 0, ulong maxBytes = 0, TimeSpan? flushInterval = null, Action<ulong, string?>? onFlush = null);
+        Task EnableWriteBufferAsync(ulong maxRows = 0, ulong maxBytes = 0, TimeSpan? flushInterval = null, Action<ulong, string?>? onFlush = null, CancellationToken token = default);
+        ulong FlushWriteBuffer();
+        Task<ulong> FlushWriteBufferAsync(CancellationToken token = default);
+        void DisableWriteBuffer();
+        Task DisableWriteBufferAsync(CancellationToken token = default);
+
+        ulong AddJson(string json, WriteMode mode = WriteMode.Append, BadVectorHandling badVectorHandling = BadVectorHandling.Error, float fillValue = 0.0F);
+        Task<ulong> AddJsonAsync(string json, WriteMode mode = WriteMode.Append, BadVectorHandling badVectorHandling = BadVectorHandling.Error, float fillValue = 0.0F, CancellationToken token = default);
+
+        void BindEmbeddingColumn(string sourceColumn, string vectorColumn, string functionName);
+        Task BindEmbeddingColumnAsync(string sourceColumn, string vectorColumn, string functionName, CancellationToken token = default);
+
+        IEnumerable<RecordBatch> BatchVectorSearch(IEnumerable<float[]> vectors, int limit = 10, string? vectorColumn = null, Metric metric = Metric.L2, int concurrency = 0);
+        Task<IEnumerable<RecordBatch>> BatchVectorSearchAsync(IEnumerable<float[]> vectors, int limit = 10, string? vectorColumn = null, Metric metric = Metric.L2, int concurrency = 0, CancellationToken token = default);
     }
 }
@@ -7,9 +7,11 @@ public partial class MergeInsertBuilder : ILanceMergeInsertBuilder
     private readonly long _connectionId;
     private readonly long _tableId;
     private readonly List<string> _columns;
+    private bool _whenMatchedUpdateAll;
     private string? _where;
     private bool _whenNotMatchedInsertAll;
-    private string? _whenNotMatchedBySourceDelete;
+    private bool _whenNotMatchedBySourceDelete;
+    private string? _whenNotMatchedBySourceDeleteFilter;
     private readonly Schema _schema;
 
     internal MergeInsertBuilder(long connectionId, long tableId, IEnumerable<string> columns, Schema schema)
@@ -27,6 +29,7 @@ public partial class MergeInsertBuilder : ILanceMergeInsertBuilder
     /// <returns>The MergeInsert builder to continue building.</returns>
     public ILanceMergeInsertBuilder WhenMatchedUpdateAll(string? where = null)
     {
+        _whenMatchedUpdateAll = true;
         _where = where;
         return this;
     }
@@ -48,7 +51,8 @@ public partial class MergeInsertBuilder : ILanceMergeInsertBuilder
     /// <returns>The MergeInsert builder to continue building.</returns>
     public ILanceMergeInsertBuilder WhenNotMatchedBySourceDelete(string? condition = null)
     {
-        _whenNotMatchedBySourceDelete = condition;
+        _whenNotMatchedBySourceDelete = true;
+        _whenNotMatchedBySourceDeleteFilter = condition;
         return this;
     }
 
@@ -98,11 +102,17 @@ public partial class MergeInsertBuilder : ILanceMergeInsertBuilder
                     _tableId,
                     _columns.ToArray(),
                     (ulong)_columns.Count,
-                    _whenNotMatchedInsertAll,
+                    _whenMatchedUpdateAll,
                     _where,
+                    _whenNotMatchedInsertAll,
                     _whenNotMatchedBySourceDelete,
+                    _whenNotMatchedBySourceDeleteFilter,
                     p,
                     (ulong) batch.Length,
+                    (uint)BadVectorHandling.Error,
+                    0.0f,
+                    false,
+                    null,
                     (result, message) =>
                     {
                         if (result < 0)
@@ -52,8 +52,10 @@ public partial class MergeInsertBuilder
                     fixed (byte* p = batch)
                     {
                         Ffi.merge_insert_with_record_batch(
-                            _connectionId, _tableId, _columns.ToArray(), (ulong)_columns.Count, _whenNotMatchedInsertAll,
-                            _where, _whenNotMatchedBySourceDelete, p, (ulong)batch.Length, callback);
+                            _connectionId, _tableId, _columns.ToArray(), (ulong)_columns.Count,
+                            _whenMatchedUpdateAll, _where, _whenNotMatchedInsertAll,
+                            _whenNotMatchedBySourceDelete, _whenNotMatchedBySourceDeleteFilter, p, (ulong)batch.Length,
+                            (uint)BadVectorHandling.Error, 0.0f, false, null, callback);
                     }
                 }
             }
@@ -0,0 +1,10 @@
+﻿namespace LanceDbClient
+{
+    // Rerankers that fuse hybrid query results inside LanceDB
+    public enum NativeReranker
+    {
+        Rrf = 1,
+        LinearCombination = 2,
+        Host = 3,
+    }
+}
@@ -235,9 +235,39 @@ public partial class QueryBuilder : ILanceQueryBuilder
                 exception = new Exception("Failed to compact files: " + message);
             }
         }, LimitCount, WhereSql, WithRowIdent, selectColumns!, (ulong)SelectColumnsList.Count,
-            FullTextSearch, (uint)batchSize);
+            FullTextSearch, (uint)batchSize, Ffi.IpcFileFormat, 0, null, null, null, null, 0, 0, 0, null);
         
         if (exception != null) throw exception;
         return result;
     }
-}
\ No newline at end of file
+
+    /// <summary>
+    /// Start the query, returning a cursor that the results are read from one batch at a time.
+    /// </summary>
+    /// <param name="batchSize">The maximum number of rows in each batch, or 0 for the default.</param>
+    /// <returns>A cursor over the results, which must be disposed.</returns>
+    /// <exception cref="Exception">If the query fails to start</exception>
+    public virtual QueryCursor ToCursor(int batchSize = 0)
+    {
+        Exception? exception = null;
+        var cursorHandle = -1L;
+        string[]? selectColumns = null;
+        if (SelectColumnsList.Count > 0)
+        {
+            selectColumns = SelectColumnsList.ToArray();
+        }
+
+        Ffi.open_query(ConnectionId, TableId, (code, message) =>
+            {
+                if (code < 0 && message != null)
+                {
+                    exception = new Exception("Failed to open a cursor: " + message);
+                }
+                cursorHandle = code;
+            }, LimitCount, WhereSql, WithRowIdent, selectColumns, (ulong)SelectColumnsList.Count,
+            FullTextSearch, (uint)batchSize, Ffi.IpcFileFormat, 0, null, null, null, null, 0);
+
+        if (exception != null) throw exception;
+        return new QueryCursor(cursorHandle);
+    }
+}
@@ -62,7 +62,7 @@ public partial class QueryBuilder
 
                 Ffi.query(ConnectionId, TableId, blobCallback, resultCallback, LimitCount, WhereSql, WithRowIdent,
                     selectColumns!, (ulong)SelectColumnsList.Count,
-                    FullTextSearch, (uint)batchSize);
+                    FullTextSearch, (uint)batchSize, Ffi.IpcFileFormat, 0, null, null, null, null, 0, 0, 0, null);
                 channel.Writer.Complete();
             }
 
@@ -78,4 +78,8 @@ public partial class QueryBuilder
         }
     }
 
-}
\ No newline at end of file
+    public Task<QueryCursor> ToCursorAsync(int batchSize = 0, CancellationToken token = default)
+    {
+        return Task.Run(() => ToCursor(batchSize), token);
+    }
+}
@@ -0,0 +1,103 @@
+using System.Collections;
+using System.Runtime.InteropServices;
+using Apache.Arrow;
+
+namespace LanceDbClient;
+
+/// <summary>
+/// Query results read one batch at a time. The query only runs ahead of the reader by a few batches,
+/// so large results are never held in memory all at once. Dispose the cursor to stop the query.
+/// </summary>
+public sealed class QueryCursor : IEnumerable<RecordBatch>, IAsyncEnumerable<RecordBatch>, IDisposable
+{
+    private readonly long _cursorHandle;
+    private bool _closed;
+
+    internal QueryCursor(long cursorHandle)
+    {
+        _cursorHandle = cursorHandle;
+    }
+
+    ~QueryCursor()
+    {
+        Dispose(false);
+    }
+
+    /// <summary>
+    /// Read the next batch of results.
+    /// </summary>
+    /// <returns>The next batch, or null at the end of the results.</returns>
+    /// <exception cref="Exception">If the cursor is closed or the query fails</exception>
+    public unsafe RecordBatch? Next()
+    {
+        if (_closed) throw new Exception("Cursor is closed.");
+        Exception? exception = null;
+        RecordBatch? batch = null;
+        Ffi.next_batch(_cursorHandle, (bytes, len) =>
+        {
+            var batchBytes = new byte[len];
+            Marshal.Copy((IntPtr)bytes, batchBytes, 0, (int)len);
+            batch = Ffi.DeserializeRecordBatch(batchBytes);
+            return true;
+        }, (code, message) =>
+        {
+            if (code < 0 && message != null)
+            {
+                exception = new Exception("Failed to read from the cursor: " + message);
+            }
+        });
+        if (exception != null) throw exception;
+        return batch;
+    }
+
+    /// <summary>
+    /// Read the next batch of results without blocking the caller.
+    /// </summary>
+    /// <returns>The next batch, or null at the end of the results.</returns>
+    public Task<RecordBatch?> NextAsync(CancellationToken token = default)
+    {
+        return Task.Run(() => Next(), token);
+    }
+
+    public IEnumerator<RecordBatch> GetEnumerator()
+    {
+        while (Next() is { } batch)
+        {
+            yield return batch;
+        }
+    }
+
+    IEnumerator IEnumerable.GetEnumerator()
+    {
+        return GetEnumerator();
+    }
+
+    public async IAsyncEnumerator<RecordBatch> GetAsyncEnumerator(CancellationToken token = default)
+    {
+        while (await NextAsync(token) is { } batch)
+        {
+            yield return batch;
+        }
+    }
+
+    public void Dispose()
+    {
+        Dispose(true);
+        GC.SuppressFinalize(this);
+    }
+
+    private void Dispose(bool disposing)
+    {
+        if (_closed) return;
+        _closed = true;
+        Exception? exception = null;
+        Ffi.close_cursor(_cursorHandle, (code, message) =>
+        {
+            if (code < 0 && message != null)
+            {
+                exception = new Exception("Failed to close the cursor: " + message);
+            }
+        });
+        if (disposing && exception != null) throw exception;
+    }
+}
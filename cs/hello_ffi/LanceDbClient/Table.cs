@@ -1,3 +1,4 @@
+using System.Runtime.InteropServices;
 using Apache.Arrow;
 using MathNet.Numerics.LinearAlgebra;
 using Array = Apache.Arrow.Array;
@@ -28,6 +29,9 @@ public sealed partial class Table : ITable
 
     private readonly long _tableHandle;
     private readonly long _connectionHandle;
+    // LanceDB calls these after the call that set them has returned, so they must not be collected
+    private Ffi.RetryCallback? _retryCallback;
+    private Ffi.DurabilityCallback? _durabilityCallback;
     
     ~Table()
     {
@@ -176,18 +180,20 @@ public sealed partial class Table : ITable
         if (!IsOpen) throw new Exception("Table is not open.");
         Exception? exception = null;
 
-        var updateList = new List<string>();
+        var columns = new List<string>();
+        var expressions = new List<string>();
         foreach (var (key, value) in updates)
         {
+            columns.Add(key);
             if (value is string s)
             {
                 // SQL Sanitizing
                 s = s.Replace("'", "''");
-                updateList.Add(key + "='" + s + "'");
+                expressions.Add("'" + s + "'");
             }
             else
             {
-                updateList.Add(key + "=" + value);
+                expressions.Add(value.ToString() ?? "NULL");
             }
         }
 
@@ -196,8 +202,9 @@ public sealed partial class Table : ITable
         Ffi.update_rows(
             _connectionHandle,
             _tableHandle,
-            updateList.ToArray(),
-            (ulong)updateList.Count,
+            columns.ToArray(),
+            expressions.ToArray(),
+            (ulong)columns.Count,
             whereClause,
             (code, message) =>
             {
@@ -228,12 +235,14 @@ public sealed partial class Table : ITable
         if (!IsOpen) throw new Exception("Table is not open.");
         Exception? exception = null;
 
-        var updateList = new List<string>();
+        var columns = new List<string>();
+        var expressions = new List<string>();
         foreach (var (key, value) in updates)
         {
             // In this case they are all guaranteed to be strings - because full SQL statements
             // with escaping already baked in are expected.
-            updateList.Add(key + "=" + value);
+            columns.Add(key);
+            expressions.Add(value);
         }
 
         var rowsUpdated = 0ul;
@@ -241,8 +250,9 @@ public sealed partial class Table : ITable
         Ffi.update_rows(
             _connectionHandle,
             _tableHandle,
-            updateList.ToArray(),
-            (ulong)updateList.Count,
+            columns.ToArray(),
+            expressions.ToArray(),
+            (ulong)columns.Count,
             whereClause,
             (code, message) =>
             {
@@ -261,23 +271,106 @@ public sealed partial class Table : ITable
         return rowsUpdated;
     }
     
+    /// <summary>
+    /// Update rows in a table with a full SQL UPDATE statement, such as
+    /// <c>UPDATE items SET price = price * 2 WHERE id &lt; 10</c>.
+    /// </summary>
+    /// <param name="sql">The statement. It must name this table.</param>
+    /// <returns>The number of records that were updated</returns>
+    /// <exception cref="Exception"></exception>
+    public ulong UpdateSql(string sql)
+    {
+        if (!IsOpen) throw new Exception("Table is not open.");
+        Exception? exception = null;
+        var rowsUpdated = 0ul;
+
+        Ffi.update_sql(
+            _connectionHandle,
+            _tableHandle,
+            sql,
+            (code, message) =>
+            {
+                if (code < 0 && message != null)
+                {
+                    exception = new Exception("Failed to update rows: " + message);
+                }
+            },
+            (rows =>
+            {
+                rowsUpdated += rows;
+            })
+        );
+        if (exception != null) throw exception;
+
+        return rowsUpdated;
+    }
+    
     /// <summary>
     /// Deletes rows from the table.
     /// </summary>
     /// <param name="whereClause">SQL-like query to specify which rows should be deleted.</param>
+    /// <returns>The number of rows deleted</returns>
+    /// <exception cref="Exception">If deletion fails</exception>
+    public ulong Delete(string whereClause)
+    {
+        return DeleteRows(whereClause, false, false, 0, null);
+    }
+
+    /// <summary>
+    /// Deletes every row in the table.
+    /// </summary>
+    /// <returns>The number of rows deleted</returns>
     /// <exception cref="Exception">If deletion fails</exception>
-    public void Delete(string whereClause)
+    public ulong DeleteAll()
+    {
+        return DeleteRows(null, true, false, 0, null);
+    }
+
+    /// <summary>
+    /// Counts the rows a delete would remove, without deleting anything.
+    /// </summary>
+    /// <param name="whereClause">The filter the delete would use, or null for every row.</param>
+    /// <param name="sampleLimit">How many of the matching rows to return.</param>
+    /// <returns>The number of matching rows, and a sample of them</returns>
+    /// <exception cref="Exception">If the preview fails</exception>
+    public DeletePreview PreviewDelete(string? whereClause, ulong sampleLimit = 10)
+    {
+        var preview = new DeletePreview();
+        preview.MatchingRows = DeleteRows(whereClause, whereClause == null, true, sampleLimit, preview.Sample);
+        return preview;
+    }
+
+    private unsafe ulong DeleteRows(string? whereClause, bool deleteAll, bool dryRun, ulong sampleLimit,
+        List<RecordBatch>? sample)
     {
         if (!IsOpen) throw new Exception("Table is not open.");
         Exception? exception = null;
-        Ffi.delete_rows(_connectionHandle, _tableHandle, whereClause, (code, message) =>
+        var rows = 0ul;
+        Ffi.BlobCallback? sampleCallback = null;
+        if (sample != null)
+        {
+            sampleCallback = (bytes, len) =>
+            {
+                var batchBytes = new byte[len];
+                Marshal.Copy((IntPtr)bytes, batchBytes, 0, (int)len);
+                sample.Add(Ffi.DeserializeRecordBatch(batchBytes));
+                return true;
+            };
+        }
+        Ffi.delete_rows(_connectionHandle, _tableHandle, whereClause, deleteAll, dryRun, sampleLimit, sampleCallback,
+            Ffi.IpcFileFormat, (code, message) =>
         {
             if (code < 0 && message != null)
             {
                 exception = new Exception("Failed to delete rows: " + message);
             }
+            else
+            {
+                rows = (ulong)code;
+            }
         });
         if (exception != null) throw exception;
+        return rows;
     }
     
     /// <summary>
@@ -535,8 +628,8 @@ public sealed partial class Table : ITable
     /// </summary>
     /// <param name="data">The record batch to add.</param>
     /// <param name="mode">Append or overwrite</param>
-    /// <param name="badVectorHandling">Not implemented yet.</param>
-    /// <param name="fillValue">Not Implemented yet.</param>
+    /// <param name="badVectorHandling">What to do with vectors of the wrong size, or containing NaN/Inf.</param>
+    /// <param name="fillValue">The value to fill bad vectors with, if <c>badVectorHandling</c> is <c>Fill</c>.</param>
     /// <exception cref="ArgumentNullException">Data must be provided</exception>
     /// <exception cref="Exception">If the add fails.</exception>
     public unsafe void Add(IEnumerable<RecordBatch> data, WriteMode mode = WriteMode.Append,
@@ -551,7 +644,8 @@ public sealed partial class Table : ITable
             var batch = Ffi.SerializeRecordBatch(recordBatch);
             fixed (byte* p = batch)
             {
-                Ffi.add_record_batch(_connectionHandle, _tableHandle, p, (ulong)batch.Length, (uint)mode, (code, message) =>
+                Ffi.add_record_batch(_connectionHandle, _tableHandle, p, (ulong)batch.Length, (uint)mode, false,
+                    (uint)badVectorHandling, fillValue, (code, message) =>
                 {
                     if (code < 0 && message != null)
                     {
@@ -568,11 +662,213 @@ public sealed partial class Table : ITable
     /// </summary>
     /// <param name="data">The dat to add</param>
     /// <param name="mode">Write mode - append or overwrite</param>
-    /// <param name="badVectorHandling">Not implemented yet</param>
-    /// <param name="fillValue">Not implemented yet</param>
+    /// <param name="badVectorHandling">What to do with vectors of the wrong size, or containing NaN/Inf.</param>
+    /// <param name="fillValue">The value to fill bad vectors with, if <c>badVectorHandling</c> is <c>Fill</c>.</param>
     public void Add(Apache.Arrow.Table data, WriteMode mode = WriteMode.Append, BadVectorHandling badVectorHandling = BadVectorHandling.Error,
         float fillValue = 0)
     {
         Add(ArrayHelpers.ArrowTableToRecordBatch(data), mode, badVectorHandling, fillValue);
     }
-}
\ No newline at end of file
+    
+    /// <summary>
+    /// Retry this table's writes when they fail because another writer committed first.
+    /// </summary>
+    /// <param name="maxAttempts">Attempts in all, including the first. 0 or 1 removes the policy.</param>
+    /// <param name="initialBackoff">The wait before the first retry (100ms if null), doubled for each retry after it.</param>
+    /// <param name="maxBackoff">The longest wait between attempts (10 seconds if null).</param>
+    /// <param name="jitter">The share of each wait, from 0 to 1, that is randomized.</param>
+    /// <param name="onRetry">Called before each retry with the attempt that failed, the wait and the conflict's error.</param>
+    /// <exception cref="Exception">If the table is not open or the policy is invalid</exception>
+    public void SetRetryPolicy(int maxAttempts, TimeSpan? initialBackoff = null, TimeSpan? maxBackoff = null,
+        double jitter = 0.0, Action<int, TimeSpan, string>? onRetry = null)
+    {
+        if (!IsOpen) throw new Exception("Table is not open.");
+        Ffi.RetryCallback? retryCallback = null;
+        if (onRetry != null)
+        {
+            retryCallback = (_, attempt, delayMs, error) =>
+                onRetry((int)attempt, TimeSpan.FromMilliseconds(delayMs), error);
+        }
+        Exception? exception = null;
+        Ffi.set_retry_policy(_connectionHandle, _tableHandle, (uint)maxAttempts,
+            (ulong)(initialBackoff ?? TimeSpan.FromMilliseconds(100)).TotalMilliseconds,
+            (ulong)(maxBackoff ?? TimeSpan.FromSeconds(10)).TotalMilliseconds, jitter, retryCallback, (code, message) =>
+        {
+            if (code < 0 && message != null)
+            {
+                exception = new Exception("Failed to set the retry policy: " + message);
+            }
+        });
+        if (exception != null) throw exception;
+        _retryCallback = retryCallback;
+    }
+    
+    /// <summary>
+    /// Buffer appends to the table, so that many small adds are committed together. Buffered rows are
+    /// committed when a limit is reached, or when <c>FlushWriteBuffer</c> is called.
+    /// </summary>
+    /// <param name="maxRows">Flush once this many rows are buffered, or 0 for no limit.</param>
+    /// <param name="maxBytes">Flush once this many bytes are buffered, or 0 for no limit.</param>
+    /// <param name="flushInterval">Flush this often, or null for no limit. At least one limit must be set.</param>
+    /// <param name="onFlush">Called after each flush with the number of rows flushed, and the error if they
+    /// couldn't be committed (null if they were).</param>
+    /// <exception cref="Exception">If the table is not open or no limit is set</exception>
+    public void EnableWriteBuffer(ulong maxRows = 0, ulong maxBytes = 0, TimeSpan? flushInterval = null,
+        Action<ulong, string?>? onFlush = null)
+    {
+        if (!IsOpen) throw new Exception("Table is not open.");
+        Ffi.DurabilityCallback? durabilityCallback = null;
+        if (onFlush != null)
+        {
+            durabilityCallback = (_, rows, error) => onFlush(rows, error);
+        }
+        Exception? exception = null;
+        Ffi.enable_write_buffer(_connectionHandle, _tableHandle, maxRows, maxBytes,
+            (ulong)(flushInterval?.TotalMilliseconds ?? 0), durabilityCallback, (code, message) =>
+        {
+            if (code < 0 && message != null)
+            {
+                exception = new Exception("Failed to enable the write buffer: " + message);
+            }
+        });
+        if (exception != null) throw exception;
+        _durabilityCallback = durabilityCallback;
+    }
+    
+    /// <summary>
+    /// Commit everything in the table's write buffer now.
+    /// </summary>
+    /// <returns>The number of rows committed</returns>
+    /// <exception cref="Exception">If the table is not open or the rows couldn't be committed</exception>
+    public ulong FlushWriteBuffer()
+    {
+        if (!IsOpen) throw new Exception("Table is not open.");
+        Exception? exception = null;
+        var rows = 0ul;
+        Ffi.flush_write_buffer(_connectionHandle, _tableHandle, (code, message) =>
+        {
+            if (code < 0 && message != null)
+            {
+                exception = new Exception("Failed to flush the write buffer: " + message);
+            }
+            else
+            {
+                rows = (ulong)code;
+            }
+        });
+        if (exception != null) throw exception;
+        return rows;
+    }
+    
+    /// <summary>
+    /// Flush the table's write buffer and stop buffering appends.
+    /// </summary>
+    /// <exception cref="Exception">If the table is not open or the buffered rows couldn't be committed</exception>
+    public void DisableWriteBuffer()
+    {
+        if (!IsOpen) throw new Exception("Table is not open.");
+        Exception? exception = null;
+        Ffi.disable_write_buffer(_connectionHandle, _tableHandle, (code, message) =>
+        {
+            if (code < 0 && message != null)
+            {
+                exception = new Exception("Failed to disable the write buffer: " + message);
+            }
+        });
+        if (exception != null) throw exception;
+    }
+    
+    /// <summary>
+    /// Adds rows given as JSON: an array of objects, or one object per line. Values are converted
+    /// using the table's schema.
+    /// </summary>
+    /// <param name="json">The rows to add</param>
+    /// <param name="mode">Write mode - append or overwrite</param>
+    /// <param name="badVectorHandling">What to do with vectors of the wrong size, or containing NaN/Inf.</param>
+    /// <param name="fillValue">The value to fill bad vectors with, if <c>badVectorHandling</c> is <c>Fill</c>.</param>
+    /// <returns>The number of rows with bad vectors that were dropped or replaced</returns>
+    /// <exception cref="Exception">If the table is not open or a row can't be converted</exception>
+    public ulong AddJson(string json, WriteMode mode = WriteMode.Append,
+        BadVectorHandling badVectorHandling = BadVectorHandling.Error, float fillValue = 0)
+    {
+        if (!IsOpen) throw new Exception("Table is not open.");
+        Exception? exception = null;
+        var badVectors = 0ul;
+        Ffi.add_json_rows(_connectionHandle, _tableHandle, json, (uint)mode, (uint)badVectorHandling, fillValue,
+            (code, message) =>
+        {
+            if (code < 0 && message != null)
+            {
+                exception = new Exception("Failed to add JSON rows: " + message);
+            }
+            else
+            {
+                badVectors = (ulong)code;
+            }
+        });
+        if (exception != null) throw exception;
+        return badVectors;
+    }
+    
+    /// <summary>
+    /// Compute a vector column from a text column, with an embedding function registered through
+    /// <c>HostFunctions.RegisterEmbeddingFunction</c>. Adds that leave out the vector column have it filled in.
+    /// </summary>
+    /// <param name="sourceColumn">The text column</param>
+    /// <param name="vectorColumn">The vector column</param>
+    /// <param name="functionName">The name the embedding function was registered under</param>
+    /// <exception cref="Exception">If the table is not open or the columns don't fit the function</exception>
+    public void BindEmbeddingColumn(string sourceColumn, string vectorColumn, string functionName)
+    {
+        if (!IsOpen) throw new Exception("Table is not open.");
+        Exception? exception = null;
+        Ffi.bind_embedding_column(_connectionHandle, _tableHandle, sourceColumn, vectorColumn, functionName,
+            (code, message) =>
+        {
+            if (code < 0 && message != null)
+            {
+                exception = new Exception("Failed to bind the embedding column: " + message);
+            }
+        });
+        if (exception != null) throw exception;
+    }
+    
+    /// <summary>
+    /// Run a separate vector search for each query vector, in one call. Each result row has a
+    /// <c>query_index</c> column, as its first column, giving the position of the vector it matched.
+    /// </summary>
+    /// <param name="vectors">The query vectors, which must all have the same length.</param>
+    /// <param name="limit">The maximum number of results for each vector.</param>
+    /// <param name="vectorColumn">The column to search, or null for the one LanceDB picks.</param>
+    /// <param name="metric">The distance metric</param>
+    /// <param name="concurrency">How many searches run at once, or 0 for the default.</param>
+    /// <returns>The results, in query order</returns>
+    /// <exception cref="Exception">If the table is not open or a search fails</exception>
+    public unsafe IEnumerable<RecordBatch> BatchVectorSearch(IEnumerable<float[]> vectors, int limit = 10,
+        string? vectorColumn = null, Metric metric = Metric.L2, int concurrency = 0)
+    {
+        if (!IsOpen) throw new Exception("Table is not open.");
+        var queries = Ffi.SerializeRecordBatch(ArrayHelpers.VectorsToRecordBatch(vectors.ToList()));
+        var result = new List<RecordBatch>();
+        Exception? exception = null;
+        fixed (byte* p = queries)
+        {
+            Ffi.batch_vector_query(_connectionHandle, _tableHandle, (bytes, len) =>
+                {
+                    var batchBytes = new byte[len];
+                    Marshal.Copy((IntPtr)bytes, batchBytes, 0, (int)len);
+                    result.Add(Ffi.DeserializeRecordBatch(batchBytes));
+                    return true;
+                }, (code, message) =>
+                {
+                    if (code < 0 && message != null)
+                    {
+                        exception = new Exception("Failed to run the batch vector query: " + message);
+                    }
+                }, p, (ulong)queries.Length, (ulong)limit, null, false, null, 0, vectorColumn, (uint)metric,
+                0, 0, (uint)concurrency, 0, Ffi.IpcFileFormat);
+        }
+        if (exception != null) throw exception;
+        return result;
+    }
+}
@@ -1,3 +1,4 @@
+using System.Runtime.InteropServices;
 using Apache.Arrow;
 using MathNet.Numerics.LinearAlgebra;
 using Array = Apache.Arrow.Array;
@@ -104,18 +105,20 @@ public sealed partial class Table
 
     public Task<ulong> UpdateAsync(IDictionary<string, object> updates, string? whereClause = null, CancellationToken token = default)
     {
-        var updateList = new List<string>();
+        var columns = new List<string>();
+        var expressions = new List<string>();
         foreach (var (key, value) in updates)
         {
+            columns.Add(key);
             if (value is string s)
             {
                 // SQL Sanitizing
                 s = s.Replace("'", "''");
-                updateList.Add(key + "='" + s + "'");
+                expressions.Add("'" + s + "'");
             }
             else
             {
-                updateList.Add(key + "=" + value);
+                expressions.Add(value.ToString() ?? "NULL");
             }
         }
         
@@ -141,7 +144,7 @@ public sealed partial class Table
         
         Task.Run(() =>
         {
-            Ffi.update_rows(_connectionHandle, _tableHandle, updateList.ToArray(), (ulong)updateList.Count, whereClause,
+            Ffi.update_rows(_connectionHandle, _tableHandle, columns.ToArray(), expressions.ToArray(), (ulong)columns.Count, whereClause,
                 resultCallback, countCallback);
         }, token);
         
@@ -150,12 +153,14 @@ public sealed partial class Table
 
     public Task<ulong> UpdateSqlAsync(IDictionary<string, string> updates, string? whereClause = null, CancellationToken token = default)
     {
-        var updateList = new List<string>();
+        var columns = new List<string>();
+        var expressions = new List<string>();
         foreach (var (key, value) in updates)
         {
             // In this case they are all guaranteed to be strings - because full SQL statements
             // with escaping already baked in are expected.
-            updateList.Add(key + "=" + value);
+            columns.Add(key);
+            expressions.Add(value);
         }
         
         var tcs = new TaskCompletionSource<ulong>();
@@ -180,16 +185,66 @@ public sealed partial class Table
 
         Task.Run(() =>
         {
-            Ffi.update_rows(_connectionHandle, _tableHandle, updateList.ToArray(), (ulong)updateList.Count,
+            Ffi.update_rows(_connectionHandle, _tableHandle, columns.ToArray(), expressions.ToArray(), (ulong)columns.Count,
                 whereClause, resultCallback, countCallback);
         });
         
         return tcs.Task;
     }
 
-    public Task DeleteAsync(string whereClause, CancellationToken token = default)
+    public Task<ulong> UpdateSqlAsync(string sql, CancellationToken token = default)
     {
-        var tcs = new TaskCompletionSource();
+        var tcs = new TaskCompletionSource<ulong>();
+        
+        var rowsUpdated = 0ul;
+        var countCallback = new Ffi.UpdateCalback((count) =>
+        {
+            rowsUpdated = count;
+        });
+        
+        var resultCallback = new Ffi.ResultCallback((code, message) =>
+        {
+            if (code < 0)
+            {
+                tcs.SetException(new Exception(message));
+            }
+            else
+            {
+                tcs.SetResult(rowsUpdated);
+            }
+        });
+
+        Task.Run(() =>
+        {
+            Ffi.update_sql(_connectionHandle, _tableHandle, sql, resultCallback, countCallback);
+        }, token);
+        
+        return tcs.Task;
+    }
+
+    public Task<ulong> DeleteAsync(string whereClause, CancellationToken token = default)
+    {
+        return DeleteRowsAsync(whereClause, false, false, 0, null, token);
+    }
+
+    public Task<ulong> DeleteAllAsync(CancellationToken token = default)
+    {
+        return DeleteRowsAsync(null, true, false, 0, null, token);
+    }
+
+    public async Task<DeletePreview> PreviewDeleteAsync(string? whereClause, ulong sampleLimit = 10,
+        CancellationToken token = default)
+    {
+        var preview = new DeletePreview();
+        preview.MatchingRows = await DeleteRowsAsync(whereClause, whereClause == null, true, sampleLimit,
+            preview.Sample, token);
+        return preview;
+    }
+
+    private Task<ulong> DeleteRowsAsync(string? whereClause, bool deleteAll, bool dryRun, ulong sampleLimit,
+        List<RecordBatch>? sample, CancellationToken token)
+    {
+        var tcs = new TaskCompletionSource<ulong>();
         Ffi.ResultCallback callback = (code, message) =>
         {
             if (code < 0)
@@ -198,12 +253,27 @@ public sealed partial class Table
             }
             else
             {
-                tcs.SetResult();
+                tcs.SetResult((ulong)code);
             }
         };
         Task.Run(() =>
         {
-            Ffi.delete_rows(_connectionHandle, _tableHandle, whereClause, callback);
+            unsafe
+            {
+                Ffi.BlobCallback? sampleCallback = null;
+                if (sample != null)
+                {
+                    sampleCallback = (bytes, len) =>
+                    {
+                        var batchBytes = new byte[len];
+                        Marshal.Copy((IntPtr)bytes, batchBytes, 0, (int)len);
+                        sample.Add(Ffi.DeserializeRecordBatch(batchBytes));
+                        return true;
+                    };
+                }
+                Ffi.delete_rows(_connectionHandle, _tableHandle, whereClause, deleteAll, dryRun, sampleLimit,
+                    sampleCallback, Ffi.IpcFileFormat, callback);
+            }
         }, token);
         return tcs.Task;
     }
@@ -368,7 +438,8 @@ public sealed partial class Table
                     var batch = Ffi.SerializeRecordBatch(recordBatch);
                     fixed (byte* p = batch)
                     {
-                        Ffi.add_record_batch(_connectionHandle, _tableHandle, p, (ulong)batch.Length, (uint)mode,
+                        Ffi.add_record_batch(_connectionHandle, _tableHandle, p, (ulong)batch.Length, (uint)mode, false,
+                            (uint)badVectorHandling, fillValue,
                             (code, message) =>
                             {
                                 if (code < 0)
@@ -392,4 +463,44 @@ public sealed partial class Table
         return AddAsync(ArrayHelpers.ArrowTableToRecordBatch(data), mode, badVectorHandling, fillValue, token);
     }
 
-}
\ No newline at end of file
+    public Task SetRetryPolicyAsync(int maxAttempts, TimeSpan? initialBackoff = null, TimeSpan? maxBackoff = null,
+        double jitter = 0.0, Action<int, TimeSpan, string>? onRetry = null, CancellationToken token = default)
+    {
+        return Task.Run(() => SetRetryPolicy(maxAttempts, initialBackoff, maxBackoff, jitter, onRetry), token);
+    }
+
+    public Task EnableWriteBufferAsync(ulong maxRows = 0, ulong maxBytes = 0, TimeSpan? flushInterval = null,
+        Action<ulong, string?>? onFlush = null, CancellationToken token = default)
+    {
+        return Task.Run(() => EnableWriteBuffer(maxRows, maxBytes, flushInterval, onFlush), token);
+    }
+
+    public Task<ulong> FlushWriteBufferAsync(CancellationToken token = default)
+    {
+        return Task.Run(() => FlushWriteBuffer(), token);
+    }
+
+    public Task DisableWriteBufferAsync(CancellationToken token = default)
+    {
+        return Task.Run(() => DisableWriteBuffer(), token);
+    }
+
+    public Task<ulong> AddJsonAsync(string json, WriteMode mode = WriteMode.Append,
+        BadVectorHandling badVectorHandling = BadVectorHandling.Error, float fillValue = 0,
+        CancellationToken token = default)
+    {
+        return Task.Run(() => AddJson(json, mode, badVectorHandling, fillValue), token);
+    }
+
+    public Task BindEmbeddingColumnAsync(string sourceColumn, string vectorColumn, string functionName,
+        CancellationToken token = default)
+    {
+        return Task.Run(() => BindEmbeddingColumn(sourceColumn, vectorColumn, functionName), token);
+    }
+
+    public Task<IEnumerable<RecordBatch>> BatchVectorSearchAsync(IEnumerable<float[]> vectors, int limit = 10,
+        string? vectorColumn = null, Metric metric = Metric.L2, int concurrency = 0, CancellationToken token = default)
+    {
+        return Task.Run(() => BatchVectorSearch(vectors, limit, vectorColumn, metric, concurrency), token);
+    }
+}
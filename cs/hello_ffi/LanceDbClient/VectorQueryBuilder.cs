@@ -117,7 +117,9 @@ public class VectorQueryBuilder : QueryBuilder, ILanceVectorQueryBuilder
                 (ulong)NumProbes,
                 (uint)RefinementFactor,
                 _minDistance,
-                _maxDistance
+                _maxDistance,
+                false,
+                false
             );
         }
 
@@ -167,13 +169,52 @@ public class VectorQueryBuilder : QueryBuilder, ILanceVectorQueryBuilder
                 }, LimitCount, WhereSql, WithRowIdent, selectColumns!, (ulong)SelectColumnsList.Count,
                 (uint)VectorData.DataType, b, (ulong)VectorData.Data.Length, VectorData.Length,
                 (uint)DistanceMetric, (ulong)NumProbes, (uint)RefinementFactor, (uint)batchSize,
-                _minDistance, _maxDistance);
+                _minDistance, _maxDistance, Ffi.IpcFileFormat, 0, null, null, null, null, 0, false, false, 0, 0, null);
         }
 
         if (exception != null) throw exception;
         return result;
     }
 
+    /// <summary>
+    /// Start the query, returning a cursor that the results are read from one batch at a time.
+    /// </summary>
+    /// <param name="batchSize">The maximum number of rows in each batch, or 0 for the default.</param>
+    /// <returns>A cursor over the results, which must be disposed.</returns>
+    /// <exception cref="Exception">If the query fails to start</exception>
+    public override unsafe QueryCursor ToCursor(int batchSize = 0)
+    {
+        if (VectorData == null)
+        {
+            throw new Exception("VectorData must be set before calling ToCursor");
+        }
+        Exception? exception = null;
+        var cursorHandle = -1L;
+        string[]? selectColumns = null;
+        if (SelectColumnsList.Count > 0)
+        {
+            selectColumns = SelectColumnsList.ToArray();
+        }
+
+        fixed (byte* b = VectorData.Data)
+        {
+            Ffi.open_vector_query(ConnectionId, TableId, (code, message) =>
+                {
+                    if (code < 0 && message != null)
+                    {
+                        exception = new Exception("Failed to open a cursor: " + message);
+                    }
+                    cursorHandle = code;
+                }, LimitCount, WhereSql, WithRowIdent, selectColumns, (ulong)SelectColumnsList.Count,
+                (uint)VectorData.DataType, b, (ulong)VectorData.Data.Length, VectorData.Length,
+                (uint)DistanceMetric, (ulong)NumProbes, (uint)RefinementFactor, (uint)batchSize,
+                _minDistance, _maxDistance, Ffi.IpcFileFormat, 0, null, null, null, null, 0, false, false);
+        }
+
+        if (exception != null) throw exception;
+        return new QueryCursor(cursorHandle);
+    }
+
     public override async IAsyncEnumerable<RecordBatch> ToBatchesAsync(int batchSize, [EnumeratorCancellation] CancellationToken token = default)
     {
         if (VectorData == null)
@@ -220,7 +261,7 @@ public class VectorQueryBuilder : QueryBuilder, ILanceVectorQueryBuilder
                         LimitCount, WhereSql, WithRowIdent, selectColumns!, (ulong)SelectColumnsList.Count,
                         (uint)VectorData.DataType, b, (ulong)VectorData.Data.Length, VectorData.Length,
                         (uint)DistanceMetric, (ulong)NumProbes, (uint)RefinementFactor, (uint)batchSize,
-                        _minDistance, _maxDistance);
+                        _minDistance, _maxDistance, Ffi.IpcFileFormat, 0, null, null, null, null, 0, false, false, 0, 0, null);
                     channel.Writer.Complete();
                 }
             }
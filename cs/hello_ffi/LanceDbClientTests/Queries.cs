@@ -1600,4 +1600,93 @@ public partial class Tests
 
         Assert.Pass();
     }
+    
+    [Test]
+    public void CursorQuery()
+    {
+        var uri = new Uri("file:///tmp/test_open_table_cursor_query");
+        try
+        {
+            using (var cnn = new Connection(uri))
+            {
+                var table = cnn.CreateTable("table1", Helpers.GetSchema());
+                var recordBatch = Helpers.CreateSampleRecordBatch(
+                    Helpers.GetSchema(), 8, 128
+                );
+                table.Add(new List<RecordBatch> { recordBatch });
+
+                using var cursor = table.Search().ToCursor(3);
+                var batches = cursor.ToList();
+                Assert.Multiple(() =>
+                {
+                    Assert.That(batches.Sum(batch => batch.Length), Is.EqualTo(8));
+                    Assert.That(batches.All(batch => batch.Length <= 3), Is.True);
+                    Assert.That(cursor.Next(), Is.Null);
+                });
+            }
+        }
+        finally
+        {
+            Cleanup(uri);
+        }
+    }
+    
+    [Test]
+    public async Task CursorQueryAsync()
+    {
+        var uri = new Uri("file:///tmp/test_open_table_cursor_query_async");
+        try
+        {
+            using (var cnn = new Connection(uri))
+            {
+                var table = await cnn.CreateTableAsync("table1", Helpers.GetSchema());
+                var recordBatch = Helpers.CreateSampleRecordBatch(
+                    Helpers.GetSchema(), 8, 128
+                );
+                await table.AddAsync(new List<RecordBatch> { recordBatch });
+
+                using var cursor = await table.Search().ToCursorAsync(3);
+                var rows = 0;
+                await foreach (var batch in cursor)
+                {
+                    rows += batch.Length;
+                }
+                Assert.That(rows, Is.EqualTo(8));
+            }
+        }
+        finally
+        {
+            Cleanup(uri);
+        }
+    }
+    
+    [Test]
+    public void CursorVectorQueryClosedEarly()
+    {
+        var uri = new Uri("file:///tmp/test_open_table_cursor_vector_query");
+        try
+        {
+            using (var cnn = new Connection(uri))
+            {
+                var table = cnn.CreateTable("table1", Helpers.GetSchema());
+                var recordBatch = Helpers.CreateSampleRecordBatch(
+                    Helpers.GetSchema(), 8, 128
+                );
+                table.Add(new List<RecordBatch> { recordBatch });
+
+                var vector = Enumerable.Repeat(1.0f, 128).ToList();
+                var cursor = table.Search(vector, "vector").Limit(8).ToCursor(2);
+                var first = cursor.Next();
+                Assert.That(first, Is.Not.Null);
+                Assert.That(first!.Length, Is.LessThanOrEqualTo(2));
+                cursor.Dispose();
+                Assert.Throws<Exception>(() => cursor.Next());
+                Assert.That(table.CountRows(), Is.EqualTo(8));
+            }
+        }
+        finally
+        {
+            Cleanup(uri);
+        }
+    }
 }
\ No newline at end of file
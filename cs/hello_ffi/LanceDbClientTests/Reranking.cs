@@ -165,4 +165,89 @@ public partial class Tests
 
         Assert.Pass();
     }
+    
+    [Test]
+    public void NativeRrfHybridQuery()
+    {
+        var uri = new Uri("file:///tmp/test_open_table_native_rrf");
+        try
+        {
+            using (var cnn = new Connection(uri))
+            {
+                var table = cnn.CreateTable("table1", Helpers.GetSchema());
+                var recordBatch = Helpers.CreateSampleRecordBatch(
+                    Helpers.GetSchema(), 8, 128
+                );
+                table.Add(new List<RecordBatch> { recordBatch });
+                table.CreateFtsIndex(["id"], ["id"]);
+
+                var vector = Enumerable.Repeat(1.0f, 128).ToList();
+                var query = (ILanceHybridQueryBuilder)table.Search(vector, "vector", queryType: QueryType.Hybrid);
+                var batches = query
+                    .RerankNative()
+                    .SelectColumns(["id", "vector"])
+                    .Text("'1'")
+                    .Limit(5)
+                    .ToBatches(0)
+                    .ToList();
+                Assert.Multiple(() =>
+                {
+                    Assert.That(batches.Sum(batch => batch.Length), Is.EqualTo(5));
+                    Assert.That(batches.First().Schema.GetFieldIndex("_relevance_score"), Is.GreaterThanOrEqualTo(0));
+                });
+            }
+        }
+        finally
+        {
+            Cleanup(uri);
+        }
+    }
+    
+    [Test]
+    public async Task HostRerankerHybridQueryAsync()
+    {
+        var uri = new Uri("file:///tmp/test_open_table_host_reranker");
+        try
+        {
+            using (var cnn = new Connection(uri))
+            {
+                var table = await cnn.CreateTableAsync("table1", Helpers.GetSchema());
+                var recordBatch = Helpers.CreateSampleRecordBatch(
+                    Helpers.GetSchema(), 8, 128
+                );
+                await table.AddAsync(new List<RecordBatch> { recordBatch });
+                await table.CreateFtsIndexAsync(["id"], ["id"]);
+
+                // Rank rows by their id, highest first
+                HostFunctions.RegisterReranker("by_id", (_, candidates) =>
+                {
+                    var candidateIds = (StringArray)candidates.Column("id");
+                    return Enumerable.Range(0, candidateIds.Length).Select(i => float.Parse(candidateIds.GetString(i))).ToArray();
+                });
+
+                var vector = Enumerable.Repeat(1.0f, 128).ToList();
+                var query = (ILanceHybridQueryBuilder)table.Search(vector, "vector", queryType: QueryType.Hybrid);
+                var ids = new List<int>();
+                await foreach (var batch in query
+                                   .RerankNative(NativeReranker.Host, hostRerankerName: "by_id")
+                                   .SelectColumns(["id"])
+                                   .Text("'1'")
+                                   .Limit(8)
+                                   .ToBatchesAsync(0))
+                {
+                    var column = (StringArray)batch.Column("id");
+                    for (var i = 0; i < column.Length; i++)
+                    {
+                        ids.Add(int.Parse(column.GetString(i)));
+                    }
+                }
+                Assert.That(ids, Is.Not.Empty);
+                Assert.That(ids, Is.Ordered.Descending);
+            }
+        }
+        finally
+        {
+            Cleanup(uri);
+        }
+    }
 }
\ No newline at end of file
@@ -407,7 +407,7 @@ public partial class Tests
                 array.Add(recordBatch);
                 table.Add(array);
                 Assert.That(table.CountRows("id = '0'"), Is.EqualTo(1));
-                table.Delete("id = '0'");
+                Assert.That(table.Delete("id = '0'"), Is.EqualTo(1));
                 Assert.That(table.CountRows("id = '0'"), Is.EqualTo(0));
             }
         }
@@ -436,7 +436,7 @@ public partial class Tests
                 array.Add(recordBatch);
                 await table.AddAsync(array);
                 Assert.That(await table.CountRowsAsync("id = '0'"), Is.EqualTo(1));
-                await table.DeleteAsync("id = '0'");
+                Assert.That(await table.DeleteAsync("id = '0'"), Is.EqualTo(1));
                 Assert.That(await table.CountRowsAsync("id = '0'"), Is.EqualTo(0));
             }
         }
@@ -504,6 +504,103 @@ public partial class Tests
         }
     }
     
+    [Test]
+    public void DeleteAllRows()
+    {
+        var uri = new Uri("file:///tmp/test_table_deleterows_all");
+        try
+        {
+            using (var cnn = new Connection(uri))
+            {
+                var table = cnn.CreateTable("table1", Helpers.GetSchema());
+                var recordBatch = Helpers.CreateSampleRecordBatch(
+                    Helpers.GetSchema(), 8, 128
+                );
+                table.Add(new List<RecordBatch> { recordBatch });
+                Assert.That(table.DeleteAll(), Is.EqualTo(8));
+                Assert.That(table.CountRows(), Is.EqualTo(0));
+            }
+        }
+        finally
+        {
+            Cleanup(uri);
+        }
+    }
+    
+    [Test]
+    public async Task DeleteAllRowsAsync()
+    {
+        var uri = new Uri("file:///tmp/test_table_deleterows_all_async");
+        try
+        {
+            using (var cnn = new Connection(uri))
+            {
+                var table = await cnn.CreateTableAsync("table1", Helpers.GetSchema());
+                var recordBatch = Helpers.CreateSampleRecordBatch(
+                    Helpers.GetSchema(), 8, 128
+                );
+                await table.AddAsync(new List<RecordBatch> { recordBatch });
+                Assert.That(await table.DeleteAllAsync(), Is.EqualTo(8));
+                Assert.That(await table.CountRowsAsync(), Is.EqualTo(0));
+            }
+        }
+        finally
+        {
+            Cleanup(uri);
+        }
+    }
+    
+    [Test]
+    public void PreviewDeleteLeavesRows()
+    {
+        var uri = new Uri("file:///tmp/test_table_deleterows_preview");
+        try
+        {
+            using (var cnn = new Connection(uri))
+            {
+                var table = cnn.CreateTable("table1", Helpers.GetSchema());
+                var recordBatch = Helpers.CreateSampleRecordBatch(
+                    Helpers.GetSchema(), 8, 128
+                );
+                table.Add(new List<RecordBatch> { recordBatch });
+                var preview = table.PreviewDelete("id = '0' OR id = '1'", 1);
+                Assert.That(preview.MatchingRows, Is.EqualTo(2));
+                Assert.That(preview.Sample.Sum(batch => batch.Length), Is.EqualTo(1));
+                Assert.That(table.PreviewDelete(null).MatchingRows, Is.EqualTo(8));
+                Assert.That(table.CountRows(), Is.EqualTo(8));
+            }
+        }
+        finally
+        {
+            Cleanup(uri);
+        }
+    }
+    
+    [Test]
+    public async Task PreviewDeleteLeavesRowsAsync()
+    {
+        var uri = new Uri("file:///tmp/test_table_deleterows_preview_async");
+        try
+        {
+            using (var cnn = new Connection(uri))
+            {
+                var table = await cnn.CreateTableAsync("table1", Helpers.GetSchema());
+                var recordBatch = Helpers.CreateSampleRecordBatch(
+                    Helpers.GetSchema(), 8, 128
+                );
+                await table.AddAsync(new List<RecordBatch> { recordBatch });
+                var preview = await table.PreviewDeleteAsync("id = '0'");
+                Assert.That(preview.MatchingRows, Is.EqualTo(1));
+                Assert.That(preview.Sample.Sum(batch => batch.Length), Is.EqualTo(1));
+                Assert.That(await table.CountRowsAsync(), Is.EqualTo(8));
+            }
+        }
+        finally
+        {
+            Cleanup(uri);
+        }
+    }
+    
     [Test]
     public void UpdateRows()
     {
@@ -624,6 +721,64 @@ public partial class Tests
         }
     }
     
+    [Test]
+    public void UpdateRowsSqlStatement()
+    {
+        var uri = new Uri("file:///tmp/test_table_updaterowssql_statement");
+        try
+        {
+            using (var cnn = new Connection(uri))
+            {
+                Assert.That(cnn.IsOpen, Is.True);
+                var table = cnn.CreateTable("table1", Helpers.GetSchema());
+                Assert.That(table.IsOpen, Is.True);
+                var recordBatch = Helpers.CreateSampleRecordBatch(
+                    Helpers.GetSchema(), 8, 128
+                );
+                // Note that the interface defines a list, so we'll use that
+                var array = new List<RecordBatch> { recordBatch };
+                table.Add(array);
+                Assert.That(table.CountRows(), Is.GreaterThan(0));
+                var updatedCount = table.UpdateSql("UPDATE table1 SET id = 'test' WHERE id = '0'");
+                Assert.That(table.CountRows("id = 'test'"), Is.EqualTo(1));
+                Assert.That(updatedCount, Is.EqualTo(1));
+            }
+        }
+        finally
+        {
+            Cleanup(uri);
+        }
+    }
+    
+    [Test]
+    public async Task UpdateRowsSqlStatementAsync()
+    {
+        var uri = new Uri("file:///tmp/test_table_updaterowssql_statement_async");
+        try
+        {
+            using (var cnn = new Connection(uri))
+            {
+                Assert.That(cnn.IsOpen, Is.True);
+                var table = await cnn.CreateTableAsync("table1", Helpers.GetSchema());
+                Assert.That(table.IsOpen, Is.True);
+                var recordBatch = Helpers.CreateSampleRecordBatch(
+                    Helpers.GetSchema(), 8, 128
+                );
+                // Note that the interface defines a list, so we'll use that
+                var array = new List<RecordBatch> { recordBatch };
+                await table.AddAsync(array);
+                Assert.That(await table.CountRowsAsync(), Is.GreaterThan(0));
+                var updatedCount = await table.UpdateSqlAsync("UPDATE table1 SET id = 'test' WHERE id = '0'");
+                Assert.That(await table.CountRowsAsync("id = 'test'"), Is.EqualTo(1));
+                Assert.That(updatedCount, Is.EqualTo(1));
+            }
+        }
+        finally
+        {
+            Cleanup(uri);
+        }
+    }
+    
     [Test]
     public void CreateDefaultScalarIndexIsOkOnEmpty()
     {
@@ -1443,4 +1598,123 @@ public partial class Tests
             Cleanup(uri);
         }
     }
+    
+    [Test]
+    public void SetRetryPolicy()
+    {
+        var uri = new Uri("file:///tmp/test_table_retry_policy");
+        try
+        {
+            using (var cnn = new Connection(uri))
+            {
+                var table = cnn.CreateTable("table1", Helpers.GetSchema());
+                table.SetRetryPolicy(5, TimeSpan.FromMilliseconds(10), TimeSpan.FromSeconds(1), 0.5,
+                    (attempt, delay, error) => TestContext.Out.WriteLine($"Retry {attempt} in {delay}: {error}"));
+                var recordBatch = Helpers.CreateSampleRecordBatch(
+                    Helpers.GetSchema(), 8, 128
+                );
+                table.Add(new List<RecordBatch> { recordBatch });
+                Assert.That(table.CountRows(), Is.EqualTo(8));
+                Assert.Throws<Exception>(() => table.SetRetryPolicy(5, jitter: 2.0));
+                table.SetRetryPolicy(0);
+            }
+        }
+        finally
+        {
+            Cleanup(uri);
+        }
+    }
+    
+    [Test]
+    public async Task WriteBufferAsync()
+    {
+        var uri = new Uri("file:///tmp/test_table_write_buffer_async");
+        try
+        {
+            using (var cnn = new Connection(uri))
+            {
+                var table = await cnn.CreateTableAsync("table1", Helpers.GetSchema());
+                var flushed = 0ul;
+                await table.EnableWriteBufferAsync(maxRows: 1000, onFlush: (rows, error) =>
+                {
+                    if (error == null) Interlocked.Add(ref flushed, rows);
+                });
+                for (var i = 0; i < 4; i++)
+                {
+                    var recordBatch = Helpers.CreateSampleRecordBatch(
+                        Helpers.GetSchema(), 2, 128, i * 2
+                    );
+                    await table.AddAsync(new List<RecordBatch> { recordBatch });
+                }
+                Assert.That(await table.FlushWriteBufferAsync(), Is.EqualTo(8));
+                Assert.That(await table.CountRowsAsync(), Is.EqualTo(8));
+                Assert.That(Interlocked.Read(ref flushed), Is.EqualTo(8));
+                await table.DisableWriteBufferAsync();
+            }
+        }
+        finally
+        {
+            Cleanup(uri);
+        }
+    }
+    
+    [Test]
+    public void AddJsonRows()
+    {
+        var uri = new Uri("file:///tmp/test_table_add_json");
+        try
+        {
+            using (var cnn = new Connection(uri))
+            {
+                var table = cnn.CreateTable("table1", Helpers.GetSchema());
+                var vector = string.Join(",", Enumerable.Repeat("1.0", 128));
+                var json = $"[{{\"id\": \"a\", \"vector\": [{vector}]}}, {{\"id\": \"b\", \"vector\": null}}]";
+                Assert.That(table.AddJson(json), Is.EqualTo(0));
+                Assert.That(table.CountRows(), Is.EqualTo(2));
+                Assert.Throws<Exception>(() => table.AddJson("[{\"id\": 1}]"));
+            }
+        }
+        finally
+        {
+            Cleanup(uri);
+        }
+    }
+    
+    [Test]
+    public void BatchVectorSearch()
+    {
+        var uri = new Uri("file:///tmp/test_table_batch_vector_search");
+        try
+        {
+            using (var cnn = new Connection(uri))
+            {
+                var table = cnn.CreateTable("table1", Helpers.GetSchema());
+                var recordBatch = Helpers.CreateSampleRecordBatch(
+                    Helpers.GetSchema(), 8, 128, increaseSample: true
+                );
+                table.Add(new List<RecordBatch> { recordBatch });
+
+                var vectors = new List<float[]>
+                {
+                    Enumerable.Repeat(0.0f, 128).ToArray(),
+                    Enumerable.Repeat(7.0f, 128).ToArray(),
+                };
+                var batches = table.BatchVectorSearch(vectors, limit: 3, vectorColumn: "vector").ToList();
+                var queryIndices = batches
+                    .SelectMany(batch => ((UInt64Array)batch.Column(0)).Values.ToArray())
+                    .ToList();
+                Assert.Multiple(() =>
+                {
+                    Assert.That(batches.First().Schema.GetFieldByIndex(0).Name, Is.EqualTo("query_index"));
+                    Assert.That(queryIndices.Count(index => index == 0), Is.EqualTo(3));
+                    Assert.That(queryIndices.Count(index => index == 1), Is.EqualTo(3));
+                    Assert.That(queryIndices, Is.Ordered);
+                });
+            }
+        }
+        finally
+        {
+            Cleanup(uri);
+        }
+    }
 }
\ No newline at end of file
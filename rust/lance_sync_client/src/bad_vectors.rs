@@ -0,0 +1,241 @@
+//! Handles "bad" vectors in incoming data. A vector is bad if its dimension
+//! doesn't match the table's vector column, or if it contains NaN, infinite
+//! or null values. Depending on the `BadVectorHandling` mode, bad vectors
+//! are reported as an error, their rows are dropped, or they are replaced
+//! with a filled vector or a null.
+
+use crate::event_loop::command::BadVectorHandling;
+use arrow_array::cast::AsArray;
+use arrow_array::{Array, ArrayRef, BooleanArray, FixedSizeListArray, Float64Array, RecordBatch};
+use arrow_buffer::NullBuffer;
+use arrow_cast::cast;
+use arrow_schema::{ArrowError, DataType, Field, Schema};
+use arrow_select::filter::filter_record_batch;
+use std::sync::Arc;
+
+/// Where each row's vector lives in a flattened `Float64Array`.
+struct FlatVectors {
+    values: Float64Array,
+    /// `(start, length)` for each row, or `None` if the row is null.
+    rows: Vec<Option<(usize, usize)>>,
+}
+
+/// Flatten a list-like column into f64 values and per-row extents. Returns
+/// `None` if the column isn't a list of floats.
+fn flatten_vectors(column: &ArrayRef) -> Option<FlatVectors> {
+    let (values, rows) = match column.data_type() {
+        DataType::List(_) => {
+            let list = column.as_list::<i32>();
+            let offsets = list.value_offsets();
+            let rows = (0..list.len())
+                .map(|i| {
+                    list.is_valid(i).then(|| {
+                        (offsets[i] as usize, (offsets[i + 1] - offsets[i]) as usize)
+                    })
+                })
+                .collect();
+            (list.values().clone(), rows)
+        }
+        DataType::LargeList(_) => {
+            let list = column.as_list::<i64>();
+            let offsets = list.value_offsets();
+            let rows = (0..list.len())
+                .map(|i| {
+                    list.is_valid(i).then(|| {
+                        (offsets[i] as usize, (offsets[i + 1] - offsets[i]) as usize)
+                    })
+                })
+                .collect();
+            (list.values().clone(), rows)
+        }
+        DataType::FixedSizeList(_, size) => {
+            let list = column.as_fixed_size_list();
+            let size = *size as usize;
+            let rows = (0..list.len())
+                .map(|i| list.is_valid(i).then(|| (list.value_offset(i) as usize, size)))
+                .collect();
+            (list.values().clone(), rows)
+        }
+        _ => return None,
+    };
+    if !values.data_type().is_floating() {
+        return None;
+    }
+    let values = cast(&values, &DataType::Float64).ok()?.as_primitive().clone();
+    Some(FlatVectors { values, rows })
+}
+
+/// Describe why a vector is bad, or return `None` if it's fine.
+fn vector_problem(vectors: &FlatVectors, row: usize, dimension: usize) -> Option<String> {
+    let (start, length) = vectors.rows[row]?;
+    if length != dimension {
+        return Some(format!("has dimension {length}, expected {dimension}"));
+    }
+    let bad_value = (start..start + length)
+        .any(|i| vectors.values.is_null(i) || !vectors.values.value(i).is_finite());
+    bad_value.then(|| "contains NaN, infinite or null values".to_string())
+}
+
+/// Apply the bad-vector policy to every vector column (a fixed-size list of
+/// floats in the table schema) present in the batch. Returns the new batch
+/// and the number of rows that were dropped, filled or nulled.
+pub(crate) fn handle_bad_vectors(
+    batch: RecordBatch,
+    table: &Schema,
+    mode: BadVectorHandling,
+    fill_value: f32,
+) -> Result<(RecordBatch, usize), String> {
+    let mut bad_rows = vec![false; batch.num_rows()];
+    let mut columns = batch.columns().to_vec();
+
+    for table_field in table.fields() {
+        let DataType::FixedSizeList(item, dimension) = table_field.data_type() else {
+            continue;
+        };
+        if !item.data_type().is_floating() {
+            continue;
+        }
+        let dimension = *dimension as usize;
+        let Ok(index) = batch.schema().index_of(table_field.name()) else {
+            continue;
+        };
+        let Some(vectors) = flatten_vectors(batch.column(index)) else {
+            continue;
+        };
+
+        let mut column_has_bad = false;
+        for (row, bad) in bad_rows.iter_mut().enumerate() {
+            if let Some(problem) = vector_problem(&vectors, row, dimension) {
+                if let BadVectorHandling::Error = mode {
+                    return Err(format!(
+                        "Row {row}: vector column `{}` {problem}",
+                        table_field.name()
+                    ));
+                }
+                *bad = true;
+                column_has_bad = true;
+            }
+        }
+
+        if column_has_bad && matches!(mode, BadVectorHandling::Fill | BadVectorHandling::Null) {
+            if matches!(mode, BadVectorHandling::Null) && !table_field.is_nullable() {
+                return Err(format!(
+                    "Vector column `{}` is not nullable, so bad vectors can't be replaced with null",
+                    table_field.name()
+                ));
+            }
+            columns[index] = replace_bad_vectors(
+                &vectors,
+                dimension,
+                item.as_ref(),
+                table_field.data_type(),
+                mode,
+                fill_value,
+            )?;
+        }
+    }
+
+    let bad_count = bad_rows.iter().filter(|bad| **bad).count();
+    if bad_count == 0 {
+        return Ok((batch, 0));
+    }
+
+    match mode {
+        BadVectorHandling::Drop => {
+            let keep = BooleanArray::from(bad_rows.iter().map(|bad| !bad).collect::<Vec<_>>());
+            let batch = filter_record_batch(&batch, &keep)
+                .map_err(|e| format!("Error dropping bad vectors: {e}"))?;
+            Ok((batch, bad_count))
+        }
+        _ => {
+            let fields = batch
+                .schema()
+                .fields()
+                .iter()
+                .zip(columns.iter())
+                .map(|(field, column)| {
+                    Field::new(
+                        field.name(),
+                        column.data_type().clone(),
+                        field.is_nullable() || column.null_count() > 0,
+                    )
+                })
+                .collect::<Vec<_>>();
+            let schema = Arc::new(Schema::new_with_metadata(
+                fields,
+                batch.schema().metadata().clone(),
+            ));
+            let batch = RecordBatch::try_new(schema, columns)
+                .map_err(|e| format!("Error replacing bad vectors: {e}"))?;
+            Ok((batch, bad_count))
+        }
+    }
+}
+
+/// Rebuild a vector column with every bad vector filled or nulled, in the
+/// table's vector type.
+fn replace_bad_vectors(
+    vectors: &FlatVectors,
+    dimension: usize,
+    item: &Field,
+    target: &DataType,
+    mode: BadVectorHandling,
+    fill_value: f32,
+) -> Result<ArrayRef, String> {
+    let mut values = Vec::with_capacity(vectors.rows.len() * dimension);
+    let mut validity = Vec::with_capacity(vectors.rows.len());
+    for (row, extent) in vectors.rows.iter().enumerate() {
+        match (extent, vector_problem(vectors, row, dimension)) {
+            (None, _) => {
+                values.extend(std::iter::repeat_n(0.0, dimension));
+                validity.push(false);
+            }
+            (Some((start, _)), None) => {
+                values.extend((*start..*start + dimension).map(|i| vectors.values.value(i)));
+                validity.push(true);
+            }
+            (Some(_), Some(_)) => {
+                if let BadVectorHandling::Fill = mode {
+                    values.extend(std::iter::repeat_n(fill_value as f64, dimension));
+                    validity.push(true);
+                } else {
+                    values.extend(std::iter::repeat_n(0.0, dimension));
+                    validity.push(false);
+                }
+            }
+        }
+    }
+
+    let list = FixedSizeListArray::try_new(
+        Arc::new(Field::new(item.name(), DataType::Float64, item.is_nullable())),
+        dimension as i32,
+        Arc::new(Float64Array::from(values)),
+        Some(NullBuffer::from(validity)),
+    )
+    .map_err(|e| format!("Error replacing bad vectors: {e}"))?;
+    cast(&list, target).map_err(|e| format!("Error replacing bad vectors: {e}"))
+}
+
+/// Apply the bad-vector policy to a set of incoming batches. Batches that
+/// failed to decode are passed through untouched. Returns the new batches
+/// and the total number of rows that were dropped, filled or nulled.
+pub(crate) fn handle_bad_vectors_in_batches(
+    batches: Vec<Result<RecordBatch, ArrowError>>,
+    table: &Schema,
+    mode: BadVectorHandling,
+    fill_value: f32,
+) -> Result<(Vec<Result<RecordBatch, ArrowError>>, usize), String> {
+    let mut total = 0;
+    let mut result = Vec::with_capacity(batches.len());
+    for batch in batches {
+        match batch {
+            Ok(batch) => {
+                let (batch, count) = handle_bad_vectors(batch, table, mode, fill_value)?;
+                total += count;
+                result.push(Ok(batch));
+            }
+            Err(e) => result.push(Err(e)),
+        }
+    }
+    Ok((result, total))
+}
@@ -0,0 +1,126 @@
+//! Support for the Arrow C Data Interface. Record batches can be passed in
+//! as an `FFI_ArrowArray`/`FFI_ArrowSchema` pair or an `FFI_ArrowArrayStream`,
+//! and query results can be handed back as an `FFI_ArrowArrayStream`, so
+//! batches cross the FFI boundary without being serialized to IPC bytes.
+
+use arrow_array::ffi::{from_ffi, FFI_ArrowArray, FFI_ArrowSchema};
+use arrow_array::ffi_stream::ArrowArrayStreamReader;
+use arrow_array::{RecordBatch, RecordBatchOptions, RecordBatchReader, StructArray};
+use arrow_schema::{ArrowError, DataType, Schema, SchemaRef};
+use futures::TryStreamExt;
+use lancedb::arrow::SendableRecordBatchStream;
+use std::sync::Arc;
+
+/// How many result batches can be waiting for the host to read them.
+const RESULT_STREAM_QUEUE: usize = 4;
+
+/// Import a single record batch. The array is moved out of `array` (which is
+/// left released); the schema is only borrowed.
+pub(crate) fn import_batch(
+    array: *mut FFI_ArrowArray,
+    schema: *const FFI_ArrowSchema,
+) -> Result<RecordBatch, String> {
+    if array.is_null() || schema.is_null() {
+        return Err("Arrow array and schema must not be null.".to_string());
+    }
+    let data = unsafe {
+        let array = FFI_ArrowArray::from_raw(array);
+        from_ffi(array, &*schema)
+    }
+    .map_err(|e| format!("Could not import Arrow array: {e}"))?;
+    if !matches!(data.data_type(), DataType::Struct(_)) {
+        return Err(format!("Expected a struct array of columns, found {}", data.data_type()));
+    }
+    if data.null_count() > 0 {
+        return Err("The struct array of columns must not have top-level nulls.".to_string());
+    }
+    let rows = data.len();
+    let (fields, columns, _) = StructArray::from(data).into_parts();
+    let options = RecordBatchOptions::new().with_row_count(Some(rows));
+    RecordBatch::try_new_with_options(Arc::new(Schema::new(fields)), columns, &options)
+        .map_err(|e| format!("Could not import Arrow array: {e}"))
+}
+
+/// Import a stream of record batches. The stream is moved out of `stream`
+/// (which is left released), and is read on a runtime thread.
+pub(crate) fn import_stream(
+    stream: *mut arrow_array::ffi_stream::FFI_ArrowArrayStream,
+) -> Result<ArrowArrayStreamReader, String> {
+    if stream.is_null() {
+        return Err("Arrow array stream must not be null.".to_string());
+    }
+    unsafe { ArrowArrayStreamReader::from_raw(stream) }
+        .map_err(|e| format!("Could not import Arrow array stream: {e}"))
+}
+
+/// Called with the number of rows a query returned, once it has run to the end.
+/// It runs on a blocking thread, so it may call into the host.
+pub(crate) type RowCountFn = Box<dyn FnOnce(usize) + Send>;
+
+/// Query results, read by the host through an `FFI_ArrowArrayStream`. A
+/// runtime task pulls batches from the query and queues them; reading
+/// blocks until the next batch is ready. This must be read from a host
+/// thread, not from inside the runtime.
+#[derive(Debug)]
+pub(crate) struct ResultStreamReader {
+    schema: SchemaRef,
+    receiver: tokio::sync::mpsc::Receiver<Result<RecordBatch, ArrowError>>,
+}
+
+impl ResultStreamReader {
+    /// Start pulling batches from a query stream. Dropping the reader (when
+    /// the host releases the stream) stops the query, unless `on_end` is
+    /// set: it is called with the number of rows in the results once the
+    /// query has run to the end, so after an early release the rest of the
+    /// rows are read (and dropped) to complete the count. A failed query
+    /// skips `on_end`.
+    pub(crate) fn start(mut stream: SendableRecordBatchStream, on_end: Option<RowCountFn>) -> Self {
+        let schema = stream.schema();
+        let (tx, receiver) = tokio::sync::mpsc::channel(RESULT_STREAM_QUEUE);
+        tokio::spawn(async move {
+            let mut rows = 0;
+            let mut released = false;
+            loop {
+                let batch = match stream.try_next().await {
+                    Ok(Some(batch)) => batch,
+                    Ok(None) => break,
+                    Err(e) => {
+                        let _ = tx.send(Err(ArrowError::ExternalError(Box::new(e)))).await;
+                        return;
+                    }
+                };
+                rows += batch.num_rows();
+                if !released && tx.send(Ok(batch)).await.is_err() {
+                    if on_end.is_none() {
+                        return;
+                    }
+                    released = true;
+                }
+            }
+            if let Some(on_end) = on_end {
+                let _ = tokio::task::spawn_blocking(move || on_end(rows)).await;
+            }
+        });
+        Self { schema, receiver }
+    }
+
+    /// Wait for the next batch from inside the runtime (where the blocking
+    /// `Iterator` can't be used). Returns `None` at the end of the results.
+    pub(crate) async fn next_batch(&mut self) -> Option<Result<RecordBatch, ArrowError>> {
+        self.receiver.recv().await
+    }
+}
+
+impl Iterator for ResultStreamReader {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.blocking_recv()
+    }
+}
+
+impl RecordBatchReader for ResultStreamReader {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
@@ -0,0 +1,97 @@
+//! Pull-based query cursors. Opening a cursor starts a query and parks its
+//! result stream in the runtime; the host then asks for one batch at a time
+//! and closes the cursor when it is done (closing early stops the query).
+//! This maps onto an enumerator on the host: each batch is handed over on
+//! the thread that asked for it, rather than from a runtime thread.
+
+use crate::c_data::ResultStreamReader;
+use crate::serialization::IpcFormat;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::Mutex;
+
+/// Strongly typed cursor handle.
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub(crate) struct CursorHandle(pub(crate) i64);
+
+/// An open cursor: the query's results, and how batches are sent to the host.
+pub(crate) struct Cursor {
+    pub(crate) reader: ResultStreamReader,
+    pub(crate) ipc_format: IpcFormat,
+    /// Set once the stream has ended (or failed); later pulls return nothing.
+    pub(crate) finished: bool,
+}
+
+/// Cursors are shared, so that pulling from one doesn't hold up the others.
+pub(crate) type SharedCursor = Arc<Mutex<Cursor>>;
+
+pub(crate) enum CursorCommand {
+    Open {
+        cursor: Cursor,
+        reply_sender: tokio::sync::oneshot::Sender<CursorHandle>,
+    },
+    Get {
+        handle: CursorHandle,
+        reply_sender: tokio::sync::oneshot::Sender<Option<SharedCursor>>,
+    },
+    /// Remove the cursor, dropping its stream once any pull in progress ends.
+    Close {
+        handle: CursorHandle,
+        reply_sender: tokio::sync::oneshot::Sender<bool>,
+    },
+    Quit,
+}
+
+pub(crate) struct CursorActor;
+
+impl CursorActor {
+    pub(crate) async fn start() -> Sender<CursorCommand> {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(128);
+        tokio::spawn(async move {
+            let mut next_id = 1_i64;
+            let mut cursors = HashMap::<CursorHandle, SharedCursor>::new();
+
+            while let Some(command) = rx.recv().await {
+                match command {
+                    CursorCommand::Open { cursor, reply_sender } => {
+                        let handle = CursorHandle(next_id);
+                        next_id += 1;
+                        cursors.insert(handle, Arc::new(Mutex::new(cursor)));
+                        let _ = reply_sender.send(handle);
+                    }
+                    CursorCommand::Get {
+                        handle,
+                        reply_sender,
+                    } => {
+                        let _ = reply_sender.send(cursors.get(&handle).cloned());
+                    }
+                    CursorCommand::Close {
+                        handle,
+                        reply_sender,
+                    } => {
+                        let _ = reply_sender.send(cursors.remove(&handle).is_some());
+                    }
+                    CursorCommand::Quit => break,
+                }
+            }
+        });
+        tx
+    }
+}
+
+/// Get an open cursor.
+pub(crate) async fn get_cursor(
+    cursors: Sender<CursorCommand>,
+    handle: CursorHandle,
+) -> Option<SharedCursor> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    cursors
+        .send(CursorCommand::Get {
+            handle,
+            reply_sender: tx,
+        })
+        .await
+        .ok()?;
+    rx.await.ok().flatten()
+}
@@ -0,0 +1,267 @@
+//! Embedding functions implemented by the host. The host registers a named
+//! callback that turns text into vectors, and binds a table's vector column
+//! to a source (text) column and one of those functions. Adds, merge-inserts
+//! and text-vector queries then compute embeddings inside the runtime.
+
+use crate::connection_handler::ConnectionHandle;
+use crate::table_handler::TableHandle;
+use crate::EmbeddingCallback;
+use arrow_array::cast::AsArray;
+use arrow_array::{Array, ArrayRef, FixedSizeListArray, Float32Array, RecordBatch};
+use arrow_buffer::NullBuffer;
+use arrow_cast::cast;
+use arrow_schema::{ArrowError, DataType, Field, Schema};
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::sync::Arc;
+use tokio::sync::mpsc::Sender;
+use tokio::task::spawn_blocking;
+
+/// A host-provided embedding function.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct EmbeddingFunction {
+    /// The number of dimensions in each vector the function produces.
+    pub(crate) dimension: usize,
+    pub(crate) callback: EmbeddingCallback,
+}
+
+/// Binds a table's vector column to the text column it is computed from.
+#[derive(Debug, Clone)]
+pub(crate) struct EmbeddingBinding {
+    pub(crate) source_column: String,
+    pub(crate) vector_column: String,
+    pub(crate) function_name: String,
+}
+
+pub(crate) enum EmbeddingCommand {
+    RegisterFunction {
+        name: String,
+        function: EmbeddingFunction,
+    },
+    Bind {
+        connection_handle: ConnectionHandle,
+        table_handle: TableHandle,
+        binding: EmbeddingBinding,
+        /// The dimension of the table's vector column.
+        dimension: usize,
+        reply_sender: tokio::sync::oneshot::Sender<Result<(), String>>,
+    },
+    /// Get every binding for a table, with its resolved function.
+    GetBindings {
+        connection_handle: ConnectionHandle,
+        table_handle: TableHandle,
+        reply_sender: tokio::sync::oneshot::Sender<Vec<(EmbeddingBinding, EmbeddingFunction)>>,
+    },
+    Quit,
+}
+
+pub(crate) struct EmbeddingActor;
+
+impl EmbeddingActor {
+    pub(crate) async fn start() -> Sender<EmbeddingCommand> {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(128);
+        tokio::spawn(async move {
+            let mut functions = HashMap::<String, EmbeddingFunction>::new();
+            let mut bindings =
+                HashMap::<(ConnectionHandle, TableHandle), Vec<EmbeddingBinding>>::new();
+
+            while let Some(command) = rx.recv().await {
+                match command {
+                    EmbeddingCommand::RegisterFunction { name, function } => {
+                        functions.insert(name, function);
+                    }
+                    EmbeddingCommand::Bind {
+                        connection_handle,
+                        table_handle,
+                        binding,
+                        dimension,
+                        reply_sender,
+                    } => {
+                        let Some(function) = functions.get(&binding.function_name) else {
+                            let err = format!("Embedding function not found: {}", binding.function_name);
+                            let _ = reply_sender.send(Err(err));
+                            continue;
+                        };
+                        if function.dimension != dimension {
+                            let err = format!(
+                                "Embedding function `{}` produces {} dimensions, but `{}` has {dimension}",
+                                binding.function_name, function.dimension, binding.vector_column
+                            );
+                            let _ = reply_sender.send(Err(err));
+                            continue;
+                        }
+                        let table_bindings = bindings.entry((connection_handle, table_handle)).or_default();
+                        table_bindings.retain(|b| b.vector_column != binding.vector_column);
+                        table_bindings.push(binding);
+                        let _ = reply_sender.send(Ok(()));
+                    }
+                    EmbeddingCommand::GetBindings {
+                        connection_handle,
+                        table_handle,
+                        reply_sender,
+                    } => {
+                        let resolved = bindings
+                            .get(&(connection_handle, table_handle))
+                            .map(|table_bindings| {
+                                table_bindings
+                                    .iter()
+                                    .filter_map(|b| {
+                                        functions.get(&b.function_name).map(|f| (b.clone(), *f))
+                                    })
+                                    .collect()
+                            })
+                            .unwrap_or_default();
+                        let _ = reply_sender.send(resolved);
+                    }
+                    EmbeddingCommand::Quit => break,
+                }
+            }
+        });
+        tx
+    }
+}
+
+/// Get the embedding bindings for a table. Tables without bindings return
+/// an empty list.
+pub(crate) async fn get_embeddings(
+    embeddings: Sender<EmbeddingCommand>,
+    connection_handle: ConnectionHandle,
+    table_handle: TableHandle,
+) -> Vec<(EmbeddingBinding, EmbeddingFunction)> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let _ = embeddings
+        .send(EmbeddingCommand::GetBindings {
+            connection_handle,
+            table_handle,
+            reply_sender: tx,
+        })
+        .await;
+    rx.await.unwrap_or_default()
+}
+
+/// Run the host's embedding callback over a set of texts, returning the
+/// vectors flattened into one buffer.
+pub(crate) async fn embed_texts(
+    function: EmbeddingFunction,
+    texts: Vec<String>,
+) -> Result<Vec<f32>, String> {
+    if texts.is_empty() {
+        return Ok(Vec::new());
+    }
+    spawn_blocking(move || {
+        let texts = texts
+            .into_iter()
+            .map(CString::new)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Text to embed contains a null character: {e}"))?;
+        let pointers = texts.iter().map(|t| t.as_ptr()).collect::<Vec<_>>();
+        let mut vectors = vec![0.0_f32; texts.len() * function.dimension];
+        let ok = (function.callback)(
+            pointers.as_ptr(),
+            texts.len() as u64,
+            vectors.as_mut_ptr(),
+            function.dimension as u64,
+        );
+        if ok {
+            Ok(vectors)
+        } else {
+            Err("Embedding function reported an error.".to_string())
+        }
+    })
+    .await
+    .map_err(|e| format!("Error running embedding function: {e:?}"))?
+}
+
+/// Compute any bound vector columns that are missing from the batch. Rows
+/// with a null source value get a null vector. Columns already present in
+/// the batch are left untouched.
+pub(crate) async fn add_embeddings(
+    batch: RecordBatch,
+    embeddings: &[(EmbeddingBinding, EmbeddingFunction)],
+    table: &Schema,
+) -> Result<RecordBatch, String> {
+    let mut fields = batch.schema().fields().iter().cloned().collect::<Vec<_>>();
+    let mut columns = batch.columns().to_vec();
+
+    for (binding, function) in embeddings {
+        if batch.column_by_name(&binding.vector_column).is_some() {
+            continue;
+        }
+        let Some(source) = batch.column_by_name(&binding.source_column) else {
+            continue;
+        };
+        let source = cast(source, &DataType::Utf8)
+            .map_err(|e| format!("Embedding source column `{}`: {e}", binding.source_column))?;
+        let source = source.as_string::<i32>();
+        let texts = source.iter().flatten().map(|t| t.to_string()).collect::<Vec<_>>();
+        let vectors = embed_texts(*function, texts).await?;
+
+        let column = build_vector_column(source.nulls(), vectors, function.dimension, &binding.vector_column, table)
+            .map_err(|e| format!("Embedding column `{}`: {e}", binding.vector_column))?;
+        fields.push(Arc::new(Field::new(
+            &binding.vector_column,
+            column.data_type().clone(),
+            true,
+        )));
+        columns.push(column);
+    }
+
+    if columns.len() == batch.num_columns() {
+        return Ok(batch);
+    }
+    let schema = Arc::new(Schema::new_with_metadata(fields, batch.schema().metadata().clone()));
+    RecordBatch::try_new(schema, columns).map_err(|e| format!("Error adding embeddings: {e}"))
+}
+
+/// Apply `add_embeddings` to a set of incoming batches. Batches that failed
+/// to decode are passed through untouched.
+pub(crate) async fn add_embeddings_to_batches(
+    batches: Vec<Result<RecordBatch, ArrowError>>,
+    embeddings: &[(EmbeddingBinding, EmbeddingFunction)],
+    table: &Schema,
+) -> Result<Vec<Result<RecordBatch, ArrowError>>, String> {
+    if embeddings.is_empty() {
+        return Ok(batches);
+    }
+    let mut result = Vec::with_capacity(batches.len());
+    for batch in batches {
+        match batch {
+            Ok(batch) => result.push(Ok(add_embeddings(batch, embeddings, table).await?)),
+            Err(e) => result.push(Err(e)),
+        }
+    }
+    Ok(result)
+}
+
+/// Build a vector column from embedded values, which only exist for the
+/// non-null rows. The column has the table's type for the vector column.
+fn build_vector_column(
+    nulls: Option<&NullBuffer>,
+    vectors: Vec<f32>,
+    dimension: usize,
+    vector_column: &str,
+    table: &Schema,
+) -> Result<ArrayRef, ArrowError> {
+    let row_count = nulls.map_or(vectors.len() / dimension.max(1), |n| n.len());
+    let mut values = Vec::with_capacity(row_count * dimension);
+    let mut embedded = vectors.chunks_exact(dimension.max(1));
+    for row in 0..row_count {
+        match nulls {
+            Some(nulls) if nulls.is_null(row) => values.extend(std::iter::repeat_n(0.0, dimension)),
+            _ => values.extend_from_slice(embedded.next().unwrap_or_default()),
+        }
+    }
+
+    let target = table.field_with_name(vector_column)?.data_type().clone();
+    let item = match &target {
+        DataType::FixedSizeList(item, _) => item.clone(),
+        _ => Arc::new(Field::new("item", DataType::Float32, true)),
+    };
+    let list = FixedSizeListArray::try_new(
+        Arc::new(Field::new(item.name(), DataType::Float32, item.is_nullable())),
+        dimension as i32,
+        Arc::new(Float32Array::from(values)),
+        nulls.cloned(),
+    )?;
+    cast(&list, &target)
+}
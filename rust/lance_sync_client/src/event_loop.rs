@@ -3,18 +3,37 @@
 //! its async, and C# (etc.) manage their own - and provide a bridge
 //! through a message-passing interface.
 
+mod batch_query;
 pub(crate) mod command;
 mod connection;
+mod cursor;
+mod embeddings;
 mod errors;
+mod export;
 pub(crate) mod helpers;
+mod hybrid;
+mod import;
 mod lifecycle;
+mod maintenance;
 mod merge_insert;
 mod metric;
 mod queries;
+mod rerank;
+mod retry;
+pub(crate) mod schema;
 mod table;
+mod write_buffer;
+mod write_session;
 
 use crate::connection_handler::{ConnectionActor, ConnectionCommand};
+use crate::cursor_handler::{CursorActor, CursorCommand};
+use crate::embedding_handler::{EmbeddingActor, EmbeddingCommand};
+use crate::maintenance_handler::{MaintenanceActor, MaintenanceCommand};
+use crate::rerankers::{RerankerActor, RerankerCommand};
+use crate::retry_handler::{RetryActor, RetryCommand};
 use crate::table_handler::{TableActor, TableCommand};
+use crate::write_buffer_handler::{WriteBufferActor, WriteBufferCommand};
+use crate::write_session_handler::{WriteSessionActor, WriteSessionCommand};
 use crate::MAX_COMMANDS;
 pub(crate) use command::LanceDbCommand;
 use std::sync::OnceLock;
@@ -26,7 +45,7 @@ use crate::event_loop::connection::{
     do_drop_table, do_list_tables, do_open_table, do_rename_table,
 };
 pub(crate) use command::CompletionSender;
-pub(crate) use connection::get_connection;
+pub(crate) use connection::{get_connection, get_table};
 pub(crate) use errors::{report_result, report_result_sync, ErrorReportFn};
 pub(crate) use lifecycle::setup;
 pub(crate) use metric::MetricType;
@@ -57,6 +76,27 @@ async fn event_loop(ready_tx: tokio::sync::oneshot::Sender<Handle>) {
     // Table handler
     let tables = TableActor::start().await;
 
+    // Background maintenance scheduler
+    let maintenance = MaintenanceActor::start().await;
+
+    // Host embedding functions and their column bindings
+    let embeddings = EmbeddingActor::start().await;
+
+    // Streaming write sessions
+    let write_sessions = WriteSessionActor::start().await;
+
+    // Per-table buffers that coalesce small appends
+    let write_buffers = WriteBufferActor::start().await;
+
+    // Per-table retry policies for commit conflicts
+    let retries = RetryActor::start().await;
+
+    // Pull-based query cursors
+    let cursors = CursorActor::start().await;
+
+    // Host rerankers
+    let rerankers = RerankerActor::start().await;
+
     // Signal readiness
     let tokio_handle = Handle::current();
     ready_tx.send(tokio_handle).unwrap();
@@ -186,13 +226,22 @@ async fn event_loop(ready_tx: tokio::sync::oneshot::Sender<Handle>) {
                 table_handle,
                 write_mode,
                 batch,
+                auto_cast,
+                bad_vector_handling,
+                fill_value,
             } => {
                 tokio::spawn(table::do_add_record_batch(
                     connection_handle,
                     tables.clone(),
+                    embeddings.clone(),
+                    write_buffers.clone(),
+                    retries.clone(),
                     table_handle,
                     write_mode,
                     batch,
+                    auto_cast,
+                    bad_vector_handling,
+                    fill_value,
                     reply_tx,
                     completion_sender,
                 ));
@@ -201,20 +250,24 @@ async fn event_loop(ready_tx: tokio::sync::oneshot::Sender<Handle>) {
                 connection_handle,
                 table_handle,
                 columns,
-                when_not_matched_insert_all,
-                where_clause,
-                when_not_matched_by_source_delete,
+                clauses,
                 batch,
+                bad_vector_handling,
+                fill_value,
+                stats_callback,
             } => {
                 tokio::spawn(merge_insert::do_merge_insert_with_record_batch(
                     connection_handle,
                     table_handle,
                     tables.clone(),
+                    embeddings.clone(),
+                    retries.clone(),
                     columns.unwrap_or_default(),
-                    when_not_matched_insert_all,
-                    where_clause,
-                    when_not_matched_by_source_delete,
+                    clauses,
                     batch,
+                    bad_vector_handling,
+                    fill_value,
+                    stats_callback,
                     reply_tx,
                     completion_sender,
                 ));
@@ -238,12 +291,17 @@ async fn event_loop(ready_tx: tokio::sync::oneshot::Sender<Handle>) {
                 connection_handle,
                 table_handle,
                 where_clause,
+                delete_all,
+                dry_run,
             } => {
                 tokio::spawn(table::do_delete_rows(
                     connection_handle,
                     tables.clone(),
+                    retries.clone(),
                     table_handle,
-                    where_clause.unwrap_or("".to_string()),
+                    where_clause,
+                    delete_all,
+                    dry_run,
                     reply_tx,
                     completion_sender,
                 ));
@@ -253,27 +311,39 @@ async fn event_loop(ready_tx: tokio::sync::oneshot::Sender<Handle>) {
                 table_handle,
                 batch_callback,
                 limit,
+                pagination,
                 where_clause,
                 with_row_id,
                 explain_callback,
                 selected_columns,
+                projections,
+                reranker,
                 full_text_search,
                 batch_size,
+                ipc_format,
+                stream_sender,
             } => {
                 tokio::spawn(queries::do_query(
                     connection_handle,
+                    connections.clone(),
                     tables.clone(),
+                    rerankers.clone(),
                     table_handle,
                     reply_tx,
                     completion_sender,
                     batch_callback,
                     limit,
+                    pagination,
                     where_clause,
                     with_row_id,
                     explain_callback,
                     selected_columns,
+                    projections,
+                    reranker,
                     full_text_search,
                     batch_size,
+                    ipc_format,
+                    stream_sender,
                 ));
             }
             LanceDbCommand::VectorQuery {
@@ -281,37 +351,54 @@ async fn event_loop(ready_tx: tokio::sync::oneshot::Sender<Handle>) {
                 table_handle,
                 batch_callback,
                 limit,
+                pagination,
                 where_clause,
                 with_row_id,
                 explain_callback,
                 selected_columns,
+                projections,
+                reranker,
                 vector_data,
                 metric,
                 n_probes,
                 refine_factor,
                 batch_size,
+                ipc_format,
                 distance_range_min,
                 distance_range_max,
+                postfilter,
+                fast_search,
+                stream_sender,
             } => {
                 tokio::spawn(queries::do_vector_query(
                     connection_handle,
+                    connections.clone(),
                     tables.clone(),
+                    rerankers.clone(),
                     table_handle,
                     reply_tx,
                     completion_sender,
                     batch_callback,
                     limit,
+                    pagination,
                     where_clause,
                     with_row_id,
                     explain_callback,
                     selected_columns,
+                    projections,
+                    reranker,
                     vector_data,
+                    None,
                     metric,
                     n_probes,
                     refine_factor,
                     batch_size,
+                    ipc_format,
                     distance_range_min,
                     distance_range_max,
+                    postfilter,
+                    fast_search,
+                    stream_sender,
                 ));
             }
             LanceDbCommand::CreateScalarIndex {
@@ -404,6 +491,7 @@ async fn event_loop(ready_tx: tokio::sync::oneshot::Sender<Handle>) {
                 tokio::spawn(table::do_update(
                     connection_handle,
                     tables.clone(),
+                    retries.clone(),
                     table_handle,
                     reply_tx,
                     completion_sender,
@@ -412,6 +500,23 @@ async fn event_loop(ready_tx: tokio::sync::oneshot::Sender<Handle>) {
                     update_callback,
                 ));
             }
+            LanceDbCommand::UpdateSql {
+                connection_handle,
+                table_handle,
+                sql,
+                update_callback,
+            } => {
+                tokio::spawn(table::do_update_sql(
+                    connection_handle,
+                    tables.clone(),
+                    retries.clone(),
+                    table_handle,
+                    reply_tx,
+                    completion_sender,
+                    sql,
+                    update_callback,
+                ));
+            }
             LanceDbCommand::ListIndices { connection_handle, table_handle, string_callback } => {
                 tokio::spawn(table::do_list_table_indices(
                     connection_handle,
@@ -433,7 +538,460 @@ async fn event_loop(ready_tx: tokio::sync::oneshot::Sender<Handle>) {
                     callback,
                 ));
             }
+            LanceDbCommand::GetTableSchema {
+                connection_handle,
+                table_handle,
+                schema_callback,
+            } => {
+                tokio::spawn(schema::do_get_table_schema(
+                    connection_handle,
+                    tables.clone(),
+                    table_handle,
+                    schema_callback,
+                    reply_tx,
+                    completion_sender,
+                ));
+            }
+            LanceDbCommand::AddColumns {
+                connection_handle,
+                table_handle,
+                columns,
+                schema_callback,
+            } => {
+                tokio::spawn(schema::do_add_columns(
+                    connection_handle,
+                    tables.clone(),
+                    table_handle,
+                    columns,
+                    schema_callback,
+                    reply_tx,
+                    completion_sender,
+                ));
+            }
+            LanceDbCommand::AlterColumns {
+                connection_handle,
+                table_handle,
+                changes,
+                schema_callback,
+            } => {
+                tokio::spawn(schema::do_alter_columns(
+                    connection_handle,
+                    tables.clone(),
+                    table_handle,
+                    changes,
+                    schema_callback,
+                    reply_tx,
+                    completion_sender,
+                ));
+            }
+            LanceDbCommand::DropColumns {
+                connection_handle,
+                table_handle,
+                columns,
+                schema_callback,
+            } => {
+                tokio::spawn(schema::do_drop_columns(
+                    connection_handle,
+                    tables.clone(),
+                    table_handle,
+                    columns,
+                    schema_callback,
+                    reply_tx,
+                    completion_sender,
+                ));
+            }
+            LanceDbCommand::RegisterMaintenance {
+                connection_handle,
+                table_handle,
+                policy,
+            } => {
+                tokio::spawn(maintenance::do_register_maintenance(
+                    maintenance.clone(),
+                    tables.clone(),
+                    connection_handle,
+                    table_handle,
+                    policy,
+                    reply_tx,
+                    completion_sender,
+                ));
+            }
+            LanceDbCommand::DeregisterMaintenance { handle } => {
+                tokio::spawn(maintenance::do_deregister_maintenance(
+                    maintenance.clone(),
+                    handle,
+                    reply_tx,
+                    completion_sender,
+                ));
+            }
+            LanceDbCommand::GetMaintenanceStatus { handle, callback } => {
+                tokio::spawn(maintenance::do_get_maintenance_status(
+                    maintenance.clone(),
+                    handle,
+                    reply_tx,
+                    completion_sender,
+                    callback,
+                ));
+            }
+            LanceDbCommand::RegisterEmbeddingFunction { name, function } => {
+                tokio::spawn(embeddings::do_register_embedding_function(
+                    embeddings.clone(),
+                    name,
+                    function,
+                    reply_tx,
+                    completion_sender,
+                ));
+            }
+            LanceDbCommand::BindEmbeddingColumn {
+                connection_handle,
+                table_handle,
+                binding,
+            } => {
+                tokio::spawn(embeddings::do_bind_embedding_column(
+                    embeddings.clone(),
+                    tables.clone(),
+                    connection_handle,
+                    table_handle,
+                    binding,
+                    reply_tx,
+                    completion_sender,
+                ));
+            }
+            LanceDbCommand::TextVectorQuery {
+                connection_handle,
+                table_handle,
+                batch_callback,
+                limit,
+                where_clause,
+                with_row_id,
+                selected_columns,
+                query_text,
+                vector_column,
+                metric,
+                n_probes,
+                refine_factor,
+                batch_size,
+                ipc_format,
+                distance_range_min,
+                distance_range_max,
+                stream_sender,
+            } => {
+                tokio::spawn(embeddings::do_text_vector_query(
+                    connection_handle,
+                    connections.clone(),
+                    tables.clone(),
+                    embeddings.clone(),
+                    rerankers.clone(),
+                    table_handle,
+                    reply_tx,
+                    completion_sender,
+                    batch_callback,
+                    limit,
+                    where_clause,
+                    with_row_id,
+                    selected_columns,
+                    query_text,
+                    vector_column,
+                    metric,
+                    n_probes,
+                    refine_factor,
+                    batch_size,
+                    ipc_format,
+                    distance_range_min,
+                    distance_range_max,
+                    stream_sender,
+                ));
+            }
+            LanceDbCommand::HybridQuery {
+                connection_handle,
+                table_handle,
+                batch_callback,
+                limit,
+                where_clause,
+                with_row_id,
+                selected_columns,
+                vector_data,
+                metric,
+                n_probes,
+                full_text_search,
+                reranker,
+                batch_size,
+                ipc_format,
+            } => {
+                tokio::spawn(hybrid::do_hybrid_query(
+                    connection_handle,
+                    tables.clone(),
+                    rerankers.clone(),
+                    table_handle,
+                    reply_tx,
+                    completion_sender,
+                    batch_callback,
+                    limit,
+                    where_clause,
+                    with_row_id,
+                    selected_columns,
+                    vector_data,
+                    metric,
+                    n_probes,
+                    full_text_search,
+                    reranker,
+                    batch_size,
+                    ipc_format,
+                ));
+            }
+            LanceDbCommand::RegisterReranker { name, callback } => {
+                tokio::spawn(rerank::do_register_reranker(
+                    rerankers.clone(),
+                    name,
+                    callback,
+                    reply_tx,
+                    completion_sender,
+                ));
+            }
+            LanceDbCommand::BatchVectorQuery {
+                connection_handle,
+                table_handle,
+                batch_callback,
+                queries,
+                search,
+                concurrency,
+                batch_size,
+                ipc_format,
+            } => {
+                tokio::spawn(batch_query::do_batch_vector_query(
+                    connection_handle,
+                    tables.clone(),
+                    table_handle,
+                    reply_tx,
+                    completion_sender,
+                    batch_callback,
+                    queries,
+                    search,
+                    concurrency,
+                    batch_size,
+                    ipc_format,
+                ));
+            }
+            LanceDbCommand::BeginWrite {
+                connection_handle,
+                table_handle,
+                write_mode,
+            } => {
+                tokio::spawn(write_session::do_begin_write(
+                    write_sessions.clone(),
+                    tables.clone(),
+                    connection_handle,
+                    table_handle,
+                    write_mode,
+                    reply_tx,
+                    completion_sender,
+                ));
+            }
+            LanceDbCommand::WriteBatch { handle, batch } => {
+                tokio::spawn(write_session::do_write_batch(
+                    write_sessions.clone(),
+                    handle,
+                    batch,
+                    reply_tx,
+                    completion_sender,
+                ));
+            }
+            LanceDbCommand::CommitWrite { handle } => {
+                tokio::spawn(write_session::do_commit_write(
+                    write_sessions.clone(),
+                    handle,
+                    reply_tx,
+                    completion_sender,
+                ));
+            }
+            LanceDbCommand::AbortWrite { handle } => {
+                tokio::spawn(write_session::do_abort_write(
+                    write_sessions.clone(),
+                    handle,
+                    reply_tx,
+                    completion_sender,
+                ));
+            }
+            LanceDbCommand::AddRecordBatchStream {
+                connection_handle,
+                table_handle,
+                write_mode,
+                reader,
+                auto_cast,
+            } => {
+                tokio::spawn(table::do_add_record_batch_stream(
+                    connection_handle,
+                    tables.clone(),
+                    table_handle,
+                    write_mode,
+                    reader,
+                    auto_cast,
+                    reply_tx,
+                    completion_sender,
+                ));
+            }
+            LanceDbCommand::AddJsonRows {
+                connection_handle,
+                table_handle,
+                write_mode,
+                json,
+                bad_vector_handling,
+                fill_value,
+            } => {
+                tokio::spawn(table::do_add_json_rows(
+                    connection_handle,
+                    tables.clone(),
+                    embeddings.clone(),
+                    write_buffers.clone(),
+                    retries.clone(),
+                    table_handle,
+                    write_mode,
+                    json,
+                    bad_vector_handling,
+                    fill_value,
+                    reply_tx,
+                    completion_sender,
+                ));
+            }
+            LanceDbCommand::ImportFile {
+                connection_handle,
+                table_name,
+                path,
+                format,
+                options,
+                write_mode,
+                create_table,
+                progress_callback,
+            } => {
+                tokio::spawn(import::do_import_file(
+                    connections.clone(),
+                    connection_handle,
+                    table_name,
+                    path,
+                    format,
+                    options,
+                    write_mode,
+                    create_table,
+                    progress_callback,
+                    reply_tx,
+                    completion_sender,
+                ));
+            }
+            LanceDbCommand::ExportToFile {
+                query,
+                path,
+                options,
+            } => {
+                tokio::spawn(export::do_export_to_file(
+                    connections.clone(),
+                    tables.clone(),
+                    rerankers.clone(),
+                    *query,
+                    path,
+                    options,
+                    reply_tx,
+                    completion_sender,
+                ));
+            }
+            LanceDbCommand::EnableWriteBuffer {
+                connection_handle,
+                table_handle,
+                policy,
+            } => {
+                tokio::spawn(write_buffer::do_enable_write_buffer(
+                    write_buffers.clone(),
+                    tables.clone(),
+                    retries.clone(),
+                    connection_handle,
+                    table_handle,
+                    policy,
+                    reply_tx,
+                    completion_sender,
+                ));
+            }
+            LanceDbCommand::DisableWriteBuffer {
+                connection_handle,
+                table_handle,
+            } => {
+                tokio::spawn(write_buffer::do_disable_write_buffer(
+                    write_buffers.clone(),
+                    connection_handle,
+                    table_handle,
+                    reply_tx,
+                    completion_sender,
+                ));
+            }
+            LanceDbCommand::FlushWriteBuffer {
+                connection_handle,
+                table_handle,
+            } => {
+                tokio::spawn(write_buffer::do_flush_write_buffer(
+                    write_buffers.clone(),
+                    connection_handle,
+                    table_handle,
+                    reply_tx,
+                    completion_sender,
+                ));
+            }
+            LanceDbCommand::OpenCursor { query } => {
+                tokio::spawn(cursor::do_open_cursor(
+                    cursors.clone(),
+                    connections.clone(),
+                    tables.clone(),
+                    rerankers.clone(),
+                    *query,
+                    reply_tx,
+                    completion_sender,
+                ));
+            }
+            LanceDbCommand::NextBatch {
+                cursor_handle,
+                batch_sender,
+            } => {
+                tokio::spawn(cursor::do_next_batch(
+                    cursors.clone(),
+                    cursor_handle,
+                    batch_sender,
+                    reply_tx,
+                    completion_sender,
+                ));
+            }
+            LanceDbCommand::CloseCursor { cursor_handle } => {
+                tokio::spawn(cursor::do_close_cursor(
+                    cursors.clone(),
+                    cursor_handle,
+                    reply_tx,
+                    completion_sender,
+                ));
+            }
+            LanceDbCommand::SetRetryPolicy {
+                connection_handle,
+                table_handle,
+                policy,
+            } => {
+                tokio::spawn(retry::do_set_retry_policy(
+                    retries.clone(),
+                    tables.clone(),
+                    connection_handle,
+                    table_handle,
+                    policy,
+                    reply_tx,
+                    completion_sender,
+                ));
+            }
             LanceDbCommand::Quit { reply_sender } => {
+                // Buffered appends are committed before anything else shuts down.
+                let (buffers_tx, buffers_rx) = tokio::sync::oneshot::channel();
+                write_buffers
+                    .send(WriteBufferCommand::Quit { reply_sender: buffers_tx })
+                    .await
+                    .unwrap();
+                let _ = buffers_rx.await;
+                write_sessions.send(WriteSessionCommand::Quit).await.unwrap();
+                retries.send(RetryCommand::Quit).await.unwrap();
+                cursors.send(CursorCommand::Quit).await.unwrap();
+                rerankers.send(RerankerCommand::Quit).await.unwrap();
+                embeddings.send(EmbeddingCommand::Quit).await.unwrap();
+                maintenance.send(MaintenanceCommand::Quit).await.unwrap();
                 tables.send(TableCommand::Quit).await.unwrap();
                 connections.send(ConnectionCommand::Quit).await.unwrap();
                 quit_sender = Some(reply_sender);
@@ -0,0 +1,195 @@
+//! Batch k-NN search: many query vectors in one call. Each vector gets its
+//! own vector search, a bounded number of which run at once, and every
+//! result row is tagged with the `query_index` of the vector it matched.
+
+use crate::connection_handler::ConnectionHandle;
+use crate::event_loop::command::BatchSearch;
+use crate::event_loop::connection::get_table;
+use crate::event_loop::{report_result, CompletionSender, ErrorReportFn};
+use crate::serialization::{batch_to_bytes, IpcFormat};
+use crate::table_handler::{TableCommand, TableHandle};
+use crate::BlobCallback;
+use arrow_array::cast::AsArray;
+use arrow_array::{Array, ArrayRef, RecordBatch, UInt64Array};
+use arrow_schema::{ArrowError, DataType, Field, Schema};
+use futures::{StreamExt, TryStreamExt};
+use lancedb::query::{ExecutableQuery, QueryBase, QueryExecutionOptions, Select};
+use lancedb::Table;
+use std::sync::Arc;
+use tokio::sync::mpsc::Sender;
+use tokio::task::spawn_blocking;
+
+/// The column that says which query vector a result row belongs to.
+const QUERY_INDEX: &str = "query_index";
+
+/// How many searches run at once when the caller doesn't say.
+const DEFAULT_CONCURRENCY: usize = 8;
+
+/// Read the query vectors from the first column of the query batches: one
+/// vector per row, as a list of floats.
+fn query_vectors(queries: &[RecordBatch]) -> Result<Vec<ArrayRef>, String> {
+    let mut vectors = Vec::new();
+    for batch in queries {
+        if batch.num_columns() == 0 {
+            return Err("Query batch has no columns".to_string());
+        }
+        let column = batch.column(0);
+        for row in 0..column.len() {
+            if column.is_null(row) {
+                return Err(format!("Query vector {} is null", vectors.len()));
+            }
+            let vector = match column.data_type() {
+                DataType::FixedSizeList(..) => column.as_fixed_size_list().value(row),
+                DataType::List(_) => column.as_list::<i32>().value(row),
+                DataType::LargeList(_) => column.as_list::<i64>().value(row),
+                other => return Err(format!("Query vectors must be lists of floats, not {other}")),
+            };
+            if !vector.data_type().is_floating() {
+                return Err(format!("Query vectors must be lists of floats, not {}", column.data_type()));
+            }
+            vectors.push(vector);
+        }
+    }
+    Ok(vectors)
+}
+
+/// Add a `query_index` column, as the first column, to a search's results.
+fn with_query_index(batch: &RecordBatch, query_index: usize) -> Result<RecordBatch, ArrowError> {
+    let schema = batch.schema();
+    let mut fields = vec![Arc::new(Field::new(QUERY_INDEX, DataType::UInt64, false))];
+    fields.extend(schema.fields().iter().cloned());
+    let mut columns: Vec<ArrayRef> =
+        vec![Arc::new(UInt64Array::from(vec![query_index as u64; batch.num_rows()]))];
+    columns.extend(batch.columns().iter().cloned());
+    RecordBatch::try_new(
+        Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone())),
+        columns,
+    )
+}
+
+/// Run one query vector's search, returning its results tagged with
+/// `query_index`.
+async fn search(
+    table: Table,
+    search: Arc<BatchSearch>,
+    query_index: usize,
+    vector: ArrayRef,
+    batch_size: u32,
+) -> Result<Vec<RecordBatch>, String> {
+    let mut query_builder = table.query();
+    if let Some(limit) = search.limit {
+        query_builder = query_builder.limit(limit);
+    }
+    if let Some(where_clause) = &search.where_clause {
+        query_builder = query_builder.only_if(where_clause.clone());
+    }
+    if search.with_row_id {
+        query_builder = query_builder.with_row_id();
+    }
+    if let Some(selected_columns) = &search.selected_columns {
+        query_builder = query_builder.select(Select::Columns(selected_columns.clone()));
+    }
+    let mut query_builder = query_builder
+        .nearest_to(vector)
+        .map_err(|e| format!("Error in query {query_index}: {e:?}"))?
+        .distance_type(search.metric);
+    if let Some(vector_column) = &search.vector_column {
+        query_builder = query_builder.column(vector_column);
+    }
+    if search.n_probes > 0 {
+        query_builder = query_builder.nprobes(search.n_probes);
+    }
+    if search.refine_factor > 0 {
+        query_builder = query_builder.refine_factor(search.refine_factor);
+    }
+
+    let mut options = QueryExecutionOptions::default();
+    if batch_size > 0 {
+        options.max_batch_length = batch_size;
+    }
+    let batches: Vec<RecordBatch> = query_builder
+        .execute_with_options(options)
+        .await
+        .map_err(|e| format!("Error in query {query_index}: {e:?}"))?
+        .try_collect()
+        .await
+        .map_err(|e| format!("Error in query {query_index}: {e:?}"))?;
+    batches
+        .iter()
+        .map(|batch| with_query_index(batch, query_index))
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Error in query {query_index}: {e:?}"))
+}
+
+/// Search for every query vector, running up to `concurrency` searches at
+/// once. Results are sent to `batch_callback` in query order.
+pub(crate) async fn do_batch_vector_query(
+    connection_handle: ConnectionHandle,
+    tables: Sender<TableCommand>,
+    table_handle: TableHandle,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+    batch_callback: BlobCallback,
+    queries: Vec<RecordBatch>,
+    search_settings: BatchSearch,
+    concurrency: usize,
+    batch_size: u32,
+    ipc_format: IpcFormat,
+) {
+    let Some(table) = get_table(tables, connection_handle, table_handle).await else {
+        let err = format!("Table not found: {table_handle:?}");
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    };
+    let vectors = match query_vectors(&queries) {
+        Ok(vectors) => vectors,
+        Err(err) => {
+            report_result(Err(err), reply_tx, Some(completion_sender)).await;
+            return;
+        }
+    };
+    let concurrency = if concurrency > 0 { concurrency } else { DEFAULT_CONCURRENCY };
+    let search_settings = Arc::new(search_settings);
+
+    // `buffered` keeps the results in query order, while letting later
+    // searches run ahead of the one being sent.
+    let mut results = futures::stream::iter(vectors.into_iter().enumerate())
+        .map(|(query_index, vector)| {
+            search(table.clone(), search_settings.clone(), query_index, vector, batch_size)
+        })
+        .buffered(concurrency);
+    // Queries whose results were all sent; the host can stop early.
+    let mut delivered = 0;
+    'queries: while let Some(result) = results.next().await {
+        let batches = match result {
+            Ok(batches) => batches,
+            Err(err) => {
+                report_result(Err(err), reply_tx, Some(completion_sender)).await;
+                return;
+            }
+        };
+        let Some(batch_callback) = batch_callback else {
+            delivered += 1;
+            continue;
+        };
+        for batch in batches {
+            let Ok(bytes) = batch_to_bytes(&batch, &batch.schema(), ipc_format) else {
+                report_result(
+                    Err("Unable to convert result to bytes".to_string()),
+                    reply_tx,
+                    Some(completion_sender),
+                )
+                .await;
+                return;
+            };
+            let proceed = spawn_blocking(move || batch_callback(bytes.as_ptr(), bytes.len() as u64))
+                .await
+                .unwrap_or(false);
+            if !proceed {
+                break 'queries;
+            }
+        }
+        delivered += 1;
+    }
+    report_result(Ok(delivered as i64), reply_tx, Some(completion_sender)).await;
+}
@@ -1,13 +1,26 @@
+use crate::c_data::ResultStreamReader;
 use crate::connection_handler::ConnectionHandle;
+use crate::cursor_handler::CursorHandle;
+use crate::embedding_handler::{EmbeddingBinding, EmbeddingFunction};
+use crate::file_export::ExportOptions;
+use crate::file_import::{ImportFormat, ImportOptions, ImportProgressCallback};
+use crate::event_loop::schema::{ColumnChange, NewColumns};
 use crate::event_loop::VectorDataType;
+use crate::serialization::IpcFormat;
+use crate::rerankers::Reranker;
+use crate::retry_handler::RetryPolicy;
+use crate::maintenance_handler::{MaintenanceHandle, MaintenancePolicy};
 use crate::table_handler::TableHandle;
+use crate::write_buffer_handler::WriteBufferPolicy;
+use crate::write_session_handler::WriteSessionHandle;
+use arrow_array::ffi_stream::ArrowArrayStreamReader;
 use arrow_array::RecordBatch;
 use arrow_schema::{ArrowError, SchemaRef};
 use lancedb::table::AddDataMode;
 use lancedb::DistanceType;
 use std::ffi::c_char;
 use strum::FromRepr;
-use crate::BlobCallback;
+use crate::{BlobCallback, RerankCallback};
 
 /// Used to synchronize timings - make sure that the function
 /// does not return until all async processing is complete.
@@ -18,6 +31,9 @@ pub(crate) fn get_completion_pair() -> (CompletionSender, tokio::sync::oneshot::
     tokio::sync::oneshot::channel()
 }
 
+/// Receives query results to hand to the host as an Arrow C stream.
+pub(crate) type ResultStreamSender = tokio::sync::oneshot::Sender<ResultStreamReader>;
+
 /// Commands that can be sent to the LanceDB event-loop.
 #[derive(Debug)]
 pub(crate) enum LanceDbCommand {
@@ -75,16 +91,20 @@ pub(crate) enum LanceDbCommand {
         table_handle: TableHandle,
         write_mode: WriteMode,
         batch: Vec<Result<RecordBatch, ArrowError>>,
+        auto_cast: bool,
+        bad_vector_handling: BadVectorHandling,
+        fill_value: f32,
     },
 
     MergeInsert {
         connection_handle: ConnectionHandle,
         table_handle: TableHandle,
         columns: Option<Vec<String>>,
-        when_not_matched_insert_all: bool,
-        where_clause: Option<String>,
-        when_not_matched_by_source_delete: Option<String>,
+        clauses: MergeInsertClauses,
         batch: Vec<Result<RecordBatch, ArrowError>>,
+        bad_vector_handling: BadVectorHandling,
+        fill_value: f32,
+        stats_callback: MergeStatsCallback,
     },
 
     /// Count the number of rows in a table.
@@ -128,18 +148,21 @@ pub(crate) enum LanceDbCommand {
         where_clause: Option<String>,
         update_callback: Option<extern "C" fn(u64)>,
     },
-    //
-    // UpdateSQL {
-    //     connection_handle: ConnectionHandle,
-    //     table_handle: TableHandle,
-    //     updates: Vec<(String, String)>,
-    //     where_clause: String,
-    // },
-    //
+    /// A full SQL `UPDATE` statement, run as a single update.
+    UpdateSql {
+        connection_handle: ConnectionHandle,
+        table_handle: TableHandle,
+        sql: String,
+        update_callback: Option<extern "C" fn(u64)>,
+    },
     DeleteRows {
         connection_handle: ConnectionHandle,
         table_handle: TableHandle,
         where_clause: Option<String>,
+        /// Must be set to delete without a filter.
+        delete_all: bool,
+        /// If set, nothing is deleted: the matching rows are counted (and sampled).
+        dry_run: Option<DeletePreview>,
     },
 
     OptimizeTable {
@@ -156,12 +179,19 @@ pub(crate) enum LanceDbCommand {
         table_handle: TableHandle,
         batch_callback: BlobCallback,
         limit: Option<usize>,
+        pagination: Pagination,
         where_clause: Option<String>,
         with_row_id: bool,
         explain_callback: Option<(bool, extern "C" fn(*const c_char))>,
         selected_columns: Option<Vec<String>>,
+        /// Computed columns, as `(alias, SQL expression)` pairs.
+        projections: Option<Vec<(String, String)>>,
+        /// Reorders the results, which are then sent all at once.
+        reranker: Option<Reranker>,
         full_text_search: Option<String>,
         batch_size: u32,
+        ipc_format: IpcFormat,
+        stream_sender: Option<ResultStreamSender>,
     },
 
     VectorQuery {
@@ -169,17 +199,29 @@ pub(crate) enum LanceDbCommand {
         table_handle: TableHandle,
         batch_callback: BlobCallback,
         limit: Option<usize>,
+        pagination: Pagination,
         where_clause: Option<String>,
         with_row_id: bool,
         explain_callback: Option<(bool, extern "C" fn(*const c_char))>,
         selected_columns: Option<Vec<String>>,
+        /// Computed columns, as `(alias, SQL expression)` pairs.
+        projections: Option<Vec<(String, String)>>,
+        /// Reorders the results, which are then sent all at once.
+        reranker: Option<Reranker>,
         vector_data: VectorDataType,
         metric: DistanceType,
         n_probes: usize,
         refine_factor: u32,
         batch_size: u32,
+        ipc_format: IpcFormat,
         distance_range_min: Option<f32>,
         distance_range_max: Option<f32>,
+        /// Apply `where_clause` to the nearest matches, instead of before the
+        /// vector search.
+        postfilter: bool,
+        /// Search only indexed rows, skipping any added since the last index.
+        fast_search: bool,
+        stream_sender: Option<ResultStreamSender>,
     },
 
     /// List indices for a table.
@@ -197,6 +239,234 @@ pub(crate) enum LanceDbCommand {
         callback: Option<extern "C" fn(u32, u32, u64, u64, u64)>,
     },
 
+    /// Fetch the current schema of an open table.
+    GetTableSchema {
+        connection_handle: ConnectionHandle,
+        table_handle: TableHandle,
+        schema_callback: BlobCallback,
+    },
+
+    /// Add columns to a table.
+    AddColumns {
+        connection_handle: ConnectionHandle,
+        table_handle: TableHandle,
+        columns: NewColumns,
+        schema_callback: BlobCallback,
+    },
+
+    /// Rename, change nullability or cast existing columns.
+    AlterColumns {
+        connection_handle: ConnectionHandle,
+        table_handle: TableHandle,
+        changes: Vec<ColumnChange>,
+        schema_callback: BlobCallback,
+    },
+
+    /// Remove columns from a table.
+    DropColumns {
+        connection_handle: ConnectionHandle,
+        table_handle: TableHandle,
+        columns: Vec<String>,
+        schema_callback: BlobCallback,
+    },
+
+    /// Register a table for background maintenance.
+    RegisterMaintenance {
+        connection_handle: ConnectionHandle,
+        table_handle: TableHandle,
+        policy: MaintenancePolicy,
+    },
+
+    /// Stop background maintenance for a registration.
+    DeregisterMaintenance {
+        handle: MaintenanceHandle,
+    },
+
+    /// Get the status of a maintenance registration.
+    GetMaintenanceStatus {
+        handle: MaintenanceHandle,
+        callback: Option<extern "C" fn(i64, i64, u64, *const c_char)>,
+    },
+
+    /// Register (or replace) a host embedding function by name.
+    RegisterEmbeddingFunction {
+        name: String,
+        function: EmbeddingFunction,
+    },
+
+    /// Bind a table's vector column to a text column and an embedding function.
+    BindEmbeddingColumn {
+        connection_handle: ConnectionHandle,
+        table_handle: TableHandle,
+        binding: EmbeddingBinding,
+    },
+
+    /// Vector query, with the query vector computed from text by the
+    /// embedding function bound to `vector_column`.
+    TextVectorQuery {
+        connection_handle: ConnectionHandle,
+        table_handle: TableHandle,
+        batch_callback: BlobCallback,
+        limit: Option<usize>,
+        where_clause: Option<String>,
+        with_row_id: bool,
+        selected_columns: Option<Vec<String>>,
+        query_text: String,
+        vector_column: Option<String>,
+        metric: DistanceType,
+        n_probes: usize,
+        refine_factor: u32,
+        batch_size: u32,
+        ipc_format: IpcFormat,
+        distance_range_min: Option<f32>,
+        distance_range_max: Option<f32>,
+        stream_sender: Option<ResultStreamSender>,
+    },
+
+    /// Hybrid query: a vector search and a full-text search, fused by a
+    /// reranker.
+    HybridQuery {
+        connection_handle: ConnectionHandle,
+        table_handle: TableHandle,
+        batch_callback: BlobCallback,
+        limit: Option<usize>,
+        where_clause: Option<String>,
+        with_row_id: bool,
+        selected_columns: Option<Vec<String>>,
+        vector_data: VectorDataType,
+        metric: DistanceType,
+        n_probes: usize,
+        full_text_search: String,
+        reranker: Reranker,
+        batch_size: u32,
+        ipc_format: IpcFormat,
+    },
+
+    /// Register a host reranker.
+    RegisterReranker {
+        name: String,
+        callback: RerankCallback,
+    },
+
+    /// Vector search for each query vector in `queries`, tagging the
+    /// results with the vector's `query_index`.
+    BatchVectorQuery {
+        connection_handle: ConnectionHandle,
+        table_handle: TableHandle,
+        batch_callback: BlobCallback,
+        queries: Vec<RecordBatch>,
+        search: BatchSearch,
+        /// The most searches run at once.
+        concurrency: usize,
+        batch_size: u32,
+        ipc_format: IpcFormat,
+    },
+
+    /// Start a streaming write session on a table.
+    BeginWrite {
+        connection_handle: ConnectionHandle,
+        table_handle: TableHandle,
+        write_mode: WriteMode,
+    },
+
+    /// Send record batches to a write session.
+    WriteBatch {
+        handle: WriteSessionHandle,
+        batch: Vec<Result<RecordBatch, ArrowError>>,
+    },
+
+    /// Finish a write session, committing everything written to it.
+    CommitWrite {
+        handle: WriteSessionHandle,
+    },
+
+    /// Finish a write session without committing anything.
+    AbortWrite {
+        handle: WriteSessionHandle,
+    },
+
+    /// Add record batches read from an Arrow C stream.
+    AddRecordBatchStream {
+        connection_handle: ConnectionHandle,
+        table_handle: TableHandle,
+        write_mode: WriteMode,
+        reader: ArrowArrayStreamReader,
+        auto_cast: bool,
+    },
+
+    /// Add rows given as a JSON array of objects, or JSON-lines, converted
+    /// using the table's schema.
+    AddJsonRows {
+        connection_handle: ConnectionHandle,
+        table_handle: TableHandle,
+        write_mode: WriteMode,
+        json: String,
+        bad_vector_handling: BadVectorHandling,
+        fill_value: f32,
+    },
+
+    /// Import a Parquet, CSV or JSON-lines file into a table, optionally
+    /// creating it from the file's schema.
+    ImportFile {
+        connection_handle: ConnectionHandle,
+        table_name: String,
+        path: String,
+        format: ImportFormat,
+        options: ImportOptions,
+        write_mode: WriteMode,
+        create_table: bool,
+        progress_callback: ImportProgressCallback,
+    },
+
+    /// Run a `Query` or `VectorQuery` and write the results to a Parquet or
+    /// Arrow IPC file.
+    ExportToFile {
+        query: Box<LanceDbCommand>,
+        path: String,
+        options: ExportOptions,
+    },
+
+    /// Start buffering appends to a table, so they are committed together.
+    EnableWriteBuffer {
+        connection_handle: ConnectionHandle,
+        table_handle: TableHandle,
+        policy: WriteBufferPolicy,
+    },
+
+    /// Flush a table's write buffer and stop buffering.
+    DisableWriteBuffer {
+        connection_handle: ConnectionHandle,
+        table_handle: TableHandle,
+    },
+
+    /// Commit everything in a table's write buffer now.
+    FlushWriteBuffer {
+        connection_handle: ConnectionHandle,
+        table_handle: TableHandle,
+    },
+
+    /// Start a `Query` or `VectorQuery` and keep its results in a cursor.
+    OpenCursor {
+        query: Box<LanceDbCommand>,
+    },
+
+    NextBatch {
+        cursor_handle: CursorHandle,
+        /// Receives the next batch as IPC bytes, if there is one.
+        batch_sender: tokio::sync::oneshot::Sender<Vec<u8>>,
+    },
+
+    CloseCursor {
+        cursor_handle: CursorHandle,
+    },
+
+    /// Set (or, with `None`, remove) a table's commit-conflict retry policy.
+    SetRetryPolicy {
+        connection_handle: ConnectionHandle,
+        table_handle: TableHandle,
+        policy: Option<RetryPolicy>,
+    },
+
     /// Gracefully shut down the event-loop.
     Quit {
         reply_sender: tokio::sync::oneshot::Sender<()>,
@@ -255,4 +525,73 @@ impl From<WriteMode> for AddDataMode {
             WriteMode::Overwrite => Self::Overwrite,
         }
     }
-}
\ No newline at end of file
+}
+
+/// Which clauses of a merge-insert run. Each one is off unless it is set.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MergeInsertClauses {
+    /// Update rows that match a key, optionally only where the condition holds.
+    pub(crate) when_matched_update_all: bool,
+    pub(crate) when_matched_update_condition: Option<String>,
+    /// Insert rows that don't match a key.
+    pub(crate) when_not_matched_insert_all: bool,
+    /// Delete table rows that no incoming row matches, optionally only where
+    /// the filter holds.
+    pub(crate) when_not_matched_by_source_delete: bool,
+    pub(crate) when_not_matched_by_source_delete_filter: Option<String>,
+}
+
+/// Which page of a query's results to return.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Pagination {
+    /// Rows to skip before the first row returned.
+    pub(crate) offset: Option<usize>,
+    /// A token from a previous page. It replaces `offset`, and the page is
+    /// read from the same table version as the previous one.
+    pub(crate) page_token: Option<String>,
+    /// Receives the token for the next page, or null if there are no more
+    /// rows. Setting it pins the first page to the current table version.
+    pub(crate) next_page_callback: Option<extern "C" fn(*const c_char)>,
+}
+
+/// What a dry-run delete sends back, besides the number of matching rows.
+#[derive(Debug, Clone)]
+pub(crate) struct DeletePreview {
+    /// Up to this many matching rows are sent to `sample_callback`.
+    pub(crate) sample_limit: usize,
+    pub(crate) sample_callback: BlobCallback,
+    pub(crate) ipc_format: IpcFormat,
+}
+
+/// Settings shared by every search in a batch vector query.
+#[derive(Debug)]
+pub(crate) struct BatchSearch {
+    pub(crate) limit: Option<usize>,
+    pub(crate) where_clause: Option<String>,
+    pub(crate) with_row_id: bool,
+    pub(crate) selected_columns: Option<Vec<String>>,
+    /// The table's vector column to search, if not the one Lance picks.
+    pub(crate) vector_column: Option<String>,
+    pub(crate) metric: DistanceType,
+    pub(crate) n_probes: usize,
+    pub(crate) refine_factor: u32,
+}
+
+/// Receives the rows inserted, updated (-1 if an update condition means it
+/// isn't known) and deleted by a merge-insert.
+pub(crate) type MergeStatsCallback = Option<extern "C" fn(inserted: u64, updated: i64, deleted: u64)>;
+
+/// What to do with vectors that have the wrong dimension or contain NaN,
+/// infinite or null values. Matches `BadVectorHandling` on the C# side.
+#[derive(Debug, Clone, Copy, FromRepr)]
+#[repr(u32)]
+pub(crate) enum BadVectorHandling {
+    /// Fail the whole operation.
+    Error = 1,
+    /// Drop rows with bad vectors.
+    Drop = 2,
+    /// Replace bad vectors with a vector filled with the fill value.
+    Fill = 3,
+    /// Replace bad vectors with null.
+    Null = 4,
+}
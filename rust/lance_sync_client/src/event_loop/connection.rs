@@ -38,6 +38,34 @@ pub(crate) async fn get_table(
     rx.await.unwrap()
 }
 
+/// Open a separate instance of a table, checked out at `version`. Reads from
+/// it see that version however the table changes, and the shared handle is
+/// left on the latest version.
+pub(crate) async fn get_table_at_version(
+    connections: Sender<ConnectionCommand>,
+    tables: Sender<TableCommand>,
+    connection_handle: ConnectionHandle,
+    table_handle: TableHandle,
+    version: u64,
+) -> Result<Table, String> {
+    let Some(table) = get_table(tables, connection_handle, table_handle).await else {
+        return Err(format!("Table not found: {table_handle:?}"));
+    };
+    let Some(connection) = get_connection(connections, connection_handle).await else {
+        return Err(format!("Connection not found: {connection_handle:?}"));
+    };
+    let pinned = connection
+        .open_table(table.name())
+        .execute()
+        .await
+        .map_err(|e| format!("Error opening table: {:?}", e))?;
+    pinned
+        .checkout(version)
+        .await
+        .map_err(|e| format!("Error checking out version {version}: {:?}", e))?;
+    Ok(pinned)
+}
+
 pub(crate) async fn do_connection_request(
     connections: Sender<ConnectionCommand>,
     uri: String,
@@ -0,0 +1,124 @@
+//! Provides the event-loop side of pull-based query cursors.
+
+use crate::connection_handler::ConnectionCommand;
+use crate::cursor_handler::{get_cursor, Cursor, CursorCommand, CursorHandle};
+use crate::event_loop::command::LanceDbCommand;
+use crate::event_loop::queries::open_result_stream;
+use crate::event_loop::{report_result, CompletionSender, ErrorReportFn};
+use crate::rerankers::RerankerCommand;
+use crate::serialization::{batch_to_bytes, IpcFormat};
+use crate::table_handler::TableCommand;
+use tokio::sync::mpsc::Sender;
+
+/// Start a `Query` or `VectorQuery` command and park its results in a new
+/// cursor, reporting the cursor's handle.
+pub(crate) async fn do_open_cursor(
+    cursors: Sender<CursorCommand>,
+    connections: Sender<ConnectionCommand>,
+    tables: Sender<TableCommand>,
+    rerankers: Sender<RerankerCommand>,
+    query: LanceDbCommand,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+) {
+    let ipc_format = match &query {
+        LanceDbCommand::Query { ipc_format, .. } | LanceDbCommand::VectorQuery { ipc_format, .. } => {
+            *ipc_format
+        }
+        _ => IpcFormat::File,
+    };
+    let reader = match open_result_stream(connections, tables, rerankers, query, reply_tx).await {
+        Ok(reader) => reader,
+        Err(err) => {
+            report_result(Err(err), reply_tx, Some(completion_sender)).await;
+            return;
+        }
+    };
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let cursor = Cursor {
+        reader,
+        ipc_format,
+        finished: false,
+    };
+    let _ = cursors
+        .send(CursorCommand::Open {
+            cursor,
+            reply_sender: tx,
+        })
+        .await;
+    let result = match rx.await {
+        Ok(handle) => Ok(handle.0),
+        Err(_) => Err("Error opening cursor.".to_string()),
+    };
+    report_result(result, reply_tx, Some(completion_sender)).await;
+}
+
+/// Send the cursor's next batch to `batch_sender`, reporting 1 if there was
+/// one and 0 at the end of the results. The batch is sent before the result
+/// is reported, so the caller has it once the command completes.
+pub(crate) async fn do_next_batch(
+    cursors: Sender<CursorCommand>,
+    handle: CursorHandle,
+    batch_sender: tokio::sync::oneshot::Sender<Vec<u8>>,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+) {
+    let Some(cursor) = get_cursor(cursors, handle).await else {
+        let err = format!("Cursor not found: {handle:?}");
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    };
+    let mut cursor = cursor.lock().await;
+    if cursor.finished {
+        report_result(Ok(0), reply_tx, Some(completion_sender)).await;
+        return;
+    }
+
+    let batch = match cursor.reader.next_batch().await {
+        Some(Ok(batch)) => batch,
+        Some(Err(e)) => {
+            cursor.finished = true;
+            let err = format!("Error reading query results: {e:?}");
+            report_result(Err(err), reply_tx, Some(completion_sender)).await;
+            return;
+        }
+        None => {
+            cursor.finished = true;
+            report_result(Ok(0), reply_tx, Some(completion_sender)).await;
+            return;
+        }
+    };
+    let Ok(bytes) = batch_to_bytes(&batch, &batch.schema(), cursor.ipc_format) else {
+        report_result(
+            Err("Unable to convert result to bytes".to_string()),
+            reply_tx,
+            Some(completion_sender),
+        )
+        .await;
+        return;
+    };
+    let _ = batch_sender.send(bytes);
+    report_result(Ok(1), reply_tx, Some(completion_sender)).await;
+}
+
+pub(crate) async fn do_close_cursor(
+    cursors: Sender<CursorCommand>,
+    handle: CursorHandle,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+) {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let _ = cursors
+        .send(CursorCommand::Close {
+            handle,
+            reply_sender: tx,
+        })
+        .await;
+    let result = match rx.await {
+        Ok(true) => Ok(0),
+        Ok(false) => Err(format!("Cursor not found: {handle:?}")),
+        Err(_) => Err("Error closing cursor.".to_string()),
+    };
+    report_result(result, reply_tx, Some(completion_sender)).await;
+}
@@ -0,0 +1,194 @@
+//! Provides the event-loop side of host embedding functions: registering
+//! them, binding them to table columns, and querying with text.
+
+use crate::connection_handler::{ConnectionCommand, ConnectionHandle};
+use crate::embedding_handler::{
+    embed_texts, get_embeddings, EmbeddingBinding, EmbeddingCommand, EmbeddingFunction,
+};
+use crate::event_loop::command::{Pagination, ResultStreamSender};
+use crate::event_loop::connection::get_table;
+use crate::event_loop::queries::do_vector_query;
+use crate::event_loop::{report_result, CompletionSender, ErrorReportFn, VectorDataType};
+use crate::rerankers::RerankerCommand;
+use crate::serialization::IpcFormat;
+use crate::table_handler::{TableCommand, TableHandle};
+use crate::BlobCallback;
+use arrow_schema::DataType;
+use lancedb::DistanceType;
+use tokio::sync::mpsc::Sender;
+
+pub(crate) async fn do_register_embedding_function(
+    embeddings: Sender<EmbeddingCommand>,
+    name: String,
+    function: EmbeddingFunction,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+) {
+    let result = embeddings
+        .send(EmbeddingCommand::RegisterFunction { name, function })
+        .await
+        .map(|_| 0)
+        .map_err(|_| "Error sending embedding function registration.".to_string());
+    report_result(result, reply_tx, Some(completion_sender)).await;
+}
+
+pub(crate) async fn do_bind_embedding_column(
+    embeddings: Sender<EmbeddingCommand>,
+    tables: Sender<TableCommand>,
+    connection_handle: ConnectionHandle,
+    table_handle: TableHandle,
+    binding: EmbeddingBinding,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+) {
+    let Some(table) = get_table(tables.clone(), connection_handle, table_handle).await else {
+        let err = format!("Table not found: {table_handle:?}");
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    };
+    let schema = match table.schema().await {
+        Ok(schema) => schema,
+        Err(e) => {
+            let err = format!("Error getting table schema: {:?}", e);
+            report_result(Err(err), reply_tx, Some(completion_sender)).await;
+            return;
+        }
+    };
+
+    // The source must be text, and the target a fixed-size list of floats.
+    match schema.field_with_name(&binding.source_column).map(|f| f.data_type()) {
+        Ok(DataType::Utf8 | DataType::LargeUtf8) => {}
+        Ok(other) => {
+            let err = format!("Source column `{}` is {other}, not a string", binding.source_column);
+            report_result(Err(err), reply_tx, Some(completion_sender)).await;
+            return;
+        }
+        Err(_) => {
+            let err = format!("Source column not found: {}", binding.source_column);
+            report_result(Err(err), reply_tx, Some(completion_sender)).await;
+            return;
+        }
+    }
+    let dimension = match schema.field_with_name(&binding.vector_column).map(|f| f.data_type()) {
+        Ok(DataType::FixedSizeList(item, dimension)) if item.data_type().is_floating() => {
+            *dimension as usize
+        }
+        Ok(other) => {
+            let err = format!("Vector column `{}` is {other}, not a vector", binding.vector_column);
+            report_result(Err(err), reply_tx, Some(completion_sender)).await;
+            return;
+        }
+        Err(_) => {
+            let err = format!("Vector column not found: {}", binding.vector_column);
+            report_result(Err(err), reply_tx, Some(completion_sender)).await;
+            return;
+        }
+    };
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    if embeddings
+        .send(EmbeddingCommand::Bind {
+            connection_handle,
+            table_handle,
+            binding,
+            dimension,
+            reply_sender: tx,
+        })
+        .await
+        .is_err()
+    {
+        let err = "Error sending embedding binding.".to_string();
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    }
+    let result = match rx.await {
+        Ok(result) => result.map(|_| 0),
+        Err(_) => Err("Error receiving embedding binding response.".to_string()),
+    };
+    report_result(result, reply_tx, Some(completion_sender)).await;
+}
+
+pub(crate) async fn do_text_vector_query(
+    connection_handle: ConnectionHandle,
+    connections: Sender<ConnectionCommand>,
+    tables: Sender<TableCommand>,
+    embeddings: Sender<EmbeddingCommand>,
+    rerankers: Sender<RerankerCommand>,
+    table_handle: TableHandle,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+    batch_callback: BlobCallback,
+    limit: Option<usize>,
+    where_clause: Option<String>,
+    with_row_id: bool,
+    selected_columns: Option<Vec<String>>,
+    query_text: String,
+    vector_column: Option<String>,
+    metric: DistanceType,
+    n_probes: usize,
+    refine_factor: u32,
+    batch_size: u32,
+    ipc_format: IpcFormat,
+    distance_range_min: Option<f32>,
+    distance_range_max: Option<f32>,
+    stream_sender: Option<ResultStreamSender>,
+) {
+    // Without a column name, the table must have exactly one binding.
+    let bindings = get_embeddings(embeddings, connection_handle, table_handle).await;
+    let binding = match &vector_column {
+        Some(column) => bindings.iter().find(|(b, _)| &b.vector_column == column),
+        None if bindings.len() == 1 => bindings.first(),
+        None => None,
+    };
+    let Some((binding, function)) = binding else {
+        let err = match vector_column {
+            Some(column) => format!("No embedding function is bound to `{column}`"),
+            None => format!(
+                "Table has {} embedding bindings; specify the vector column",
+                bindings.len()
+            ),
+        };
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    };
+
+    let vector = match embed_texts(*function, vec![query_text]).await {
+        Ok(vector) => vector,
+        Err(e) => {
+            report_result(Err(e), reply_tx, Some(completion_sender)).await;
+            return;
+        }
+    };
+
+    do_vector_query(
+        connection_handle,
+        connections,
+        tables,
+        rerankers,
+        table_handle,
+        reply_tx,
+        completion_sender,
+        batch_callback,
+        limit,
+        Pagination::default(),
+        where_clause,
+        with_row_id,
+        None,
+        selected_columns,
+        None,
+        None,
+        VectorDataType::F32(vector),
+        Some(binding.vector_column.clone()),
+        metric,
+        n_probes,
+        refine_factor,
+        batch_size,
+        ipc_format,
+        distance_range_min,
+        distance_range_max,
+        false,
+        false,
+        stream_sender,
+    )
+    .await;
+}
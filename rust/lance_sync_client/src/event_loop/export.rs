@@ -0,0 +1,40 @@
+//! Provides export of query results to Parquet or Arrow IPC files.
+
+use crate::connection_handler::ConnectionCommand;
+use crate::event_loop::command::LanceDbCommand;
+use crate::event_loop::queries::open_result_stream;
+use crate::event_loop::{report_result, CompletionSender, ErrorReportFn};
+use crate::file_export::{export_batches, ExportOptions};
+use crate::rerankers::RerankerCommand;
+use crate::table_handler::TableCommand;
+use tokio::sync::mpsc::Sender;
+use tokio::task::spawn_blocking;
+
+/// Run a `Query` or `VectorQuery` command and write its results to `path`,
+/// reporting the number of rows written. The results are streamed straight
+/// to the file, so they never pass through the host.
+pub(crate) async fn do_export_to_file(
+    connections: Sender<ConnectionCommand>,
+    tables: Sender<TableCommand>,
+    rerankers: Sender<RerankerCommand>,
+    query: LanceDbCommand,
+    path: String,
+    options: ExportOptions,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+) {
+    let reader = match open_result_stream(connections, tables, rerankers, query, reply_tx).await {
+        Ok(reader) => reader,
+        Err(err) => {
+            report_result(Err(err), reply_tx, Some(completion_sender)).await;
+            return;
+        }
+    };
+
+    // The result stream blocks while it waits for batches, so the file is
+    // written off the runtime threads.
+    let result = spawn_blocking(move || export_batches(reader, &path, &options))
+        .await
+        .unwrap_or_else(|e| Err(format!("Error exporting results: {e:?}")));
+    report_result(result.map(|rows| rows as i64), reply_tx, Some(completion_sender)).await;
+}
@@ -0,0 +1,107 @@
+//! Hybrid search: a vector search and a full-text search on the same table,
+//! run concurrently and fused into one result set by a reranker.
+
+use crate::connection_handler::ConnectionHandle;
+use crate::event_loop::connection::get_table;
+use crate::event_loop::rerank::{collect_results, send_reranked};
+use crate::event_loop::{report_result, CompletionSender, ErrorReportFn, VectorDataType};
+use crate::rerankers::{Reranker, RerankerCommand};
+use crate::serialization::IpcFormat;
+use crate::table_handler::{TableCommand, TableHandle};
+use crate::BlobCallback;
+use lancedb::index::scalar::FullTextSearchQuery;
+use lancedb::query::{QueryBase, Select};
+use lancedb::DistanceType;
+use tokio::sync::mpsc::Sender;
+
+/// How many rows a hybrid query returns when it has no limit, matching
+/// LanceDB's default for vector queries.
+const DEFAULT_LIMIT: usize = 10;
+
+pub(crate) async fn do_hybrid_query(
+    connection_handle: ConnectionHandle,
+    tables: Sender<TableCommand>,
+    rerankers: Sender<RerankerCommand>,
+    table_handle: TableHandle,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+    batch_callback: BlobCallback,
+    limit: Option<usize>,
+    where_clause: Option<String>,
+    with_row_id: bool,
+    selected_columns: Option<Vec<String>>,
+    vector_data: VectorDataType,
+    metric: DistanceType,
+    n_probes: usize,
+    full_text_search: String,
+    reranker: Reranker,
+    batch_size: u32,
+    ipc_format: IpcFormat,
+) {
+    let Some(table) = get_table(tables, connection_handle, table_handle).await else {
+        let err = format!("Table not found: {table_handle:?}");
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    };
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+
+    // Both searches share the limit, filter and columns. Row IDs are always
+    // fetched, since they identify the rows found by both searches.
+    let mut fts_query = table
+        .query()
+        .full_text_search(FullTextSearchQuery::new(full_text_search.clone()))
+        .limit(limit)
+        .with_row_id();
+    let mut vector_query = table.query().limit(limit).with_row_id();
+    if let Some(where_clause) = where_clause {
+        fts_query = fts_query.only_if(where_clause.clone());
+        vector_query = vector_query.only_if(where_clause);
+    }
+    if let Some(selected_columns) = selected_columns {
+        fts_query = fts_query.select(Select::Columns(selected_columns.clone()));
+        vector_query = vector_query.select(Select::Columns(selected_columns));
+    }
+    let vector_query = match vector_data {
+        VectorDataType::F16(vector) => vector_query.nearest_to(vector),
+        VectorDataType::F32(vector) => vector_query.nearest_to(vector),
+        VectorDataType::F64(vector) => vector_query.nearest_to(vector),
+        VectorDataType::ArrowArray(array) => vector_query.nearest_to(array),
+    };
+    let mut vector_query = match vector_query {
+        Ok(vector_query) => vector_query.distance_type(metric),
+        Err(e) => {
+            let err = format!("Error querying table: {e:?}");
+            report_result(Err(err), reply_tx, Some(completion_sender)).await;
+            return;
+        }
+    };
+    if n_probes > 0 {
+        vector_query = vector_query.nprobes(n_probes);
+    }
+
+    let (vector_results, fts_results) = tokio::join!(
+        collect_results(&vector_query, "vector search"),
+        collect_results(&fts_query, "full-text search"),
+    );
+    let (vector_results, fts_results) = match (vector_results, fts_results) {
+        (Ok(vector_results), Ok(fts_results)) => (vector_results, fts_results),
+        (Err(err), _) | (_, Err(err)) => {
+            report_result(Err(err), reply_tx, Some(completion_sender)).await;
+            return;
+        }
+    };
+    let result = send_reranked(
+        rerankers,
+        &reranker,
+        Some(full_text_search),
+        Some(&vector_results),
+        Some(&fts_results),
+        Some(limit),
+        with_row_id,
+        batch_callback,
+        batch_size,
+        ipc_format,
+    )
+    .await;
+    report_result(result.map(|rows| rows as i64), reply_tx, Some(completion_sender)).await;
+}
@@ -0,0 +1,119 @@
+//! Provides bulk import of Parquet, CSV and JSON-lines files into a table.
+
+use crate::connection_handler::{ConnectionCommand, ConnectionHandle};
+use crate::event_loop::command::WriteMode;
+use crate::event_loop::{get_connection, report_result, CompletionSender, ErrorReportFn};
+use crate::file_import::{
+    open_import_file, ImportFormat, ImportOptions, ImportProgressCallback, ProgressReader,
+};
+use crate::schema_compat::{conform_batch, schema_differences};
+use arrow_array::RecordBatchIterator;
+use std::sync::atomic::Ordering;
+use tokio::sync::mpsc::Sender;
+use tokio::task::spawn_blocking;
+
+pub(crate) async fn do_import_file(
+    connections: Sender<ConnectionCommand>,
+    connection_handle: ConnectionHandle,
+    table_name: String,
+    path: String,
+    format: ImportFormat,
+    options: ImportOptions,
+    write_mode: WriteMode,
+    create_table: bool,
+    progress_callback: ImportProgressCallback,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+) {
+    let Some(cnn) = get_connection(connections.clone(), connection_handle).await else {
+        let err = format!("Connection not found: {connection_handle:?}");
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    };
+
+    let table = match cnn.open_table(&table_name).execute().await {
+        Ok(table) => Some(table),
+        Err(lancedb::Error::TableNotFound { .. }) if create_table => None,
+        Err(e) => {
+            let err = format!("Error opening table {table_name}: {:?}", e);
+            report_result(Err(err), reply_tx, Some(completion_sender)).await;
+            return;
+        }
+    };
+    let table_schema = match &table {
+        Some(table) => match table.schema().await {
+            Ok(schema) => Some(schema),
+            Err(e) => {
+                let err = format!("Error getting table schema: {:?}", e);
+                report_result(Err(err), reply_tx, Some(completion_sender)).await;
+                return;
+            }
+        },
+        None => None,
+    };
+
+    // Opening the file may read all of it to infer a schema.
+    let file_schema = table_schema.clone();
+    let file = spawn_blocking(move || {
+        open_import_file(&path, format, &options, file_schema.as_deref())
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Error opening import file: {e:?}")));
+    let file = match file {
+        Ok(file) => file,
+        Err(e) => {
+            report_result(Err(e), reply_tx, Some(completion_sender)).await;
+            return;
+        }
+    };
+
+    let result = match (table, table_schema) {
+        (Some(table), Some(schema)) => {
+            let differences = schema_differences(&file.reader.schema(), &schema, true);
+            if !differences.is_empty() {
+                let err = format!(
+                    "Import file does not match the table schema:\n{}",
+                    differences.join("\n")
+                );
+                report_result(Err(err), reply_tx, Some(completion_sender)).await;
+                return;
+            }
+
+            // Batches are conformed as they are read, so the file is never held in memory.
+            let table_schema = schema.clone();
+            let batches = file
+                .reader
+                .map(move |batch| batch.and_then(|batch| conform_batch(&batch, &table_schema)));
+            let batches = RecordBatchIterator::new(batches, schema.clone());
+            let (reader, rows) =
+                ProgressReader::new(batches, schema, file.total_rows, progress_callback);
+            table
+                .add(reader)
+                .mode(write_mode.into())
+                .execute()
+                .await
+                .map(|_| rows.load(Ordering::SeqCst))
+        }
+        _ => {
+            // Create the table from the file's own schema.
+            let schema = file.reader.schema();
+            let (reader, rows) =
+                ProgressReader::new(file.reader, schema, file.total_rows, progress_callback);
+            cnn.create_table(&table_name, reader)
+                .execute()
+                .await
+                .map(|_| rows.load(Ordering::SeqCst))
+        }
+    };
+
+    match result {
+        Ok(rows) => {
+            // Report the number of rows imported.
+            report_result(Ok(rows as i64), reply_tx, Some(completion_sender)).await;
+        }
+        Err(e) => {
+            let err = format!("Error importing file: {:?}", e);
+            report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        }
+    }
+}
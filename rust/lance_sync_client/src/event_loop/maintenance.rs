@@ -0,0 +1,98 @@
+//! Provides the event-loop side of background table maintenance.
+
+use crate::connection_handler::ConnectionHandle;
+use crate::event_loop::{report_result, CompletionSender, ErrorReportFn};
+use crate::maintenance_handler::{MaintenanceCommand, MaintenanceHandle, MaintenancePolicy};
+use crate::table_handler::{TableCommand, TableHandle};
+use std::ffi::{c_char, CString};
+use tokio::sync::mpsc::Sender;
+
+pub(crate) async fn do_register_maintenance(
+    maintenance: Sender<MaintenanceCommand>,
+    tables: Sender<TableCommand>,
+    connection_handle: ConnectionHandle,
+    table_handle: TableHandle,
+    policy: MaintenancePolicy,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+) {
+    if maintenance
+        .send(MaintenanceCommand::Register {
+            connection_handle,
+            table_handle,
+            policy,
+            tables,
+            reply_sender: reply_tx,
+            completion_sender,
+        })
+        .await
+        .is_err()
+    {
+        report_result(
+            Err("Error sending maintenance registration.".to_string()),
+            reply_tx,
+            None,
+        ).await;
+    }
+}
+
+pub(crate) async fn do_deregister_maintenance(
+    maintenance: Sender<MaintenanceCommand>,
+    handle: MaintenanceHandle,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+) {
+    if maintenance
+        .send(MaintenanceCommand::Deregister {
+            handle,
+            reply_sender: reply_tx,
+            completion_sender,
+        })
+        .await
+        .is_err()
+    {
+        report_result(
+            Err("Error sending maintenance deregistration.".to_string()),
+            reply_tx,
+            None,
+        ).await;
+    }
+}
+
+pub(crate) async fn do_get_maintenance_status(
+    maintenance: Sender<MaintenanceCommand>,
+    handle: MaintenanceHandle,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+    // fn(last_run_ms, next_run_ms, run_count, last_error)
+    callback: Option<extern "C" fn(i64, i64, u64, *const c_char)>,
+) {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let _ = maintenance
+        .send(MaintenanceCommand::GetStatus {
+            handle,
+            reply_sender: tx,
+        })
+        .await;
+    let Ok(Some(status)) = rx.await else {
+        let err = format!("Maintenance registration not found: {handle:?}");
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    };
+
+    if let Some(callback) = callback {
+        let last_run = status.last_run.map(|t| t.timestamp_millis()).unwrap_or(-1);
+        let next_run = status.next_run.timestamp_millis();
+        let last_error = status
+            .last_error
+            .map(|e| CString::new(e).unwrap_or_default());
+        callback(
+            last_run,
+            next_run,
+            status.run_count,
+            last_error.as_ref().map_or(std::ptr::null(), |e| e.as_ptr()),
+        );
+    }
+
+    report_result(Ok(0), reply_tx, Some(completion_sender)).await;
+}
@@ -1,49 +1,285 @@
 //! Provides support for the merge-insert idiom.
 
+use crate::bad_vectors::handle_bad_vectors_in_batches;
 use crate::connection_handler::ConnectionHandle;
+use crate::embedding_handler::{add_embeddings_to_batches, get_embeddings, EmbeddingCommand};
+use crate::event_loop::command::{BadVectorHandling, MergeInsertClauses, MergeStatsCallback};
 use crate::event_loop::connection::get_table;
 use crate::event_loop::{report_result, CompletionSender, ErrorReportFn};
+use crate::retry_handler::{get_retry_policy, retry_on_conflict, RetryCommand};
 use crate::table_handler::{TableCommand, TableHandle};
-use anyhow::Result;
-use arrow_array::{RecordBatch, RecordBatchIterator};
-use arrow_schema::ArrowError;
+use arrow_array::{Array, ArrayRef, RecordBatch, RecordBatchIterator};
+use arrow_cast::cast;
+use arrow_row::{RowConverter, SortField};
+use arrow_schema::{ArrowError, DataType, Schema};
+use futures::TryStreamExt;
+use lancedb::query::{ExecutableQuery, QueryBase, Select};
+use lancedb::Table;
+use std::collections::HashSet;
 use tokio::sync::mpsc::Sender;
+use tokio::task::spawn_blocking;
 
 pub(crate) async fn do_merge_insert_with_record_batch(
     connection_handle: ConnectionHandle,
     table_handle: TableHandle,
     table_actor: Sender<TableCommand>,
+    embeddings: Sender<EmbeddingCommand>,
+    retries: Sender<RetryCommand>,
     columns: Vec<String>,
-    when_not_matched_insert_all: bool,
-    where_clause: Option<String>,
-    when_not_matched_by_source_delete: Option<String>,
-    batch: Vec<std::result::Result<RecordBatch, ArrowError>>,
+    clauses: MergeInsertClauses,
+    batch: Vec<Result<RecordBatch, ArrowError>>,
+    bad_vector_handling: BadVectorHandling,
+    fill_value: f32,
+    stats_callback: MergeStatsCallback,
     reply_tx: ErrorReportFn,
     completion_sender: CompletionSender,
-) -> Result<()> {
+) {
+    if !clauses.when_matched_update_all
+        && !clauses.when_not_matched_insert_all
+        && !clauses.when_not_matched_by_source_delete
+    {
+        let err = "Merge insert needs at least one clause.".to_string();
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    }
     let Some(table) = get_table(table_actor.clone(), connection_handle, table_handle).await else {
         let err = format!("Table not found: {table_handle:?}");
         report_result(Err(err), reply_tx, Some(completion_sender)).await;
-        return Ok(());
+        return;
+    };
+    let schema = match table.schema().await {
+        Ok(schema) => schema,
+        Err(e) => {
+            let err = format!("Error getting table schema: {:?}", e);
+            report_result(Err(err), reply_tx, Some(completion_sender)).await;
+            return;
+        }
     };
-    let columns = columns.iter().map(|s| s.as_str()).collect::<Vec<&str>>();
-    let mut merge_insert_builder = table.merge_insert(&columns);
 
-    merge_insert_builder.when_matched_update_all(where_clause);
+    let bindings = get_embeddings(embeddings, connection_handle, table_handle).await;
+    let batch = match add_embeddings_to_batches(batch, &bindings, &schema).await {
+        Ok(batch) => batch,
+        Err(e) => {
+            report_result(Err(e), reply_tx, Some(completion_sender)).await;
+            return;
+        }
+    };
 
-    if when_not_matched_insert_all {
-        merge_insert_builder.when_not_matched_insert_all();
+    let (batch, bad_vector_count) =
+        match handle_bad_vectors_in_batches(batch, &schema, bad_vector_handling, fill_value) {
+            Ok(result) => result,
+            Err(e) => {
+                report_result(Err(e), reply_tx, Some(completion_sender)).await;
+                return;
+            }
+        };
+    let batch = match batch.into_iter().collect::<Result<Vec<_>, _>>() {
+        Ok(batch) => batch,
+        Err(e) => {
+            let err = format!("Error reading record batch: {e}");
+            report_result(Err(err), reply_tx, Some(completion_sender)).await;
+            return;
+        }
+    };
+
+    // Execute, building the merge again for each attempt. Statistics cost a
+    // scan of the key columns, so they are only gathered on request, and are
+    // counted just before each attempt so that a retry sees the rows it
+    // actually merges with.
+    let column_names = columns.iter().map(|s| s.as_str()).collect::<Vec<&str>>();
+    let policy = get_retry_policy(retries, connection_handle, table_handle).await;
+    let result = retry_on_conflict(policy.as_ref(), table_handle, "Error running merge insert", || {
+        let (table, columns, column_names, clauses, batch, schema) =
+            (&table, &columns, &column_names, &clauses, &batch, &schema);
+        async move {
+            let counts = match stats_callback {
+                Some(_) => match count_merge(table, columns, clauses, schema, batch).await {
+                    Ok(counts) => Some(counts),
+                    Err(e) => return Ok(Err(e)),
+                },
+                None => None,
+            };
+            let mut merge_insert_builder = table.merge_insert(column_names);
+            if clauses.when_matched_update_all {
+                merge_insert_builder.when_matched_update_all(clauses.when_matched_update_condition.clone());
+            }
+            if clauses.when_not_matched_insert_all {
+                merge_insert_builder.when_not_matched_insert_all();
+            }
+            if clauses.when_not_matched_by_source_delete {
+                merge_insert_builder
+                    .when_not_matched_by_source_delete(clauses.when_not_matched_by_source_delete_filter.clone());
+            }
+            let batch = Box::new(RecordBatchIterator::new(batch.clone().into_iter().map(Ok), schema.clone()));
+            merge_insert_builder.execute(batch).await.map(|_| Ok(counts))
+        }
+    })
+    .await;
+    let counts = match result {
+        Ok(Ok(counts)) => counts,
+        Ok(Err(err)) | Err(err) => {
+            report_result(Err(err), reply_tx, Some(completion_sender)).await;
+            return;
+        }
+    };
+
+    if let (Some(callback), Some(counts)) = (stats_callback, counts) {
+        let updated = counts.updated.map_or(-1, |updated| updated as i64);
+        spawn_blocking(move || callback(counts.inserted, updated, counts.deleted))
+            .await
+            .ok();
     }
-    if when_not_matched_by_source_delete.is_some() {
-        merge_insert_builder.when_not_matched_by_source_delete(when_not_matched_by_source_delete);
+
+    // Report how many rows had bad vectors dropped or replaced.
+    report_result(Ok(bad_vector_count as i64), reply_tx, Some(completion_sender)).await;
+}
+
+/// What a merge is expected to do, worked out just before it runs. LanceDB
+/// 0.17 doesn't return the statistics Lance gathers while merging, so they
+/// are predicted from the keys instead. A write that commits between the
+/// count and the merge can make these off, so they are a best effort.
+struct MergeCounts {
+    inserted: u64,
+    /// `None` if an update condition means it can't be worked out.
+    updated: Option<u64>,
+    deleted: u64,
+}
+
+/// Count how many incoming rows match an existing key (and so will be
+/// updated) or don't (and so will be inserted), and how many table rows no
+/// incoming row matches (and so will be deleted).
+async fn count_merge(
+    table: &Table,
+    columns: &[String],
+    clauses: &MergeInsertClauses,
+    schema: &Schema,
+    batches: &[RecordBatch],
+) -> Result<MergeCounts, String> {
+    let key_types = columns
+        .iter()
+        .map(|name| {
+            schema
+                .field_with_name(name)
+                .map(|field| field.data_type().clone())
+                .map_err(|e| format!("Merge key `{name}`: {e}"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Inserts count incoming rows with no match; updates count table rows with
+    // a match, so a key that appears in several table rows updates them all.
+    let needs_keys = clauses.when_not_matched_insert_all
+        || (clauses.when_matched_update_all && clauses.when_matched_update_condition.is_none());
+    let (incoming_matched, existing_matched) = if needs_keys {
+        let existing = read_keys(table, columns, None).await?;
+        let (columns, key_types, batches) = (columns.to_vec(), key_types.clone(), batches.to_vec());
+        spawn_blocking(move || -> Result<_, String> {
+            Ok((
+                count_matched_keys(&existing, &batches, &columns, &key_types)?,
+                count_matched_keys(&batches, &existing, &columns, &key_types)?,
+            ))
+        })
+        .await
+        .unwrap_or_else(|e| Err(format!("Error matching merge keys: {e:?}")))?
+    } else {
+        (0, 0)
+    };
+
+    let deleted = if clauses.when_not_matched_by_source_delete {
+        let filter = clauses.when_not_matched_by_source_delete_filter.as_deref();
+        let candidates = read_keys(table, columns, filter).await?;
+        let total = candidates.iter().map(|b| b.num_rows() as u64).sum::<u64>();
+        let (columns, batches) = (columns.to_vec(), batches.to_vec());
+        let kept = spawn_blocking(move || count_matched_keys(&batches, &candidates, &columns, &key_types))
+            .await
+            .unwrap_or_else(|e| Err(format!("Error matching merge keys: {e:?}")))?;
+        total - kept
+    } else {
+        0
+    };
+
+    let incoming = batches.iter().map(|b| b.num_rows() as u64).sum::<u64>();
+    Ok(MergeCounts {
+        inserted: if clauses.when_not_matched_insert_all {
+            incoming - incoming_matched
+        } else {
+            0
+        },
+        updated: match (clauses.when_matched_update_all, &clauses.when_matched_update_condition) {
+            (false, _) => Some(0),
+            (true, None) => Some(existing_matched),
+            (true, Some(_)) => None,
+        },
+        deleted,
+    })
+}
+
+/// Read the key columns of the table's rows, optionally only where `filter` holds.
+async fn read_keys(table: &Table, columns: &[String], filter: Option<&str>) -> Result<Vec<RecordBatch>, String> {
+    let mut query = table.query().select(Select::Columns(columns.to_vec()));
+    if let Some(filter) = filter {
+        query = query.only_if(filter);
     }
-    let schema = table.schema().await?;
+    query
+        .execute()
+        .await
+        .map_err(|e| format!("Error reading merge keys: {:?}", e))?
+        .try_collect::<Vec<_>>()
+        .await
+        .map_err(|e| format!("Error reading merge keys: {:?}", e))
+}
+
+/// Count the rows of `incoming` whose key columns match a row in `existing`,
+/// with both cast to the table's key types. As in SQL, a key containing a
+/// null never matches.
+fn count_matched_keys(
+    existing: &[RecordBatch],
+    incoming: &[RecordBatch],
+    columns: &[String],
+    key_types: &[DataType],
+) -> Result<u64, String> {
+    let converter = RowConverter::new(key_types.iter().cloned().map(SortField::new).collect())
+        .map_err(|e| format!("Error matching merge keys: {e}"))?;
 
-    // Execute
-    let batch = Box::new(RecordBatchIterator::new(batch, schema));
-    merge_insert_builder.execute(batch).await?;
+    let mut keys = HashSet::new();
+    for batch in existing {
+        let key_columns = key_columns(batch, columns, key_types)?;
+        let rows = converter
+            .convert_columns(&key_columns)
+            .map_err(|e| format!("Error matching merge keys: {e}"))?;
+        for (i, row) in rows.iter().enumerate() {
+            if key_columns.iter().all(|c| c.is_valid(i)) {
+                keys.insert(row.as_ref().to_vec());
+            }
+        }
+    }
 
-    report_result(Ok(0), reply_tx, Some(completion_sender)).await;
+    let mut matched = 0;
+    for batch in incoming {
+        let key_columns = key_columns(batch, columns, key_types)?;
+        let rows = converter
+            .convert_columns(&key_columns)
+            .map_err(|e| format!("Error matching merge keys: {e}"))?;
+        matched += rows
+            .iter()
+            .enumerate()
+            .filter(|(i, row)| {
+                key_columns.iter().all(|c| c.is_valid(*i)) && keys.contains(row.as_ref())
+            })
+            .count() as u64;
+    }
+    Ok(matched)
+}
 
-    Ok(())
+/// The key columns of a batch, cast to the table's key types.
+fn key_columns(batch: &RecordBatch, columns: &[String], key_types: &[DataType]) -> Result<Vec<ArrayRef>, String> {
+    columns
+        .iter()
+        .zip(key_types)
+        .map(|(name, data_type)| {
+            let column = batch
+                .column_by_name(name)
+                .ok_or_else(|| format!("Merge key `{name}` is missing from the data"))?;
+            cast(column, data_type).map_err(|e| format!("Merge key `{name}`: {e}"))
+        })
+        .collect()
 }
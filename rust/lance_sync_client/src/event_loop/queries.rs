@@ -1,15 +1,19 @@
-use crate::connection_handler::ConnectionHandle;
-use crate::event_loop::connection::get_table;
+use crate::c_data::{ResultStreamReader, RowCountFn};
+use crate::connection_handler::{ConnectionCommand, ConnectionHandle};
+use crate::event_loop::connection::{get_table, get_table_at_version};
+use crate::event_loop::command::{get_completion_pair, LanceDbCommand, Pagination, ResultStreamSender};
+use crate::event_loop::rerank::{collect_results, send_reranked};
 use crate::event_loop::{report_result, CompletionSender, ErrorReportFn};
-use crate::serialization::{batch_to_bytes, bytes_to_batch};
+use crate::rerankers::{Reranker, RerankerCommand};
+use crate::serialization::{batch_to_bytes, bytes_to_batch, IpcFormat};
 use crate::table_handler::{TableCommand, TableHandle};
 use arrow_array::Array;
 use futures::TryStreamExt;
 use half::f16;
 use lancedb::index::scalar::FullTextSearchQuery;
 use lancedb::query::{ExecutableQuery, QueryBase, QueryExecutionOptions, Select};
-use lancedb::DistanceType;
-use std::ffi::c_char;
+use lancedb::{DistanceType, Table};
+use std::ffi::{c_char, CString};
 use std::sync::Arc;
 use tokio::sync::mpsc::Sender;
 use tokio::task::spawn_blocking;
@@ -99,32 +103,151 @@ impl VectorDataType {
     }
 }
 
+/// Combine plain and computed columns into one selection. Computed columns
+/// need a dynamic select, which takes plain columns as `(name, name)`.
+fn selection(
+    selected_columns: Option<Vec<String>>,
+    projections: Option<Vec<(String, String)>>,
+) -> Option<Select> {
+    match (selected_columns, projections) {
+        (columns, Some(projections)) => {
+            let columns = columns.unwrap_or_default().into_iter().map(|c| (c.clone(), c));
+            Some(Select::Dynamic(columns.chain(projections).collect()))
+        }
+        (Some(columns), None) => Some(Select::Columns(columns)),
+        (None, None) => None,
+    }
+}
+
+/// A continuation token: the table version the first page was read from,
+/// and the offset of the next page. Hosts treat it as an opaque string.
+struct PageToken {
+    version: u64,
+    offset: usize,
+}
+
+impl PageToken {
+    fn encode(&self) -> String {
+        format!("{:x}-{:x}", self.version, self.offset)
+    }
+
+    fn decode(token: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid page token: {token}");
+        let (version, offset) = token.split_once('-').ok_or_else(invalid)?;
+        Ok(Self {
+            version: u64::from_str_radix(version, 16).map_err(|_| invalid())?,
+            offset: usize::from_str_radix(offset, 16).map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// Get the table to read a page from, the offset the page starts at, and
+/// (when pages are tracked) the table version the pages are read from.
+async fn open_page(
+    connections: Sender<ConnectionCommand>,
+    tables: Sender<TableCommand>,
+    connection_handle: ConnectionHandle,
+    table_handle: TableHandle,
+    pagination: &Pagination,
+) -> Result<(Table, Option<usize>, Option<u64>), String> {
+    if let Some(token) = &pagination.page_token {
+        let token = PageToken::decode(token)?;
+        let table =
+            get_table_at_version(connections, tables, connection_handle, table_handle, token.version).await?;
+        return Ok((table, Some(token.offset), Some(token.version)));
+    }
+    let Some(table) = get_table(tables.clone(), connection_handle, table_handle).await else {
+        return Err(format!("Table not found: {table_handle:?}"));
+    };
+    if pagination.next_page_callback.is_none() {
+        return Ok((table, pagination.offset, None));
+    }
+    // The first page is read from a pinned copy too, so later pages match it.
+    let version = table
+        .version()
+        .await
+        .map_err(|e| format!("Error getting table version: {:?}", e))?;
+    let table = get_table_at_version(connections, tables, connection_handle, table_handle, version).await?;
+    Ok((table, pagination.offset, Some(version)))
+}
+
+/// Whether the next page's token depends on how many rows this page has.
+fn counts_page_rows(pagination: &Pagination, version: Option<u64>, limit: Option<usize>) -> bool {
+    pagination.next_page_callback.is_some() && version.is_some() && limit.is_some()
+}
+
+/// Send the next page's token to the host. `rows` is the number of rows in
+/// the page: a short page means there are no more.
+async fn send_next_page(
+    pagination: &Pagination,
+    version: Option<u64>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    rows: usize,
+) {
+    if let Some(send) = next_page(pagination, version, offset, limit, rows) {
+        let _ = spawn_blocking(send).await;
+    }
+}
+
+/// The call that hands the next page's token to the host, if it wants one.
+/// This calls into the host, so it must run on a blocking thread.
+fn next_page(
+    pagination: &Pagination,
+    version: Option<u64>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    rows: usize,
+) -> Option<impl FnOnce() + Send + 'static> {
+    let (Some(callback), Some(version)) = (pagination.next_page_callback, version) else {
+        return None;
+    };
+    let token = match limit {
+        Some(limit) if rows >= limit => {
+            let offset = offset.unwrap_or(0) + limit;
+            CString::new(PageToken { version, offset }.encode()).ok()
+        }
+        _ => None,
+    };
+    Some(move || callback(token.as_ref().map_or(std::ptr::null(), |token| token.as_ptr())))
+}
+
 pub(crate) async fn do_query(
     connection_handle: ConnectionHandle,
+    connections: Sender<ConnectionCommand>,
     tables: Sender<TableCommand>,
+    rerankers: Sender<RerankerCommand>,
     table_handle: TableHandle,
     reply_tx: ErrorReportFn,
     completion_sender: CompletionSender,
     batch_callback: BlobCallback,
     limit: Option<usize>,
+    pagination: Pagination,
     where_clause: Option<String>,
     with_row_id: bool,
     explain_callback: Option<(bool, extern "C" fn(*const c_char))>,
     selected_columns: Option<Vec<String>>,
+    projections: Option<Vec<(String, String)>>,
+    reranker: Option<Reranker>,
     full_text_search: Option<String>,
     batch_size: u32,
+    ipc_format: IpcFormat,
+    stream_sender: Option<ResultStreamSender>,
 ) {
-    let Some(table) = get_table(tables.clone(), connection_handle, table_handle).await else {
-        let err = format!("Table not found: {table_handle:?}");
-        report_result(Err(err), reply_tx, Some(completion_sender)).await;
-        return;
-    };
+    let (table, offset, version) =
+        match open_page(connections, tables, connection_handle, table_handle, &pagination).await {
+            Ok(page) => page,
+            Err(err) => {
+                report_result(Err(err), reply_tx, Some(completion_sender)).await;
+                return;
+            }
+        };
 
     // Use the query builder setup
     let mut query_builder = table.query();
 
     // Full text search
-    if let Some(query) = full_text_search {
+    if let Some(query) = full_text_search.clone() {
         query_builder = query_builder.full_text_search(FullTextSearchQuery::new(query));
     }
 
@@ -134,6 +257,11 @@ pub(crate) async fn do_query(
         query_builder = query_builder.limit(limit);
     }
 
+    // Skip to the start of the page
+    if let Some(offset) = offset {
+        query_builder = query_builder.offset(offset);
+    }
+
     // Add a where clause if one is provided
     if let Some(where_clause) = where_clause {
         query_builder = query_builder.only_if(where_clause);
@@ -145,8 +273,8 @@ pub(crate) async fn do_query(
     }
 
     // Selected columns
-    if let Some(selected_columns) = selected_columns {
-        query_builder = query_builder.select(Select::Columns(selected_columns));
+    if let Some(selection) = selection(selected_columns, projections) {
+        query_builder = query_builder.select(selection);
     }
 
     // Explain handling
@@ -167,6 +295,37 @@ pub(crate) async fn do_query(
         }
     }
 
+    // Reranked results are gathered and scored before any are sent
+    if let Some(reranker) = reranker {
+        let result = match (full_text_search, stream_sender) {
+            (_, Some(_)) => Err("Reranked results can't be streamed.".to_string()),
+            (None, None) => Err("Only full-text queries can be reranked.".to_string()),
+            (Some(query_text), None) => match collect_results(&query_builder, "query").await {
+                Ok(results) => {
+                    send_reranked(
+                        rerankers,
+                        &reranker,
+                        Some(query_text),
+                        None,
+                        Some(&results),
+                        limit,
+                        with_row_id,
+                        batch_callback,
+                        batch_size,
+                        ipc_format,
+                    )
+                    .await
+                }
+                Err(err) => Err(err),
+            },
+        };
+        if let Ok(rows) = result {
+            send_next_page(&pagination, version, offset, limit, rows).await;
+        }
+        report_result(result.map(|_| 0), reply_tx, Some(completion_sender)).await;
+        return;
+    }
+
     let options = if batch_size > 0 {
         let mut qo = QueryExecutionOptions::default();
         qo.max_batch_length = batch_size;
@@ -175,13 +334,35 @@ pub(crate) async fn do_query(
         QueryExecutionOptions::default()
     };
 
-    match query_builder.execute_with_options(options).await {
-        Ok(mut query) => {
+    match (query_builder.execute_with_options(options).await, stream_sender) {
+        (Ok(query), Some(stream_sender)) => {
+            // The next page's token is sent once the stream has counted this page's rows.
+            let on_end: Option<RowCountFn> = if counts_page_rows(&pagination, version, limit) {
+                Some(Box::new(move |rows| {
+                    if let Some(send) = next_page(&pagination, version, offset, limit, rows) {
+                        send();
+                    }
+                }))
+            } else {
+                send_next_page(&pagination, version, offset, limit, 0).await;
+                None
+            };
+            // Hand the results to the host as an Arrow C stream
+            let _ = stream_sender.send(ResultStreamReader::start(query, on_end));
+            report_result(Ok(0), reply_tx, Some(completion_sender)).await;
+        }
+        (Ok(mut query), None) => {
+            let mut rows = 0;
+            let mut cancelled = false;
             while let Ok(Some(record)) = query.try_next().await {
+                rows += record.num_rows();
+                if cancelled {
+                    continue;
+                }
                 // Return results as a batch
                 if let Some(batch_callback) = batch_callback {
                     let schema = record.schema();
-                    let Ok(bytes) = batch_to_bytes(&record, &schema) else {
+                    let Ok(bytes) = batch_to_bytes(&record, &schema, ipc_format) else {
                         report_result(
                             Err("Unable to convert result to bytes".to_string()),
                             reply_tx,
@@ -193,15 +374,20 @@ pub(crate) async fn do_query(
                         batch_callback(bytes.as_ptr(), bytes.len() as u64)
                     }).await.unwrap();
                     if !proceed {
-                        break;
+                        // Stop sending, but read the rest of the page if its rows decide the next token.
+                        if !counts_page_rows(&pagination, version, limit) {
+                            break;
+                        }
+                        cancelled = true;
                     }
                 }
             }
+            send_next_page(&pagination, version, offset, limit, rows).await;
 
             // Announce that we're done
             report_result(Ok(0), reply_tx, Some(completion_sender)).await;
         }
-        Err(e) => {
+        (Err(e), _) => {
             let err = format!("Error querying table: {:?}", e);
             report_result(Err(err), reply_tx, Some(completion_sender)).await;
         }
@@ -210,29 +396,42 @@ pub(crate) async fn do_query(
 
 pub(crate) async fn do_vector_query(
     connection_handle: ConnectionHandle,
+    connections: Sender<ConnectionCommand>,
     tables: Sender<TableCommand>,
+    rerankers: Sender<RerankerCommand>,
     table_handle: TableHandle,
     reply_tx: ErrorReportFn,
     completion_sender: CompletionSender,
     batch_callback: BlobCallback,
     limit: Option<usize>,
+    pagination: Pagination,
     where_clause: Option<String>,
     with_row_id: bool,
     explain_callback: Option<(bool, extern "C" fn(*const c_char))>,
     selected_columns: Option<Vec<String>>,
+    projections: Option<Vec<(String, String)>>,
+    reranker: Option<Reranker>,
     vector_data: VectorDataType,
+    vector_column: Option<String>,
     metric: DistanceType,
     n_probes: usize,
     refine_factor: u32,
     batch_size: u32,
+    ipc_format: IpcFormat,
     distance_range_min: Option<f32>,
     distance_range_max: Option<f32>,
+    postfilter: bool,
+    fast_search: bool,
+    stream_sender: Option<ResultStreamSender>,
 ) {
-    let Some(table) = get_table(tables.clone(), connection_handle, table_handle).await else {
-        let err = format!("Table not found: {table_handle:?}");
-        report_result(Err(err), reply_tx, Some(completion_sender)).await;
-        return;
-    };
+    let (table, offset, version) =
+        match open_page(connections, tables, connection_handle, table_handle, &pagination).await {
+            Ok(page) => page,
+            Err(err) => {
+                report_result(Err(err), reply_tx, Some(completion_sender)).await;
+                return;
+            }
+        };
 
     // Use the query builder setup
     let mut query_builder = table.query();
@@ -243,6 +442,11 @@ pub(crate) async fn do_vector_query(
         query_builder = query_builder.limit(limit);
     }
 
+    // Skip to the start of the page
+    if let Some(offset) = offset {
+        query_builder = query_builder.offset(offset);
+    }
+
     // Add a where clause if one is provided
     if let Some(where_clause) = where_clause {
         query_builder = query_builder.only_if(where_clause);
@@ -254,8 +458,8 @@ pub(crate) async fn do_vector_query(
     }
 
     // Selected columns
-    if let Some(selected_columns) = selected_columns {
-        query_builder = query_builder.select(Select::Columns(selected_columns));
+    if let Some(selection) = selection(selected_columns, projections) {
+        query_builder = query_builder.select(selection);
     }
 
     // Vector handling
@@ -272,6 +476,11 @@ pub(crate) async fn do_vector_query(
     }
     let mut query_builder = vec_result.unwrap();
 
+    // Search a specific vector column, rather than the one Lance picks
+    if let Some(vector_column) = vector_column {
+        query_builder = query_builder.column(&vector_column);
+    }
+
     // Distance metric
     query_builder = query_builder.distance_type(metric);
 
@@ -288,6 +497,16 @@ pub(crate) async fn do_vector_query(
         query_builder = query_builder.distance_range(distance_range_min, distance_range_max);
     }
 
+    // Filter the nearest matches, rather than the rows searched
+    if postfilter {
+        query_builder = query_builder.postfilter();
+    }
+
+    // Search only indexed data
+    if fast_search {
+        query_builder = query_builder.fast_search();
+    }
+
     // Explain handling
     if let Some((verbose, explain_callback)) = explain_callback {
         match query_builder.explain_plan(verbose).await {
@@ -306,6 +525,36 @@ pub(crate) async fn do_vector_query(
         }
     }
 
+    // Reranked results are gathered and scored before any are sent
+    if let Some(reranker) = reranker {
+        let result = match stream_sender {
+            Some(_) => Err("Reranked results can't be streamed.".to_string()),
+            None => match collect_results(&query_builder, "vector query").await {
+                Ok(results) => {
+                    send_reranked(
+                        rerankers,
+                        &reranker,
+                        None,
+                        Some(&results),
+                        None,
+                        limit,
+                        with_row_id,
+                        batch_callback,
+                        batch_size,
+                        ipc_format,
+                    )
+                    .await
+                }
+                Err(err) => Err(err),
+            },
+        };
+        if let Ok(rows) = result {
+            send_next_page(&pagination, version, offset, limit, rows).await;
+        }
+        report_result(result.map(|_| 0), reply_tx, Some(completion_sender)).await;
+        return;
+    }
+
     println!("Batch size: {}", batch_size);
     let options = if batch_size > 0 {
         println!("Setting batch size to: {}", batch_size);
@@ -316,9 +565,31 @@ pub(crate) async fn do_vector_query(
         QueryExecutionOptions::default()
     };
 
-    match query_builder.execute_with_options(options).await {
-        Ok(mut query) => {
+    match (query_builder.execute_with_options(options).await, stream_sender) {
+        (Ok(query), Some(stream_sender)) => {
+            // The next page's token is sent once the stream has counted this page's rows.
+            let on_end: Option<RowCountFn> = if counts_page_rows(&pagination, version, limit) {
+                Some(Box::new(move |rows| {
+                    if let Some(send) = next_page(&pagination, version, offset, limit, rows) {
+                        send();
+                    }
+                }))
+            } else {
+                send_next_page(&pagination, version, offset, limit, 0).await;
+                None
+            };
+            // Hand the results to the host as an Arrow C stream
+            let _ = stream_sender.send(ResultStreamReader::start(query, on_end));
+            report_result(Ok(0), reply_tx, Some(completion_sender)).await;
+        }
+        (Ok(mut query), None) => {
+            let mut rows = 0;
+            let mut cancelled = false;
             while let Ok(Some(record)) = query.try_next().await {
+                rows += record.num_rows();
+                if cancelled {
+                    continue;
+                }
                 // Return results as a batch
                 println!("Received a record from the query");
                 let mut cancel = false;
@@ -331,7 +602,7 @@ pub(crate) async fn do_vector_query(
                         println!("Dividing result ({} rows) into {n_slices} slices for individual yielding", record.num_rows());
                         for slice in 0..n_slices {
                             let slice = record.slice(slice * batch_size as usize, batch_size as usize);
-                            let Ok(bytes) = batch_to_bytes(&slice, &schema) else {
+                            let Ok(bytes) = batch_to_bytes(&slice, &schema, ipc_format) else {
                                 report_result(
                                     Err("Unable to convert result to bytes".to_string()),
                                     reply_tx,
@@ -349,7 +620,7 @@ pub(crate) async fn do_vector_query(
                         }
                     } else {
                         // Return the whole record
-                        let Ok(bytes) = batch_to_bytes(&record, &schema) else {
+                        let Ok(bytes) = batch_to_bytes(&record, &schema, ipc_format) else {
                             report_result(
                                 Err("Unable to convert result to bytes".to_string()),
                                 reply_tx,
@@ -361,21 +632,142 @@ pub(crate) async fn do_vector_query(
                             batch_callback(bytes.as_ptr(), bytes.len() as u64)
                         }).await.unwrap();
                         if !proceed {
-                            break;
+                            cancel = true;
                         }
                     }
                 }
                 if cancel {
-                    break;
+                    // Stop sending, but read the rest of the page if its rows decide the next token.
+                    if !counts_page_rows(&pagination, version, limit) {
+                        break;
+                    }
+                    cancelled = true;
                 }
             } // end of while loop
+            send_next_page(&pagination, version, offset, limit, rows).await;
 
             // Announce that we're done
             report_result(Ok(0), reply_tx, Some(completion_sender)).await;
         }
-        Err(e) => {
+        (Err(e), _) => {
             let err = format!("Error querying table: {:?}", e);
             report_result(Err(err), reply_tx, Some(completion_sender)).await;
         }
     }
 }
+
+/// Run a `Query` or `VectorQuery` command, returning its results as a
+/// stream instead of sending them to the host.
+pub(crate) async fn open_result_stream(
+    connections: Sender<ConnectionCommand>,
+    tables: Sender<TableCommand>,
+    rerankers: Sender<RerankerCommand>,
+    query: LanceDbCommand,
+    reply_tx: ErrorReportFn,
+) -> Result<ResultStreamReader, String> {
+    let (stream_tx, stream_rx) = tokio::sync::oneshot::channel();
+    let (query_completion, query_result) = get_completion_pair();
+    match query {
+        LanceDbCommand::Query {
+            connection_handle,
+            table_handle,
+            limit,
+            pagination,
+            where_clause,
+            with_row_id,
+            selected_columns,
+            projections,
+            full_text_search,
+            batch_size,
+            ipc_format,
+            ..
+        } => {
+            do_query(
+                connection_handle,
+                connections,
+                tables,
+                rerankers.clone(),
+                table_handle,
+                reply_tx,
+                query_completion,
+                None,
+                limit,
+                pagination,
+                where_clause,
+                with_row_id,
+                None,
+                selected_columns,
+                projections,
+                None,
+                full_text_search,
+                batch_size,
+                ipc_format,
+                Some(stream_tx),
+            )
+            .await;
+        }
+        LanceDbCommand::VectorQuery {
+            connection_handle,
+            table_handle,
+            limit,
+            pagination,
+            where_clause,
+            with_row_id,
+            selected_columns,
+            projections,
+            vector_data,
+            metric,
+            n_probes,
+            refine_factor,
+            batch_size,
+            ipc_format,
+            distance_range_min,
+            distance_range_max,
+            postfilter,
+            fast_search,
+            ..
+        } => {
+            do_vector_query(
+                connection_handle,
+                connections,
+                tables,
+                rerankers.clone(),
+                table_handle,
+                reply_tx,
+                query_completion,
+                None,
+                limit,
+                pagination,
+                where_clause,
+                with_row_id,
+                None,
+                selected_columns,
+                projections,
+                None,
+                vector_data,
+                None,
+                metric,
+                n_probes,
+                refine_factor,
+                batch_size,
+                ipc_format,
+                distance_range_min,
+                distance_range_max,
+                postfilter,
+                fast_search,
+                Some(stream_tx),
+            )
+            .await;
+        }
+        other => return Err(format!("Expected a query, not {other:?}")),
+    }
+
+    match query_result.await {
+        Ok((code, _)) if code >= 0 => {}
+        Ok((_, err)) => return Err(err),
+        Err(_) => return Err("Query did not complete.".to_string()),
+    }
+    stream_rx
+        .await
+        .map_err(|_| "Query did not return a result stream.".to_string())
+}
@@ -0,0 +1,92 @@
+//! Provides the event-loop side of reranking: registering host rerankers,
+//! and gathering, reranking and sending query results.
+
+use crate::event_loop::{report_result, CompletionSender, ErrorReportFn};
+use crate::rerankers::{rerank, Reranker, RerankerCommand};
+use crate::serialization::{batch_to_bytes, IpcFormat};
+use crate::{BlobCallback, RerankCallback};
+use arrow_array::RecordBatch;
+use arrow_select::concat::concat_batches;
+use futures::TryStreamExt;
+use lancedb::query::ExecutableQuery;
+use tokio::sync::mpsc::Sender;
+use tokio::task::spawn_blocking;
+
+pub(crate) async fn do_register_reranker(
+    rerankers: Sender<RerankerCommand>,
+    name: String,
+    callback: RerankCallback,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+) {
+    let result = rerankers
+        .send(RerankerCommand::Register { name, callback })
+        .await
+        .map(|_| 0)
+        .map_err(|_| "Error sending reranker registration.".to_string());
+    report_result(result, reply_tx, Some(completion_sender)).await;
+}
+
+/// Run a query and gather its results into one batch.
+pub(crate) async fn collect_results(
+    query: &impl ExecutableQuery,
+    context: &str,
+) -> Result<RecordBatch, String> {
+    let stream = query
+        .execute()
+        .await
+        .map_err(|e| format!("Error running {context}: {e:?}"))?;
+    let schema = stream.schema();
+    let batches: Vec<RecordBatch> = stream
+        .try_collect()
+        .await
+        .map_err(|e| format!("Error reading {context} results: {e:?}"))?;
+    concat_batches(&schema, &batches).map_err(|e| format!("Error reading {context} results: {e:?}"))
+}
+
+/// Send results to `batch_callback`, in batches of `batch_size` rows if it
+/// is set, stopping early if the host asks to.
+async fn send_results(
+    results: &RecordBatch,
+    batch_callback: BlobCallback,
+    batch_size: u32,
+    ipc_format: IpcFormat,
+) -> Result<(), String> {
+    let Some(batch_callback) = batch_callback else {
+        return Ok(());
+    };
+    let rows = results.num_rows();
+    let batch_size = if batch_size > 0 { batch_size as usize } else { rows.max(1) };
+    for start in (0..rows).step_by(batch_size) {
+        let slice = results.slice(start, batch_size.min(rows - start));
+        let bytes = batch_to_bytes(&slice, &results.schema(), ipc_format)
+            .map_err(|_| "Unable to convert result to bytes".to_string())?;
+        let proceed = spawn_blocking(move || batch_callback(bytes.as_ptr(), bytes.len() as u64))
+            .await
+            .unwrap_or(false);
+        if !proceed {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Rerank vector results, full-text results or both, and send them to
+/// `batch_callback`. Returns the number of reranked rows.
+pub(crate) async fn send_reranked(
+    rerankers: Sender<RerankerCommand>,
+    reranker: &Reranker,
+    query_text: Option<String>,
+    vector: Option<&RecordBatch>,
+    fts: Option<&RecordBatch>,
+    limit: Option<usize>,
+    with_row_id: bool,
+    batch_callback: BlobCallback,
+    batch_size: u32,
+    ipc_format: IpcFormat,
+) -> Result<usize, String> {
+    let results =
+        rerank(rerankers, reranker, query_text, vector, fts, limit, with_row_id, ipc_format).await?;
+    send_results(&results, batch_callback, batch_size, ipc_format).await?;
+    Ok(results.num_rows())
+}
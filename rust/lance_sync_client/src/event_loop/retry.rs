@@ -0,0 +1,32 @@
+//! Provides the event-loop side of commit-conflict retry policies.
+
+use crate::connection_handler::ConnectionHandle;
+use crate::event_loop::connection::get_table;
+use crate::event_loop::{report_result, CompletionSender, ErrorReportFn};
+use crate::retry_handler::{RetryCommand, RetryPolicy};
+use crate::table_handler::{TableCommand, TableHandle};
+use tokio::sync::mpsc::Sender;
+
+pub(crate) async fn do_set_retry_policy(
+    retries: Sender<RetryCommand>,
+    tables: Sender<TableCommand>,
+    connection_handle: ConnectionHandle,
+    table_handle: TableHandle,
+    policy: Option<RetryPolicy>,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+) {
+    if get_table(tables, connection_handle, table_handle).await.is_none() {
+        let err = format!("Table not found: {table_handle:?}");
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    }
+    let _ = retries
+        .send(RetryCommand::SetPolicy {
+            connection_handle,
+            table_handle,
+            policy,
+        })
+        .await;
+    report_result(Ok(0), reply_tx, Some(completion_sender)).await;
+}
@@ -0,0 +1,182 @@
+//! Provides schema access and evolution: fetching the current schema, and
+//! adding, altering and dropping columns.
+
+use crate::connection_handler::ConnectionHandle;
+use crate::event_loop::connection::get_table;
+use crate::event_loop::{report_result, CompletionSender, ErrorReportFn};
+use crate::serialization::schema_to_bytes;
+use crate::table_handler::{TableCommand, TableHandle};
+use crate::BlobCallback;
+use arrow_schema::{DataType, SchemaRef};
+use lancedb::table::{ColumnAlteration, NewColumnTransform};
+use lancedb::Table;
+use tokio::sync::mpsc::Sender;
+use tokio::task::spawn_blocking;
+
+/// Ways to fill the values of newly added columns.
+#[derive(Debug)]
+pub(crate) enum NewColumns {
+    /// `(name, sql_expression)` pairs, evaluated against the existing columns.
+    SqlExpressions(Vec<(String, String)>),
+    /// Columns from the schema, with every value null.
+    AllNulls(SchemaRef),
+}
+
+impl From<NewColumns> for NewColumnTransform {
+    fn from(value: NewColumns) -> Self {
+        match value {
+            NewColumns::SqlExpressions(expressions) => Self::SqlExpressions(expressions),
+            NewColumns::AllNulls(schema) => Self::AllNulls(schema),
+        }
+    }
+}
+
+/// A change to a single existing column. Anything left as `None` is unchanged.
+#[derive(Debug)]
+pub(crate) struct ColumnChange {
+    pub(crate) path: String,
+    pub(crate) rename: Option<String>,
+    pub(crate) nullable: Option<bool>,
+    pub(crate) data_type: Option<DataType>,
+}
+
+impl From<ColumnChange> for ColumnAlteration {
+    fn from(value: ColumnChange) -> Self {
+        let mut alteration = ColumnAlteration::new(value.path);
+        if let Some(rename) = value.rename {
+            alteration = alteration.rename(rename);
+        }
+        if let Some(nullable) = value.nullable {
+            alteration = alteration.set_nullable(nullable);
+        }
+        if let Some(data_type) = value.data_type {
+            alteration = alteration.cast_to(data_type);
+        }
+        alteration
+    }
+}
+
+/// Send a schema to the host as IPC bytes.
+async fn send_schema(schema: &SchemaRef, schema_callback: BlobCallback) {
+    if let Some(cb) = schema_callback {
+        let schema_bytes = schema_to_bytes(schema);
+        let _ = spawn_blocking(move || {
+            cb(schema_bytes.as_ptr(), schema_bytes.len() as u64);
+        }).await;
+    }
+}
+
+/// Report the table's current schema, and complete the command.
+async fn report_schema(
+    table: &Table,
+    schema_callback: BlobCallback,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+) {
+    match table.schema().await {
+        Ok(schema) => {
+            send_schema(&schema, schema_callback).await;
+            report_result(Ok(0), reply_tx, Some(completion_sender)).await;
+        }
+        Err(e) => {
+            let err = format!("Error getting table schema: {:?}", e);
+            report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        }
+    }
+}
+
+pub(crate) async fn do_get_table_schema(
+    connection_handle: ConnectionHandle,
+    tables: Sender<TableCommand>,
+    table_handle: TableHandle,
+    schema_callback: BlobCallback,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+) {
+    // `get_table` checks out the latest version, so changes made by other
+    // writers are visible here.
+    let Some(table) = get_table(tables.clone(), connection_handle, table_handle).await else {
+        let err = format!("Table not found: {table_handle:?}");
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    };
+
+    report_schema(&table, schema_callback, reply_tx, completion_sender).await;
+}
+
+pub(crate) async fn do_add_columns(
+    connection_handle: ConnectionHandle,
+    tables: Sender<TableCommand>,
+    table_handle: TableHandle,
+    columns: NewColumns,
+    schema_callback: BlobCallback,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+) {
+    let Some(table) = get_table(tables.clone(), connection_handle, table_handle).await else {
+        let err = format!("Table not found: {table_handle:?}");
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    };
+
+    if let Err(e) = table.add_columns(columns.into(), None).await {
+        let err = format!("Error adding columns: {:?}", e);
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    }
+
+    report_schema(&table, schema_callback, reply_tx, completion_sender).await;
+}
+
+pub(crate) async fn do_alter_columns(
+    connection_handle: ConnectionHandle,
+    tables: Sender<TableCommand>,
+    table_handle: TableHandle,
+    changes: Vec<ColumnChange>,
+    schema_callback: BlobCallback,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+) {
+    let Some(table) = get_table(tables.clone(), connection_handle, table_handle).await else {
+        let err = format!("Table not found: {table_handle:?}");
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    };
+
+    let alterations = changes
+        .into_iter()
+        .map(ColumnAlteration::from)
+        .collect::<Vec<_>>();
+    if let Err(e) = table.alter_columns(&alterations).await {
+        let err = format!("Error altering columns: {:?}", e);
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    }
+
+    report_schema(&table, schema_callback, reply_tx, completion_sender).await;
+}
+
+pub(crate) async fn do_drop_columns(
+    connection_handle: ConnectionHandle,
+    tables: Sender<TableCommand>,
+    table_handle: TableHandle,
+    columns: Vec<String>,
+    schema_callback: BlobCallback,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+) {
+    let Some(table) = get_table(tables.clone(), connection_handle, table_handle).await else {
+        let err = format!("Table not found: {table_handle:?}");
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    };
+
+    let columns = columns.iter().map(|c| c.as_str()).collect::<Vec<&str>>();
+    if let Err(e) = table.drop_columns(&columns).await {
+        let err = format!("Error dropping columns: {:?}", e);
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    }
+
+    report_schema(&table, schema_callback, reply_tx, completion_sender).await;
+}
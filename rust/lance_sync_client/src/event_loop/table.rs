@@ -1,18 +1,32 @@
 use std::ffi::{c_char, CString};
 use crate::connection_handler::{ConnectionCommand, ConnectionHandle};
-use crate::event_loop::command::{IndexType, ScalarIndexType, WriteMode};
+use crate::bad_vectors::handle_bad_vectors_in_batches;
+use crate::embedding_handler::{add_embeddings_to_batches, get_embeddings, EmbeddingCommand};
+use crate::event_loop::command::{
+    BadVectorHandling, DeletePreview, IndexType, ScalarIndexType, WriteMode,
+};
 use crate::event_loop::connection::get_table;
 use crate::event_loop::{get_connection, report_result, CompletionSender, ErrorReportFn, MetricType};
+use crate::json_rows::{json_rows_to_batch, parse_json_rows};
+use crate::retry_handler::{get_retry_policy, retry_on_conflict, RetryCommand};
+use crate::schema_compat::{conform_batch, conform_batches, schema_differences};
+use crate::serialization::batch_to_bytes;
+use crate::sql_update::parse_sql_update;
 use crate::table_handler::{TableCommand, TableHandle};
-use arrow_array::{RecordBatch, RecordBatchIterator};
+use crate::write_buffer_handler::{get_write_buffer, WriteBufferCommand};
+use arrow_array::ffi_stream::ArrowArrayStreamReader;
+use arrow_array::{RecordBatch, RecordBatchIterator, RecordBatchReader};
 use arrow_schema::ArrowError;
+use futures::TryStreamExt;
 use lancedb::index::scalar::{
     BTreeIndexBuilder, BitmapIndexBuilder, FtsIndexBuilder, LabelListIndexBuilder,
 };
 use lancedb::index::Index;
+use lancedb::query::{ExecutableQuery, QueryBase};
 use lancedb::table::{OptimizeAction, OptimizeOptions};
-use lancedb::DistanceType;
+use lancedb::{DistanceType, Table};
 use tokio::sync::mpsc::Sender;
+use tokio::task::spawn_blocking;
 
 pub(crate) async fn do_count_rows(
     connections: Sender<ConnectionCommand>,
@@ -49,9 +63,102 @@ pub(crate) async fn do_count_rows(
 pub(crate) async fn do_add_record_batch(
     connection_handle: ConnectionHandle,
     tables: Sender<TableCommand>,
+    embeddings: Sender<EmbeddingCommand>,
+    write_buffers: Sender<WriteBufferCommand>,
+    retries: Sender<RetryCommand>,
     table_handle: TableHandle,
     write_mode: WriteMode,
     batch: Vec<Result<RecordBatch, ArrowError>>,
+    auto_cast: bool,
+    bad_vector_handling: BadVectorHandling,
+    fill_value: f32,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+) {
+    let Some(table) = get_table(tables.clone(), connection_handle, table_handle).await else {
+        let err = format!("Table not found: {table_handle:?}");
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    };
+
+    let Ok(schema) = table.schema().await else {
+        report_result(
+            Err("Error getting table schema".to_string()),
+            reply_tx,
+            Some(completion_sender),
+        ).await;
+        return;
+    };
+
+    // Bound vector columns that weren't supplied are computed by the host's embedding functions.
+    let bindings = get_embeddings(embeddings, connection_handle, table_handle).await;
+    let batch = match add_embeddings_to_batches(batch, &bindings, &schema).await {
+        Ok(batch) => batch,
+        Err(e) => {
+            report_result(Err(e), reply_tx, Some(completion_sender)).await;
+            return;
+        }
+    };
+
+    // Bad vectors are dealt with first, since a vector of the wrong length can't be cast.
+    let (batch, bad_vector_count) =
+        match handle_bad_vectors_in_batches(batch, &schema, bad_vector_handling, fill_value) {
+            Ok(result) => result,
+            Err(e) => {
+                report_result(Err(e), reply_tx, Some(completion_sender)).await;
+                return;
+            }
+        };
+
+    // Check the incoming data up-front, so that mismatches are reported clearly.
+    let batch = match conform_batches(batch, &schema, auto_cast) {
+        Ok(batch) => batch,
+        Err(e) => {
+            report_result(Err(e), reply_tx, Some(completion_sender)).await;
+            return;
+        }
+    };
+
+    // Appends to a table with a write buffer are committed later, by the buffer.
+    if let Some(buffer) = get_write_buffer(write_buffers, connection_handle, table_handle).await {
+        if let WriteMode::Append = write_mode {
+            let result = buffer.append(batch).await.map(|_| bad_vector_count as i64);
+            report_result(result, reply_tx, Some(completion_sender)).await;
+            return;
+        }
+        // Buffered rows were added before the overwrite, so they are committed first.
+        if let Err(e) = buffer.flush().await {
+            report_result(Err(e), reply_tx, Some(completion_sender)).await;
+            return;
+        }
+    }
+
+    // Each attempt adds the same (already conformed) batches again.
+    let policy = get_retry_policy(retries, connection_handle, table_handle).await;
+    let result = retry_on_conflict(policy.as_ref(), table_handle, "Error adding record batch", || {
+        let batch = RecordBatchIterator::new(batch.clone().into_iter().map(Ok), schema.clone());
+        table.add(batch).mode(write_mode.into()).execute()
+    })
+    .await;
+
+    // Report how many rows had bad vectors dropped or replaced.
+    let result = result.map(|_| bad_vector_count as i64);
+    report_result(result, reply_tx, Some(completion_sender)).await;
+}
+
+/// Add rows given as JSON. The rows are converted using the table's schema,
+/// then added exactly like a record batch, with missing columns filled.
+pub(crate) async fn do_add_json_rows(
+    connection_handle: ConnectionHandle,
+    tables: Sender<TableCommand>,
+    embeddings: Sender<EmbeddingCommand>,
+    write_buffers: Sender<WriteBufferCommand>,
+    retries: Sender<RetryCommand>,
+    table_handle: TableHandle,
+    write_mode: WriteMode,
+    json: String,
+    bad_vector_handling: BadVectorHandling,
+    fill_value: f32,
     reply_tx: ErrorReportFn,
     completion_sender: CompletionSender,
 ) {
@@ -61,8 +168,61 @@ pub(crate) async fn do_add_record_batch(
         return;
     };
 
-    // TODO: Implement bad vector handling
-    // TODO: Implement fill value
+    let Ok(schema) = table.schema().await else {
+        report_result(
+            Err("Error getting table schema".to_string()),
+            reply_tx,
+            Some(completion_sender),
+        ).await;
+        return;
+    };
+
+    let batch = spawn_blocking(move || {
+        parse_json_rows(&json).and_then(|rows| json_rows_to_batch(&rows, &schema))
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Error converting JSON rows: {e:?}")));
+    let batch = match batch {
+        Ok(batch) => batch,
+        Err(e) => {
+            report_result(Err(e), reply_tx, Some(completion_sender)).await;
+            return;
+        }
+    };
+
+    do_add_record_batch(
+        connection_handle,
+        tables,
+        embeddings,
+        write_buffers,
+        retries,
+        table_handle,
+        write_mode,
+        vec![Ok(batch)],
+        true,
+        bad_vector_handling,
+        fill_value,
+        reply_tx,
+        completion_sender,
+    )
+    .await;
+}
+
+pub(crate) async fn do_add_record_batch_stream(
+    connection_handle: ConnectionHandle,
+    tables: Sender<TableCommand>,
+    table_handle: TableHandle,
+    write_mode: WriteMode,
+    reader: ArrowArrayStreamReader,
+    auto_cast: bool,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+) {
+    let Some(table) = get_table(tables.clone(), connection_handle, table_handle).await else {
+        let err = format!("Table not found: {table_handle:?}");
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    };
 
     let Ok(schema) = table.schema().await else {
         report_result(
@@ -72,42 +232,122 @@ pub(crate) async fn do_add_record_batch(
         ).await;
         return;
     };
-    let batch = RecordBatchIterator::new(batch, schema);
-    let result = table.add(batch).mode(write_mode.into()).execute().await;
+
+    let differences = schema_differences(&reader.schema(), &schema, auto_cast);
+    if !differences.is_empty() {
+        let err = format!(
+            "Record batch stream does not match the table schema:\n{}",
+            differences.join("\n")
+        );
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    }
+
+    // Batches are conformed as they are read, so the stream is never held in memory.
+    let table_schema = schema.clone();
+    let batches = reader.map(move |batch| batch.and_then(|batch| conform_batch(&batch, &table_schema)));
+    let batches = RecordBatchIterator::new(batches, schema);
+    let result = table.add(batches).mode(write_mode.into()).execute().await;
 
     match result {
         Ok(_) => {
             report_result(Ok(0), reply_tx, Some(completion_sender)).await;
         }
         Err(e) => {
-            let err = format!("Error adding record batch: {:?}", e);
+            let err = format!("Error adding record batch stream: {:?}", e);
             report_result(Err(err), reply_tx, Some(completion_sender)).await;
         }
     }
 }
 
+/// Delete the rows matching `where_clause`, reporting how many were deleted.
+/// Without a filter, `delete_all` must be set. With `dry_run` set, nothing is
+/// deleted: the matching rows are counted, and a sample of them is sent back.
+/// The count is a best effort: it is taken just before the delete, so a write
+/// that commits in between can make it off.
 pub(crate) async fn do_delete_rows(
     connection_handle: ConnectionHandle,
     tables: Sender<TableCommand>,
+    retries: Sender<RetryCommand>,
     table_handle: TableHandle,
-    where_clause: String,
+    where_clause: Option<String>,
+    delete_all: bool,
+    dry_run: Option<DeletePreview>,
     reply_tx: ErrorReportFn,
     completion_sender: CompletionSender,
 ) {
+    let where_clause = where_clause.filter(|filter| !filter.trim().is_empty());
+    if where_clause.is_none() && !delete_all {
+        let err = "Deleting without a filter removes every row; set delete_all to confirm.".to_string();
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    }
     let Some(table) = get_table(tables.clone(), connection_handle, table_handle).await else {
         let err = format!("Table not found: {table_handle:?}");
         report_result(Err(err), reply_tx, Some(completion_sender)).await;
         return;
     };
-    match table.delete(&where_clause).await {
-        Ok(_) => {
-            report_result(Ok(0), reply_tx, Some(completion_sender)).await;
+
+    if let Some(preview) = dry_run {
+        let result = preview_delete(&table, where_clause, preview).await;
+        report_result(result, reply_tx, Some(completion_sender)).await;
+        return;
+    }
+
+    // The matching rows are counted just before each attempt, so that a retry
+    // reports the rows it actually deleted.
+    let predicate = where_clause.as_deref().unwrap_or("true");
+    let policy = get_retry_policy(retries, connection_handle, table_handle).await;
+    let result = retry_on_conflict(policy.as_ref(), table_handle, "Error deleting rows", || {
+        let (table, where_clause) = (&table, &where_clause);
+        async move {
+            let matching = table.count_rows(where_clause.clone()).await?;
+            table.delete(predicate).await.map(|_| matching as i64)
         }
-        Err(e) => {
-            let err = format!("Error deleting rows: {:?}", e);
-            report_result(Err(err), reply_tx, Some(completion_sender)).await;
+    })
+    .await;
+    // Report the number of rows deleted.
+    report_result(result, reply_tx, Some(completion_sender)).await;
+}
+
+/// Count the rows a delete would remove, and send up to `sample_limit` of
+/// them to the sample callback.
+async fn preview_delete(
+    table: &Table,
+    where_clause: Option<String>,
+    preview: DeletePreview,
+) -> Result<i64, String> {
+    let matching = table
+        .count_rows(where_clause.clone())
+        .await
+        .map_err(|e| format!("Error counting rows: {:?}", e))?;
+    let Some(sample_callback) = preview.sample_callback.filter(|_| preview.sample_limit > 0) else {
+        return Ok(matching as i64);
+    };
+
+    let mut query = table.query().limit(preview.sample_limit);
+    if let Some(where_clause) = where_clause {
+        query = query.only_if(where_clause);
+    }
+    let mut sample = query
+        .execute()
+        .await
+        .map_err(|e| format!("Error sampling rows: {:?}", e))?;
+    while let Some(batch) = sample
+        .try_next()
+        .await
+        .map_err(|e| format!("Error sampling rows: {:?}", e))?
+    {
+        let bytes = batch_to_bytes(&batch, &batch.schema(), preview.ipc_format)
+            .map_err(|_| "Unable to convert result to bytes".to_string())?;
+        let proceed = spawn_blocking(move || sample_callback(bytes.as_ptr(), bytes.len() as u64))
+            .await
+            .unwrap_or(false);
+        if !proceed {
+            break;
         }
     }
+    Ok(matching as i64)
 }
 
 pub(crate) async fn do_crate_scalar_index(
@@ -330,6 +570,7 @@ pub(crate) async fn do_optimize_table(
 pub(crate) async fn do_update(
     connection_handle: ConnectionHandle,
     tables: Sender<TableCommand>,
+    retries: Sender<RetryCommand>,
     table_handle: TableHandle,
     reply_tx: ErrorReportFn,
     completion_sender: CompletionSender,
@@ -343,28 +584,96 @@ pub(crate) async fn do_update(
         return;
     };
 
-    for (column, data) in updates {
+    // Every column goes into one builder, so the update is a single commit.
+    let policy = get_retry_policy(retries, connection_handle, table_handle).await;
+    let result = retry_on_conflict(policy.as_ref(), table_handle, "Error updating table", || {
         let mut update_builder = table.update();
         if let Some(where_clause) = &where_clause {
             update_builder = update_builder.only_if(where_clause);
         }
-        update_builder = update_builder.column(column, data);
-        match update_builder.execute().await {
-            Ok(num_rows) => {
-                if let Some(callback) = update_callback {
-                    callback(num_rows);
-                }
-            }
-            Err(e) => {
-                let err = format!("Error updating table: {:?}", e);
-                report_result(Err(err), reply_tx, Some(completion_sender)).await;
-                return;
+        for (column, expression) in &updates {
+            update_builder = update_builder.column(column, expression);
+        }
+        update_builder.execute()
+    })
+    .await;
+    match result {
+        Ok(num_rows) => {
+            if let Some(callback) = update_callback {
+                callback(num_rows);
             }
+            // Report the number of rows updated.
+            report_result(Ok(num_rows as i64), reply_tx, Some(completion_sender)).await;
+        }
+        Err(err) => {
+            report_result(Err(err), reply_tx, Some(completion_sender)).await;
         }
     }
+}
 
-    // Indicate that it is done
-    completion_sender.send((0, String::new())).unwrap();
+/// Run a SQL `UPDATE` statement against an open table. The statement must
+/// name the table the handle refers to, and only assign existing columns.
+pub(crate) async fn do_update_sql(
+    connection_handle: ConnectionHandle,
+    tables: Sender<TableCommand>,
+    retries: Sender<RetryCommand>,
+    table_handle: TableHandle,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+    sql: String,
+    update_callback: Option<extern "C" fn(u64)>,
+) {
+    let update = match parse_sql_update(&sql) {
+        Ok(update) => update,
+        Err(e) => {
+            report_result(Err(e), reply_tx, Some(completion_sender)).await;
+            return;
+        }
+    };
+    let Some(table) = get_table(tables.clone(), connection_handle, table_handle).await else {
+        let err = format!("Table not found: {table_handle:?}");
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    };
+    if !update.table_name.eq_ignore_ascii_case(table.name()) {
+        let err = format!(
+            "UPDATE targets table `{}`, but the handle refers to `{}`",
+            update.table_name,
+            table.name()
+        );
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    }
+    let schema = match table.schema().await {
+        Ok(schema) => schema,
+        Err(e) => {
+            let err = format!("Error getting table schema: {:?}", e);
+            report_result(Err(err), reply_tx, Some(completion_sender)).await;
+            return;
+        }
+    };
+    if let Some((column, _)) = update
+        .updates
+        .iter()
+        .find(|(column, _)| schema.field_with_name(column).is_err())
+    {
+        let err = format!("Column `{column}` is not in table `{}`", table.name());
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    }
+
+    do_update(
+        connection_handle,
+        tables,
+        retries,
+        table_handle,
+        reply_tx,
+        completion_sender,
+        update.where_clause,
+        update.updates,
+        update_callback,
+    )
+    .await;
 }
 
 pub(crate) async fn do_list_table_indices(
@@ -0,0 +1,88 @@
+//! Provides the event-loop side of per-table write buffers.
+
+use crate::connection_handler::ConnectionHandle;
+use crate::event_loop::connection::get_table;
+use crate::event_loop::{report_result, CompletionSender, ErrorReportFn};
+use crate::retry_handler::RetryCommand;
+use crate::table_handler::{TableCommand, TableHandle};
+use crate::write_buffer_handler::{get_write_buffer, WriteBufferCommand, WriteBufferPolicy};
+use tokio::sync::mpsc::Sender;
+
+pub(crate) async fn do_enable_write_buffer(
+    write_buffers: Sender<WriteBufferCommand>,
+    tables: Sender<TableCommand>,
+    retries: Sender<RetryCommand>,
+    connection_handle: ConnectionHandle,
+    table_handle: TableHandle,
+    policy: WriteBufferPolicy,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+) {
+    if policy.max_rows.is_none() && policy.max_bytes.is_none() && policy.flush_interval.is_none() {
+        let err = "A write buffer needs a row count, byte size or flush interval.".to_string();
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    }
+    let Some(table) = get_table(tables, connection_handle, table_handle).await else {
+        let err = format!("Table not found: {table_handle:?}");
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    };
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let _ = write_buffers
+        .send(WriteBufferCommand::Enable {
+            connection_handle,
+            table_handle,
+            table,
+            policy,
+            retries,
+            reply_sender: tx,
+        })
+        .await;
+    let result = match rx.await {
+        Ok(result) => result.map(|_| 0),
+        Err(_) => Err("Error enabling write buffer.".to_string()),
+    };
+    report_result(result, reply_tx, Some(completion_sender)).await;
+}
+
+pub(crate) async fn do_disable_write_buffer(
+    write_buffers: Sender<WriteBufferCommand>,
+    connection_handle: ConnectionHandle,
+    table_handle: TableHandle,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+) {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let _ = write_buffers
+        .send(WriteBufferCommand::Disable {
+            connection_handle,
+            table_handle,
+            reply_sender: tx,
+        })
+        .await;
+    let result = match rx.await {
+        // Report the number of rows committed by the final flush.
+        Ok(result) => result.map(|rows| rows as i64),
+        Err(_) => Err("Error disabling write buffer.".to_string()),
+    };
+    report_result(result, reply_tx, Some(completion_sender)).await;
+}
+
+pub(crate) async fn do_flush_write_buffer(
+    write_buffers: Sender<WriteBufferCommand>,
+    connection_handle: ConnectionHandle,
+    table_handle: TableHandle,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+) {
+    let Some(buffer) = get_write_buffer(write_buffers, connection_handle, table_handle).await else {
+        let err = format!("No write buffer is enabled for {table_handle:?}");
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    };
+    // Report the number of rows committed.
+    let result = buffer.flush().await.map(|rows| rows as i64);
+    report_result(result, reply_tx, Some(completion_sender)).await;
+}
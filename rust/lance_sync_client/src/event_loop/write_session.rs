@@ -0,0 +1,154 @@
+//! Provides the event-loop side of streaming write sessions.
+
+use crate::connection_handler::ConnectionHandle;
+use crate::event_loop::command::WriteMode;
+use crate::event_loop::connection::get_table;
+use crate::event_loop::{report_result, CompletionSender, ErrorReportFn};
+use crate::schema_compat::conform_batches;
+use crate::table_handler::{TableCommand, TableHandle};
+use crate::write_session_handler::{WriteSession, WriteSessionCommand, WriteSessionHandle};
+use arrow_array::RecordBatch;
+use arrow_schema::ArrowError;
+use tokio::sync::mpsc::Sender;
+use tokio::task::spawn_blocking;
+
+pub(crate) async fn do_begin_write(
+    sessions: Sender<WriteSessionCommand>,
+    tables: Sender<TableCommand>,
+    connection_handle: ConnectionHandle,
+    table_handle: TableHandle,
+    write_mode: WriteMode,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+) {
+    let Some(table) = get_table(tables.clone(), connection_handle, table_handle).await else {
+        let err = format!("Table not found: {table_handle:?}");
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    };
+    let schema = match table.schema().await {
+        Ok(schema) => schema,
+        Err(e) => {
+            let err = format!("Error getting table schema: {:?}", e);
+            report_result(Err(err), reply_tx, Some(completion_sender)).await;
+            return;
+        }
+    };
+
+    // The add runs for the lifetime of the session, reading from the channel.
+    let (reader, session) = WriteSession::channel(schema);
+    let task = tokio::spawn(async move {
+        table
+            .add(reader)
+            .mode(write_mode.into())
+            .execute()
+            .await
+            .map_err(|e| format!("Error adding record batch: {:?}", e))
+    });
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    if sessions
+        .send(WriteSessionCommand::Begin {
+            session: session(task),
+            reply_sender: tx,
+        })
+        .await
+        .is_err()
+    {
+        let err = "Error sending write session.".to_string();
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    }
+    let result = match rx.await {
+        Ok(handle) => Ok(handle.0),
+        Err(_) => Err("Error receiving write session handle.".to_string()),
+    };
+    report_result(result, reply_tx, Some(completion_sender)).await;
+}
+
+pub(crate) async fn do_write_batch(
+    sessions: Sender<WriteSessionCommand>,
+    handle: WriteSessionHandle,
+    batch: Vec<Result<RecordBatch, ArrowError>>,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+) {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let _ = sessions
+        .send(WriteSessionCommand::GetSender {
+            handle,
+            reply_sender: tx,
+        })
+        .await;
+    let Ok(Some((schema, sender))) = rx.await else {
+        let err = format!("Write session not found: {handle:?}");
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    };
+
+    // Every batch must have the table's schema, since they share one reader.
+    let batch = match conform_batches(batch, &schema, false) {
+        Ok(batch) => batch,
+        Err(e) => {
+            report_result(Err(e), reply_tx, Some(completion_sender)).await;
+            return;
+        }
+    };
+
+    // Sending waits while the queue is full, which keeps memory use bounded.
+    let result = spawn_blocking(move || {
+        for batch in batch {
+            if sender.send(batch).is_err() {
+                return Err("Write session has failed; commit it to get the error.".to_string());
+            }
+        }
+        Ok(0)
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Error sending record batch: {e:?}")));
+    report_result(result, reply_tx, Some(completion_sender)).await;
+}
+
+async fn finish_session(
+    sessions: Sender<WriteSessionCommand>,
+    handle: WriteSessionHandle,
+) -> Option<WriteSession> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let _ = sessions
+        .send(WriteSessionCommand::Finish {
+            handle,
+            reply_sender: tx,
+        })
+        .await;
+    rx.await.ok().flatten()
+}
+
+pub(crate) async fn do_commit_write(
+    sessions: Sender<WriteSessionCommand>,
+    handle: WriteSessionHandle,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+) {
+    let Some(session) = finish_session(sessions, handle).await else {
+        let err = format!("Write session not found: {handle:?}");
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    };
+    let result = session.commit().await.map(|_| 0);
+    report_result(result, reply_tx, Some(completion_sender)).await;
+}
+
+pub(crate) async fn do_abort_write(
+    sessions: Sender<WriteSessionCommand>,
+    handle: WriteSessionHandle,
+    reply_tx: ErrorReportFn,
+    completion_sender: CompletionSender,
+) {
+    let Some(session) = finish_session(sessions, handle).await else {
+        let err = format!("Write session not found: {handle:?}");
+        report_result(Err(err), reply_tx, Some(completion_sender)).await;
+        return;
+    };
+    session.abort().await;
+    report_result(Ok(0), reply_tx, Some(completion_sender)).await;
+}
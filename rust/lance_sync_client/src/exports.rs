@@ -1,17 +1,55 @@
 //! Module containing all the FFI exports for LanceDB.
 //! These are using the C ABI and are intended to be used by other languages.
 
+use crate::c_data::{import_batch, import_stream, ResultStreamReader};
 use crate::command_from_ffi;
 use crate::connection_handler::ConnectionHandle;
+use crate::cursor_handler::CursorHandle;
+use crate::embedding_handler::{EmbeddingBinding, EmbeddingFunction};
 use crate::event_loop::{report_result_sync, ErrorReportFn, LanceDbCommand, MetricType, VectorDataType};
-use crate::serialization::{bytes_to_batch, bytes_to_schema};
+use crate::serialization::{bytes_to_batch, bytes_to_schema, IpcFormat};
 use crate::table_handler::TableHandle;
+use crate::write_session_handler::WriteSessionHandle;
+use arrow_array::ffi::{FFI_ArrowArray, FFI_ArrowSchema};
+use arrow_array::ffi_stream::FFI_ArrowArrayStream;
 use std::ffi::c_char;
-use crate::event_loop::command::{ScalarIndexType, WriteMode};
+use crate::event_loop::command::{
+    BadVectorHandling, BatchSearch, DeletePreview, MergeInsertClauses, Pagination, ScalarIndexType,
+    WriteMode,
+};
+use crate::file_export::{ExportCompression, ExportFormat, ExportOptions};
+use crate::file_import::{ImportFormat, ImportOptions};
+use crate::event_loop::schema::{ColumnChange, NewColumns};
+use crate::maintenance_handler::{MaintenanceHandle, MaintenancePolicy};
+use crate::rerankers::{Reranker, RerankerKind, DEFAULT_RRF_K, DEFAULT_VECTOR_WEIGHT};
+use crate::retry_handler::RetryPolicy;
+use crate::write_buffer_handler::WriteBufferPolicy;
 
 /// Defines a function type for a "blob" callback: a bunch of bytes and a length.
 pub type BlobCallback = Option<extern "C" fn(bytes: *const u8, len: u64) -> bool>;
 
+/// Defines a function type for a host embedding function. It receives `text_count`
+/// strings, and writes `text_count * dimension` floats into `vectors`. Returns
+/// false if the embedding failed.
+pub type EmbeddingCallback = extern "C" fn(
+    texts: *const *const c_char,
+    text_count: u64,
+    vectors: *mut f32,
+    dimension: u64,
+) -> bool;
+
+/// Defines a function type for a host reranker. It receives the query text
+/// (null if there is none, as for a vector query) and `row_count` candidate
+/// rows as Arrow IPC bytes, and writes one score per row into `scores`,
+/// higher being more relevant. Returns false if reranking failed.
+pub type RerankCallback = extern "C" fn(
+    query_text: *const c_char,
+    candidates: *const u8,
+    len: u64,
+    scores: *mut f32,
+    row_count: u64,
+) -> bool;
+
 /// Connect to a LanceDB database. This function will return a handle
 /// to the connection, which can be used in other functions.
 ///
@@ -219,7 +257,17 @@ pub extern "C" fn rename_table(
     );
 }
 
-/// Add a record batch to a table
+/// Add a record batch to a table. The batch is checked against the table's
+/// schema first, and any differences are reported field-by-field. If
+/// `auto_cast` is set, columns are cast to the table's types where that is
+/// safe (e.g. Float64 to Float32, Int32 to Int64, Utf8 to LargeUtf8 or a list
+/// of floats to a fixed-size vector), and missing nullable columns are filled
+/// with nulls.
+///
+/// Vectors with the wrong dimension, or containing NaN or infinite values, are
+/// handled according to `bad_vector_handling` (1 = error, 2 = drop the row,
+/// 3 = fill with `fill_value`, 4 = replace with null). On success, the result
+/// code is the number of rows that were dropped or replaced.
 #[no_mangle]
 pub extern "C" fn add_record_batch(
     connection_handle: i64,
@@ -227,6 +275,9 @@ pub extern "C" fn add_record_batch(
     data: *const u8,
     len: usize,
     write_mode: u32,
+    auto_cast: bool,
+    bad_vector_handling: u32,
+    fill_value: f32,
     reply_tx: ErrorReportFn,
 ) {
     let data = unsafe { std::slice::from_raw_parts(data, len) };
@@ -243,12 +294,19 @@ pub extern "C" fn add_record_batch(
         report_result_sync(Err("Invalid write mode.".to_string()), reply_tx, None);
         return;
     };
+    let Some(bad_vector_handling) = BadVectorHandling::from_repr(bad_vector_handling) else {
+        report_result_sync(Err("Invalid bad vector handling mode.".to_string()), reply_tx, None);
+        return;
+    };
     command_from_ffi!(
         LanceDbCommand::AddRecordBatch {
             connection_handle: ConnectionHandle(connection_handle),
             table_handle: TableHandle(table_handle),
             write_mode,
             batch: batch.unwrap(),
+            auto_cast,
+            bad_vector_handling,
+            fill_value,
         },
         "AddRecordBatch",
         reply_tx
@@ -256,11 +314,26 @@ pub extern "C" fn add_record_batch(
 }
 
 /// Delete rows from a table
+///
+/// A null or empty `filter` deletes every row, so `delete_all` must be set to
+/// allow it. With `dry_run` set nothing is deleted: up to `sample_limit` of the
+/// matching rows are sent to `sample_callback` (as Arrow IPC in `ipc_format`:
+/// 1 = file, 2 = stream), and the count is reported instead.
+///
+/// Return values:
+/// - The number of rows deleted (or that would be), or -1 if an error occurred. The
+///   rows are counted just before the delete, so a write that commits in between can
+///   make the count off.
 #[no_mangle]
 pub extern "C" fn delete_rows(
     connection_handle: i64,
     table_handle: i64,
     filter: *const c_char,
+    delete_all: bool,
+    dry_run: bool,
+    sample_limit: u64,
+    sample_callback: BlobCallback,
+    ipc_format: u32,
     reply_tx: ErrorReportFn,
 ) {
     let where_clause = if filter.is_null() {
@@ -272,11 +345,26 @@ pub extern "C" fn delete_rows(
                 .to_string()
         })
     };
+    let dry_run = if dry_run {
+        let Some(ipc_format) = IpcFormat::from_repr(ipc_format) else {
+            report_result_sync(Err("Invalid IPC format.".to_string()), reply_tx, None);
+            return;
+        };
+        Some(DeletePreview {
+            sample_limit: sample_limit as usize,
+            sample_callback,
+            ipc_format,
+        })
+    } else {
+        None
+    };
     command_from_ffi!(
         LanceDbCommand::DeleteRows {
             connection_handle: ConnectionHandle(connection_handle),
             table_handle: TableHandle(table_handle),
             where_clause,
+            delete_all,
+            dry_run,
         },
         "DeleteRows",
         reply_tx
@@ -453,7 +541,109 @@ pub extern "C" fn optimize_table(
     );
 }
 
+/// Build the paging options shared by the query functions. An `offset` of 0
+/// and a null `page_token` start from the first row.
+fn pagination(
+    offset: u64,
+    page_token: *const c_char,
+    next_page_callback: Option<extern "C" fn(*const c_char)>,
+) -> Pagination {
+    Pagination {
+        offset: if offset == 0 { None } else { Some(offset as usize) },
+        page_token: if page_token.is_null() {
+            None
+        } else {
+            Some(unsafe {
+                std::ffi::CStr::from_ptr(page_token)
+                    .to_string_lossy()
+                    .to_string()
+            })
+        },
+        next_page_callback,
+    }
+}
+
+/// Read a reranker choice: `kind` is 0 for none, 1 for Reciprocal Rank
+/// Fusion (`param` is `k`, 60 if 0 or less), 2 for linear combination
+/// (`param` is the vector search's weight from 0 to 1, 0.7 if NaN) or 3 for
+/// the host reranker registered as `name`.
+fn reranker_from_ffi(kind: u32, param: f32, name: *const c_char) -> Result<Option<Reranker>, String> {
+    if kind == 0 {
+        return Ok(None);
+    }
+    let Some(kind) = RerankerKind::from_repr(kind) else {
+        return Err("Invalid reranker.".to_string());
+    };
+    let reranker = match kind {
+        RerankerKind::ReciprocalRankFusion => Reranker::ReciprocalRankFusion {
+            k: if param > 0.0 { param } else { DEFAULT_RRF_K },
+        },
+        RerankerKind::LinearCombination if param.is_nan() => Reranker::LinearCombination {
+            vector_weight: DEFAULT_VECTOR_WEIGHT,
+        },
+        RerankerKind::LinearCombination if (0.0..=1.0).contains(&param) => {
+            Reranker::LinearCombination { vector_weight: param }
+        }
+        RerankerKind::LinearCombination => {
+            return Err("Linear combination weight must be between 0 and 1.".to_string())
+        }
+        RerankerKind::Host if name.is_null() => return Err("A host reranker needs a name.".to_string()),
+        RerankerKind::Host => Reranker::Host {
+            name: unsafe { std::ffi::CStr::from_ptr(name).to_string_lossy().to_string() },
+        },
+    };
+    Ok(Some(reranker))
+}
+
+/// Read computed columns from parallel arrays of aliases and SQL
+/// expressions. Null arrays (or a length of 0) mean there are none.
+fn projections(
+    aliases: *const *const c_char,
+    expressions: *const *const c_char,
+    len: u64,
+) -> Option<Vec<(String, String)>> {
+    if aliases.is_null() || expressions.is_null() || len == 0 {
+        return None;
+    }
+    let mut projections = Vec::with_capacity(len as usize);
+    for i in 0..len {
+        let (alias, expression) = unsafe {
+            (
+                std::ffi::CStr::from_ptr(*aliases.offset(i as isize))
+                    .to_string_lossy()
+                    .to_string(),
+                std::ffi::CStr::from_ptr(*expressions.offset(i as isize))
+                    .to_string_lossy()
+                    .to_string(),
+            )
+        };
+        projections.push((alias, expression));
+    }
+    Some(projections)
+}
+
 /// Initial query code
+///
+/// `ipc_format` selects the Arrow IPC format of the batches sent to
+/// `batch_callback`: 1 = file, 2 = stream (no footer).
+///
+/// Paging: `offset` skips that many rows. For stable pages under concurrent
+/// writes, set `next_page_callback` (with a `limit`): it receives an opaque
+/// token, or null after the last page. Passing the token back as `page_token`
+/// reads the next page from the same table version. For cursors, the token
+/// is sent from another thread once the results have been read to the end,
+/// which may be after the call returns or the cursor is closed: the host must
+/// keep the callback (and any delegate behind it) alive until it is called.
+/// It is not called if reading the results fails.
+///
+/// Computed columns: `projection_aliases` and `projection_expressions` are
+/// parallel arrays of `projections_len` entries, returning each SQL
+/// expression (such as `price * qty`) as a column named by its alias,
+/// alongside any `selected_columns`.
+///
+/// Reranking (full-text queries only): `reranker` is 0 for none, or a
+/// reranker as for `hybrid_query`. Reranked results are sent together, once
+/// all are scored, with a `_relevance_score` column.
 #[no_mangle]
 pub extern "C" fn query(
     connection_handle: i64,
@@ -467,7 +657,30 @@ pub extern "C" fn query(
     selected_columns_len: u64,
     full_text_search: *const c_char,
     batch_size: u32,
+    ipc_format: u32,
+    offset: u64,
+    page_token: *const c_char,
+    next_page_callback: Option<extern "C" fn(*const c_char)>,
+    projection_aliases: *const *const c_char,
+    projection_expressions: *const *const c_char,
+    projections_len: u64,
+    reranker: u32,
+    reranker_param: f32,
+    reranker_name: *const c_char,
 ) {
+    let reranker = match reranker_from_ffi(reranker, reranker_param, reranker_name) {
+        Ok(reranker) => reranker,
+        Err(err) => {
+            report_result_sync(Err(err), reply_tx, None);
+            return;
+        }
+    };
+    let projections = projections(projection_aliases, projection_expressions, projections_len);
+    let pagination = pagination(offset, page_token, next_page_callback);
+    let Some(ipc_format) = IpcFormat::from_repr(ipc_format) else {
+        report_result_sync(Err("Invalid IPC format.".to_string()), reply_tx, None);
+        return;
+    };
     let where_clause = if where_clause.is_null() {
         None
     } else {
@@ -514,12 +727,17 @@ pub extern "C" fn query(
             } else {
                 Some(limit as usize)
             },
+            pagination,
             where_clause,
             with_row_id,
             explain_callback: None,
             selected_columns,
+            projections,
+            reranker,
             full_text_search,
             batch_size,
+            ipc_format,
+            stream_sender: None,
         },
         "Query",
         reply_tx
@@ -527,6 +745,33 @@ pub extern "C" fn query(
 }
 
 /// Initial query code
+///
+/// `ipc_format` selects the Arrow IPC format of the batches sent to
+/// `batch_callback`: 1 = file, 2 = stream (no footer).
+///
+/// Paging: `offset` skips that many rows. For stable pages under concurrent
+/// writes, set `next_page_callback` (with a `limit`): it receives an opaque
+/// token, or null after the last page. Passing the token back as `page_token`
+/// reads the next page from the same table version. For cursors, the token
+/// is sent from another thread once the results have been read to the end,
+/// which may be after the call returns or the cursor is closed: the host must
+/// keep the callback (and any delegate behind it) alive until it is called.
+/// It is not called if reading the results fails.
+///
+/// Computed columns: `projection_aliases` and `projection_expressions` are
+/// parallel arrays of `projections_len` entries, returning each SQL
+/// expression (such as `price * qty`) as a column named by its alias,
+/// alongside any `selected_columns`.
+///
+/// Filtering: by default `where_clause` picks the rows to search. Set
+/// `postfilter` to search first and filter the nearest matches instead,
+/// which is cheaper for broad filters but can return fewer than `limit`
+/// rows. Set `fast_search` to search only indexed data, skipping rows added
+/// since the index was last built.
+///
+/// Reranking: `reranker` is 0 for none, or a reranker as for `hybrid_query`.
+/// Reranked results are sent together, once all are scored, with a
+/// `_relevance_score` column.
 #[no_mangle]
 pub extern "C" fn vector_query(
     connection_handle: i64,
@@ -548,11 +793,36 @@ pub extern "C" fn vector_query(
     batch_size: u32,
     distance_range_min: f32,
     distance_range_max: f32,
+    ipc_format: u32,
+    offset: u64,
+    page_token: *const c_char,
+    next_page_callback: Option<extern "C" fn(*const c_char)>,
+    projection_aliases: *const *const c_char,
+    projection_expressions: *const *const c_char,
+    projections_len: u64,
+    postfilter: bool,
+    fast_search: bool,
+    reranker: u32,
+    reranker_param: f32,
+    reranker_name: *const c_char,
 ) {
+    let reranker = match reranker_from_ffi(reranker, reranker_param, reranker_name) {
+        Ok(reranker) => reranker,
+        Err(err) => {
+            report_result_sync(Err(err), reply_tx, None);
+            return;
+        }
+    };
+    let projections = projections(projection_aliases, projection_expressions, projections_len);
+    let pagination = pagination(offset, page_token, next_page_callback);
     let Some(metric) = MetricType::from_repr(metric) else {
         report_result_sync(Err("Invalid metric.".to_string()), reply_tx, None);
         return;
     };
+    let Some(ipc_format) = IpcFormat::from_repr(ipc_format) else {
+        report_result_sync(Err("Invalid IPC format.".to_string()), reply_tx, None);
+        return;
+    };
     let where_clause = if where_clause.is_null() {
         None
     } else {
@@ -596,15 +866,20 @@ pub extern "C" fn vector_query(
             } else {
                 Some(limit as usize)
             },
+            pagination,
             where_clause,
             with_row_id,
             explain_callback: None,
             selected_columns,
+            projections,
+            reranker,
             vector_data,
             metric: metric.into(),
             n_probes: n_probes as usize,
             refine_factor,
             batch_size,
+            ipc_format,
+            stream_sender: None,
             distance_range_min: if distance_range_min.is_nan() {
                 None
             } else {
@@ -615,6 +890,8 @@ pub extern "C" fn vector_query(
             } else {
                 Some(distance_range_max)
             },
+            postfilter,
+            fast_search,
         },
         "Query",
         reply_tx
@@ -682,19 +959,24 @@ pub extern "C" fn explain_query(
             } else {
                 Some(limit as usize)
             },
+            pagination: Pagination::default(),
             where_clause,
             with_row_id,
             explain_callback: Some((verbose, explain_callback)),
             selected_columns,
+            projections: None,
+            reranker: None,
             full_text_search,
             batch_size: 0,
+            ipc_format: IpcFormat::File,
+            stream_sender: None,
         },
         "ExplainQuery",
         reply_tx
     );
 }
 
-/// Explain a vector query
+/// Explain a vector query. `postfilter` and `fast_search` are as for `vector_query`.
 #[no_mangle]
 pub extern "C" fn explain_vector_query(
     connection_handle: i64,
@@ -716,6 +998,8 @@ pub extern "C" fn explain_vector_query(
     refine_factor: u32,
     distance_range_min: f32,
     distance_range_max: f32,
+    postfilter: bool,
+    fast_search: bool,
 ) {
     let Some(metric) = MetricType::from_repr(metric) else {
         report_result_sync(Err("Invalid metric.".to_string()), reply_tx, None);
@@ -764,15 +1048,20 @@ pub extern "C" fn explain_vector_query(
             } else {
                 Some(limit as usize)
             },
+            pagination: Pagination::default(),
             where_clause,
             with_row_id,
             explain_callback: Some((verbose, explain_callback)),
             selected_columns,
+            projections: None,
+            reranker: None,
             vector_data,
             metric: metric.into(),
             n_probes: n_probes as usize,
             refine_factor,
             batch_size: 0,
+            ipc_format: IpcFormat::File,
+            stream_sender: None,
             distance_range_min: if distance_range_min.is_nan() {
                 None
             } else {
@@ -783,26 +1072,54 @@ pub extern "C" fn explain_vector_query(
             } else {
                 Some(distance_range_max)
             },
+            postfilter,
+            fast_search,
         },
         "Query",
         reply_tx
     );
 }
 
-/// MergeInsert with a record batch
+/// MergeInsert with a record batch. Bad vectors are handled as for
+/// `add_record_batch`, and on success the result code is the number of rows
+/// that were dropped or replaced.
+///
+/// Each clause runs only if it is enabled, and at least one must be:
+/// - `when_matched_update_all`: update rows that match on `columns`, only where
+///   `when_matched_update_condition` holds if it isn't null.
+/// - `when_not_matched_insert_all`: insert rows that don't match.
+/// - `when_not_matched_by_source_delete`: delete table rows that no incoming row matches,
+///   only where `when_not_matched_by_source_delete_filter` holds if it isn't null.
+///
+/// Statistics are opt-in. LanceDB 0.17 doesn't return the counts Lance gathers while
+/// merging, so with `predict_stats` set they are predicted by reading the key columns of
+/// the whole table before each attempt, which costs a scan of the table. `stats_callback`
+/// (which `predict_stats` needs) then receives the number of rows inserted, updated (-1 if
+/// there is an update condition, since it can't be known) and deleted. They are a best
+/// effort: a write that commits between the count and the merge can make them off.
 #[no_mangle]
 pub extern "C" fn merge_insert_with_record_batch(
     connection_handle: i64,
     table_handle: i64,
     columns: *const *const c_char,
     columns_len: u64,
+    when_matched_update_all: bool,
+    when_matched_update_condition: *const c_char,
     when_not_matched_insert_all: bool,
-    where_clause: *const c_char,
-    when_not_matched_by_source_delete: *const c_char,
+    when_not_matched_by_source_delete: bool,
+    when_not_matched_by_source_delete_filter: *const c_char,
     batch: *const u8,
     batch_len: usize,
+    bad_vector_handling: u32,
+    fill_value: f32,
+    predict_stats: bool,
+    stats_callback: Option<extern "C" fn(inserted: u64, updated: i64, deleted: u64)>,
     reply_tx: ErrorReportFn,
 ) {
+    if predict_stats && stats_callback.is_none() {
+        report_result_sync(Err("predict_stats needs a stats_callback.".to_string()), reply_tx, None);
+        return;
+    }
     let columns: Option<Vec<String>> = if columns.is_null() {
         None
     } else {
@@ -818,21 +1135,21 @@ pub extern "C" fn merge_insert_with_record_batch(
         Some(column_list)
     };
 
-    let where_clause = if where_clause.is_null() {
+    let when_matched_update_condition = if when_matched_update_condition.is_null() {
         None
     } else {
         Some(unsafe {
-            std::ffi::CStr::from_ptr(where_clause)
+            std::ffi::CStr::from_ptr(when_matched_update_condition)
                 .to_string_lossy()
                 .to_string()
         })
     };
 
-    let when_not_matched_by_source_delete = if when_not_matched_by_source_delete.is_null() {
+    let when_not_matched_by_source_delete_filter = if when_not_matched_by_source_delete_filter.is_null() {
         None
     } else {
         Some(unsafe {
-            std::ffi::CStr::from_ptr(when_not_matched_by_source_delete)
+            std::ffi::CStr::from_ptr(when_not_matched_by_source_delete_filter)
                 .to_string_lossy()
                 .to_string()
         })
@@ -849,45 +1166,66 @@ pub extern "C" fn merge_insert_with_record_batch(
         return;
     }
     let batch = batch.unwrap();
+    let Some(bad_vector_handling) = BadVectorHandling::from_repr(bad_vector_handling) else {
+        report_result_sync(Err("Invalid bad vector handling mode.".to_string()), reply_tx, None);
+        return;
+    };
     command_from_ffi!(
         LanceDbCommand::MergeInsert {
             connection_handle: ConnectionHandle(connection_handle),
             table_handle: TableHandle(table_handle),
             columns,
-            when_not_matched_insert_all,
-            where_clause,
-            when_not_matched_by_source_delete,
+            clauses: MergeInsertClauses {
+                when_matched_update_all,
+                when_matched_update_condition,
+                when_not_matched_insert_all,
+                when_not_matched_by_source_delete,
+                when_not_matched_by_source_delete_filter,
+            },
             batch,
+            bad_vector_handling,
+            fill_value,
+            stats_callback: stats_callback.filter(|_| predict_stats),
         },
         "MergeInsert",
         reply_tx
     );
 }
 
-/// Update rows in a table
+/// Update rows in a table. `columns` and `expressions` are parallel arrays of
+/// `updates_len` entries: each column is set to the result of its SQL
+/// expression. All the columns are updated together, in a single commit.
+///
+/// Return values:
+/// - The number of rows updated (also passed to `callback`), or -1 if an error occurred.
 #[no_mangle]
 pub extern "C" fn update_rows(
     connection_handle: i64,
     table_handle: i64,
-    updates: *const *const c_char,
+    columns: *const *const c_char,
+    expressions: *const *const c_char,
     updates_len: u64,
     where_clause: *const c_char,
     reply_tx: ErrorReportFn,
     callback: Option<extern "C" fn(u64)>,
 ) {
+    if updates_len == 0 || columns.is_null() || expressions.is_null() {
+        report_result_sync(Err("No columns to update.".to_string()), reply_tx, None);
+        return;
+    }
     let mut update_list: Vec<(String, String)> = Vec::new();
     for i in 0..updates_len {
-        let update = unsafe {
-            std::ffi::CStr::from_ptr(*updates.offset(i as isize))
-                .to_string_lossy()
-                .to_string()
+        let (column, expression) = unsafe {
+            (
+                std::ffi::CStr::from_ptr(*columns.offset(i as isize))
+                    .to_string_lossy()
+                    .to_string(),
+                std::ffi::CStr::from_ptr(*expressions.offset(i as isize))
+                    .to_string_lossy()
+                    .to_string(),
+            )
         };
-        let parts: Vec<&str> = update.split('=').collect();
-        if parts.len() != 2 {
-            report_result_sync(Err("Invalid update statement".to_string()), reply_tx, None);
-            return;
-        }
-        update_list.push((parts[0].to_string(), parts[1].to_string()));
+        update_list.push((column, expression));
     }
 
     let where_clause = if where_clause.is_null() {
@@ -913,6 +1251,39 @@ pub extern "C" fn update_rows(
     );
 }
 
+/// Run a SQL `UPDATE` statement, such as
+/// `UPDATE items SET price = price * 2 WHERE id < 10`, against an open table.
+/// The statement must name the table, and every assigned column must exist.
+/// All the columns are updated together, in a single commit.
+///
+/// Return values:
+/// - The number of rows updated (also passed to `callback`), or -1 if an error occurred.
+#[no_mangle]
+pub extern "C" fn update_sql(
+    connection_handle: i64,
+    table_handle: i64,
+    sql: *const c_char,
+    reply_tx: ErrorReportFn,
+    callback: Option<extern "C" fn(u64)>,
+) {
+    if sql.is_null() {
+        report_result_sync(Err("No UPDATE statement.".to_string()), reply_tx, None);
+        return;
+    }
+    let sql = unsafe { std::ffi::CStr::from_ptr(sql).to_string_lossy().to_string() };
+
+    command_from_ffi!(
+        LanceDbCommand::UpdateSql {
+            connection_handle: ConnectionHandle(connection_handle),
+            table_handle: TableHandle(table_handle),
+            sql,
+            update_callback: callback,
+        },
+        "UpdateSql",
+        reply_tx
+    );
+}
+
 /// List indices in a table
 #[no_mangle]
 pub extern "C" fn list_indices(
@@ -956,4 +1327,1864 @@ pub extern "C" fn get_index_statistics(
         "GetIndexStatistics",
         reply_tx
     );
-}
\ No newline at end of file
+}
+/// Get the current schema of an open table, serialized in the same way as
+/// the schema passed to `open_table`'s callback. The latest version of the
+/// table is checked out first, so schema changes made by other writers are
+/// visible.
+#[no_mangle]
+pub extern "C" fn get_table_schema(
+    connection_handle: i64,
+    table_handle: i64,
+    schema_callback: BlobCallback,
+    reply_tx: ErrorReportFn,
+) {
+    command_from_ffi!(
+        LanceDbCommand::GetTableSchema {
+            connection_handle: ConnectionHandle(connection_handle),
+            table_handle: TableHandle(table_handle),
+            schema_callback,
+        },
+        "GetTableSchema",
+        reply_tx
+    );
+}
+
+/// Add columns to a table. Each new column is named by `names[i]` and its
+/// values are computed from the SQL expression `expressions[i]`, which can
+/// refer to the existing columns. The new schema is sent to `schema_callback`.
+#[no_mangle]
+pub extern "C" fn add_columns(
+    connection_handle: i64,
+    table_handle: i64,
+    names: *const *const c_char,
+    expressions: *const *const c_char,
+    columns_len: u64,
+    schema_callback: BlobCallback,
+    reply_tx: ErrorReportFn,
+) {
+    let mut columns = Vec::new();
+    for i in 0..columns_len {
+        let name = unsafe {
+            std::ffi::CStr::from_ptr(*names.offset(i as isize))
+                .to_string_lossy()
+                .to_string()
+        };
+        let expression = unsafe {
+            std::ffi::CStr::from_ptr(*expressions.offset(i as isize))
+                .to_string_lossy()
+                .to_string()
+        };
+        columns.push((name, expression));
+    }
+    command_from_ffi!(
+        LanceDbCommand::AddColumns {
+            connection_handle: ConnectionHandle(connection_handle),
+            table_handle: TableHandle(table_handle),
+            columns: NewColumns::SqlExpressions(columns),
+            schema_callback,
+        },
+        "AddColumns",
+        reply_tx
+    );
+}
+
+/// Add all-null columns to a table. The columns are described by a schema,
+/// serialized in the same way as for `create_empty_table`. The new schema is
+/// sent to `schema_callback`.
+#[no_mangle]
+pub extern "C" fn add_null_columns(
+    connection_handle: i64,
+    table_handle: i64,
+    schema_bytes: *const u8,
+    len: usize,
+    schema_callback: BlobCallback,
+    reply_tx: ErrorReportFn,
+) {
+    let schema_batch = unsafe { std::slice::from_raw_parts(schema_bytes, len) };
+    let Ok(schema) = bytes_to_schema(schema_batch) else {
+        report_result_sync(Err("Could not process schema.".to_string()), reply_tx, None);
+        return;
+    };
+    command_from_ffi!(
+        LanceDbCommand::AddColumns {
+            connection_handle: ConnectionHandle(connection_handle),
+            table_handle: TableHandle(table_handle),
+            columns: NewColumns::AllNulls(schema),
+            schema_callback,
+        },
+        "AddNullColumns",
+        reply_tx
+    );
+}
+
+/// Alter existing columns. For each column path in `paths`:
+/// - `renames[i]` is the new name, or null to keep the name. `renames` may be null.
+/// - `nullable[i]` is 1 to make the column nullable, 0 to make it non-nullable and
+///   -1 to leave it unchanged. `nullable` may be null.
+/// - If the serialized `cast_schema` contains a field named after the column, the
+///   column is cast to that field's data type. `cast_schema` may be null.
+///
+/// The new schema is sent to `schema_callback`.
+#[no_mangle]
+pub extern "C" fn alter_columns(
+    connection_handle: i64,
+    table_handle: i64,
+    paths: *const *const c_char,
+    renames: *const *const c_char,
+    nullable: *const i32,
+    columns_len: u64,
+    cast_schema: *const u8,
+    cast_schema_len: usize,
+    schema_callback: BlobCallback,
+    reply_tx: ErrorReportFn,
+) {
+    let cast_schema = if cast_schema.is_null() {
+        None
+    } else {
+        let schema_batch = unsafe { std::slice::from_raw_parts(cast_schema, cast_schema_len) };
+        let Ok(schema) = bytes_to_schema(schema_batch) else {
+            report_result_sync(Err("Could not process cast schema.".to_string()), reply_tx, None);
+            return;
+        };
+        Some(schema)
+    };
+
+    let mut changes = Vec::new();
+    for i in 0..columns_len {
+        let path = unsafe {
+            std::ffi::CStr::from_ptr(*paths.offset(i as isize))
+                .to_string_lossy()
+                .to_string()
+        };
+        let rename = if renames.is_null() {
+            None
+        } else {
+            let rename = unsafe { *renames.offset(i as isize) };
+            if rename.is_null() {
+                None
+            } else {
+                Some(unsafe { std::ffi::CStr::from_ptr(rename).to_string_lossy().to_string() })
+            }
+        };
+        let nullable = if nullable.is_null() {
+            None
+        } else {
+            match unsafe { *nullable.offset(i as isize) } {
+                0 => Some(false),
+                1 => Some(true),
+                _ => None,
+            }
+        };
+        let data_type = cast_schema
+            .as_ref()
+            .and_then(|schema| schema.field_with_name(&path).ok())
+            .map(|field| field.data_type().clone());
+        changes.push(ColumnChange {
+            path,
+            rename,
+            nullable,
+            data_type,
+        });
+    }
+    command_from_ffi!(
+        LanceDbCommand::AlterColumns {
+            connection_handle: ConnectionHandle(connection_handle),
+            table_handle: TableHandle(table_handle),
+            changes,
+            schema_callback,
+        },
+        "AlterColumns",
+        reply_tx
+    );
+}
+
+/// Drop columns from a table. The new schema is sent to `schema_callback`.
+#[no_mangle]
+pub extern "C" fn drop_columns(
+    connection_handle: i64,
+    table_handle: i64,
+    columns: *const *const c_char,
+    columns_len: u64,
+    schema_callback: BlobCallback,
+    reply_tx: ErrorReportFn,
+) {
+    let mut column_list = Vec::new();
+    for i in 0..columns_len {
+        let column = unsafe {
+            std::ffi::CStr::from_ptr(*columns.offset(i as isize))
+                .to_string_lossy()
+                .to_string()
+        };
+        column_list.push(column);
+    }
+    command_from_ffi!(
+        LanceDbCommand::DropColumns {
+            connection_handle: ConnectionHandle(connection_handle),
+            table_handle: TableHandle(table_handle),
+            columns: column_list,
+            schema_callback,
+        },
+        "DropColumns",
+        reply_tx
+    );
+}
+
+/// Register a table for background maintenance. The table is checked every
+/// `check_interval_seconds`, and is compacted, pruned or has its indices
+/// optimized when the policy thresholds are exceeded.
+///
+/// Parameters:
+/// - `max_fragments`: compact when the fragment count exceeds this. Negative means "never".
+/// - `prune_older_than_seconds`: prune versions older than this. Negative means "never".
+/// - `max_unindexed_rows`: optimize indices when any index has more unindexed rows than this.
+///   Negative means "never".
+///
+/// Return values:
+/// - A handle to the maintenance registration, or -1 if an error occurred.
+#[no_mangle]
+pub extern "C" fn register_maintenance(
+    connection_handle: i64,
+    table_handle: i64,
+    check_interval_seconds: u64,
+    max_fragments: i64,
+    prune_older_than_seconds: i64,
+    max_unindexed_rows: i64,
+    reply_tx: ErrorReportFn,
+) {
+    let policy = MaintenancePolicy {
+        check_interval: std::time::Duration::from_secs(check_interval_seconds),
+        max_fragments: if max_fragments < 0 {
+            None
+        } else {
+            Some(max_fragments as usize)
+        },
+        prune_older_than: if prune_older_than_seconds < 0 {
+            None
+        } else {
+            Some(chrono::Duration::seconds(prune_older_than_seconds))
+        },
+        max_unindexed_rows: if max_unindexed_rows < 0 {
+            None
+        } else {
+            Some(max_unindexed_rows as u64)
+        },
+    };
+    command_from_ffi!(
+        LanceDbCommand::RegisterMaintenance {
+            connection_handle: ConnectionHandle(connection_handle),
+            table_handle: TableHandle(table_handle),
+            policy,
+        },
+        "RegisterMaintenance",
+        reply_tx
+    );
+}
+
+/// Stop background maintenance for a registration.
+#[no_mangle]
+pub extern "C" fn deregister_maintenance(maintenance_handle: i64, reply_tx: ErrorReportFn) {
+    command_from_ffi!(
+        LanceDbCommand::DeregisterMaintenance {
+            handle: MaintenanceHandle(maintenance_handle),
+        },
+        "DeregisterMaintenance",
+        reply_tx
+    );
+}
+
+/// Get the status of a maintenance registration. The callback receives the
+/// last time maintenance did any work and the next scheduled check (both in
+/// milliseconds since the Unix epoch, -1 for "never"), the number of runs, and
+/// the error from the most recent check (null if it succeeded).
+#[no_mangle]
+pub extern "C" fn get_maintenance_status(
+    maintenance_handle: i64,
+    callback: Option<extern "C" fn(i64, i64, u64, *const c_char)>,
+    reply_tx: ErrorReportFn,
+) {
+    command_from_ffi!(
+        LanceDbCommand::GetMaintenanceStatus {
+            handle: MaintenanceHandle(maintenance_handle),
+            callback,
+        },
+        "GetMaintenanceStatus",
+        reply_tx
+    );
+}
+
+/// Register a host embedding function under `name`, replacing any function
+/// already registered with that name. The function must produce vectors with
+/// `dimension` elements.
+#[no_mangle]
+pub extern "C" fn register_embedding_function(
+    name: *const c_char,
+    dimension: u64,
+    callback: Option<EmbeddingCallback>,
+    reply_tx: ErrorReportFn,
+) {
+    let Some(callback) = callback else {
+        report_result_sync(Err("Embedding callback must not be null.".to_string()), reply_tx, None);
+        return;
+    };
+    if dimension == 0 {
+        report_result_sync(Err("Embedding dimension must be greater than zero.".to_string()), reply_tx, None);
+        return;
+    }
+    let name = unsafe { std::ffi::CStr::from_ptr(name).to_string_lossy().to_string() };
+    command_from_ffi!(
+        LanceDbCommand::RegisterEmbeddingFunction {
+            name,
+            function: EmbeddingFunction {
+                dimension: dimension as usize,
+                callback,
+            },
+        },
+        "RegisterEmbeddingFunction",
+        reply_tx
+    );
+}
+
+/// Bind a table's vector column to a text column and a registered embedding
+/// function. From then on, adds and merge-inserts that don't include the
+/// vector column have it computed from the text column.
+#[no_mangle]
+pub extern "C" fn bind_embedding_column(
+    connection_handle: i64,
+    table_handle: i64,
+    source_column: *const c_char,
+    vector_column: *const c_char,
+    function_name: *const c_char,
+    reply_tx: ErrorReportFn,
+) {
+    let source_column = unsafe { std::ffi::CStr::from_ptr(source_column).to_string_lossy().to_string() };
+    let vector_column = unsafe { std::ffi::CStr::from_ptr(vector_column).to_string_lossy().to_string() };
+    let function_name = unsafe { std::ffi::CStr::from_ptr(function_name).to_string_lossy().to_string() };
+    command_from_ffi!(
+        LanceDbCommand::BindEmbeddingColumn {
+            connection_handle: ConnectionHandle(connection_handle),
+            table_handle: TableHandle(table_handle),
+            binding: EmbeddingBinding {
+                source_column,
+                vector_column,
+                function_name,
+            },
+        },
+        "BindEmbeddingColumn",
+        reply_tx
+    );
+}
+
+/// Vector query using text: the query vector is computed by the embedding
+/// function bound to `vector_column`. `vector_column` may be null if the
+/// table has exactly one binding.
+///
+/// `ipc_format` selects the Arrow IPC format of the batches sent to
+/// `batch_callback`: 1 = file, 2 = stream (no footer).
+#[no_mangle]
+pub extern "C" fn text_vector_query(
+    connection_handle: i64,
+    table_handle: i64,
+    batch_callback: BlobCallback,
+    reply_tx: ErrorReportFn,
+    limit: u64,
+    where_clause: *const c_char,
+    with_row_id: bool,
+    selected_columns: *const *const c_char,
+    selected_columns_len: u64,
+    query_text: *const c_char,
+    vector_column: *const c_char,
+    metric: u32,
+    n_probes: u64,
+    refine_factor: u32,
+    batch_size: u32,
+    distance_range_min: f32,
+    distance_range_max: f32,
+    ipc_format: u32,
+) {
+    let Some(metric) = MetricType::from_repr(metric) else {
+        report_result_sync(Err("Invalid metric.".to_string()), reply_tx, None);
+        return;
+    };
+    let Some(ipc_format) = IpcFormat::from_repr(ipc_format) else {
+        report_result_sync(Err("Invalid IPC format.".to_string()), reply_tx, None);
+        return;
+    };
+    let where_clause = if where_clause.is_null() {
+        None
+    } else {
+        Some(unsafe {
+            std::ffi::CStr::from_ptr(where_clause)
+                .to_string_lossy()
+                .to_string()
+        })
+    };
+
+    // Selected columns - C array of strings
+    let selected_columns = if selected_columns.is_null() {
+        None
+    } else {
+        let mut columns = Vec::new();
+        for i in 0..selected_columns_len {
+            let column = unsafe {
+                std::ffi::CStr::from_ptr(*selected_columns.offset(i as isize))
+                    .to_string_lossy()
+                    .to_string()
+            };
+            columns.push(column);
+        }
+        Some(columns)
+    };
+
+    let query_text = unsafe { std::ffi::CStr::from_ptr(query_text).to_string_lossy().to_string() };
+    let vector_column = if vector_column.is_null() {
+        None
+    } else {
+        Some(unsafe {
+            std::ffi::CStr::from_ptr(vector_column)
+                .to_string_lossy()
+                .to_string()
+        })
+    };
+
+    command_from_ffi!(
+        LanceDbCommand::TextVectorQuery {
+            connection_handle: ConnectionHandle(connection_handle),
+            table_handle: TableHandle(table_handle),
+            batch_callback,
+            limit: if limit == 0 {
+                None
+            } else {
+                Some(limit as usize)
+            },
+            where_clause,
+            with_row_id,
+            selected_columns,
+            query_text,
+            vector_column,
+            metric: metric.into(),
+            n_probes: n_probes as usize,
+            refine_factor,
+            batch_size,
+            ipc_format,
+            stream_sender: None,
+            distance_range_min: if distance_range_min.is_nan() {
+                None
+            } else {
+                Some(distance_range_min)
+            },
+            distance_range_max: if distance_range_max.is_nan() {
+                None
+            } else {
+                Some(distance_range_max)
+            },
+        },
+        "TextVectorQuery",
+        reply_tx
+    );
+}
+
+/// Hybrid query: a vector search and a full-text search of `full_text_search`
+/// run concurrently on the same table, and are fused by Reciprocal Rank
+/// Fusion, or another reranker. Each search returns up to `limit` rows (10
+/// if 0), as does the fused result.
+///
+/// Rerankers: `reranker` is
+/// - 0 or 1 for Reciprocal Rank Fusion: each row scores
+///   `1 / (reranker_param + rank)` in each search that found it. A larger
+///   `reranker_param` (60 if 0 or less) gives the top few ranks less of an edge.
+/// - 2 for linear combination: a weighted sum of the vector and full-text
+///   scores, each scaled to 0..1 (distances inverted). `reranker_param` is the
+///   vector search's weight, from 0 to 1 (0.7 if NaN).
+/// - 3 for the host reranker registered as `reranker_name`.
+///
+/// Results are sent to `batch_callback` (in batches of `batch_size` rows, if
+/// set) with a `_distance` column (null for rows only the full-text search
+/// found), a `_score` column (null for rows only the vector search found)
+/// and a `_relevance_score` column, best first. The table needs a
+/// full-text index on the searched column.
+///
+/// `ipc_format` selects the Arrow IPC format of the batches sent to
+/// `batch_callback`: 1 = file, 2 = stream (no footer).
+///
+/// Return values:
+/// - The number of rows in the fused result, or -1 if an error occurred.
+#[no_mangle]
+pub extern "C" fn hybrid_query(
+    connection_handle: i64,
+    table_handle: i64,
+    batch_callback: BlobCallback,
+    reply_tx: ErrorReportFn,
+    limit: u64,
+    where_clause: *const c_char,
+    with_row_id: bool,
+    selected_columns: *const *const c_char,
+    selected_columns_len: u64,
+    vector_type: u32,
+    vector_blob: *const u8,
+    vector_blob_len: u64,
+    vector_num_elements: u64,
+    metric: u32,
+    n_probes: u64,
+    full_text_search: *const c_char,
+    reranker: u32,
+    reranker_param: f32,
+    reranker_name: *const c_char,
+    batch_size: u32,
+    ipc_format: u32,
+) {
+    let reranker = match reranker_from_ffi(reranker, reranker_param, reranker_name) {
+        Ok(reranker) => reranker.unwrap_or(Reranker::ReciprocalRankFusion { k: DEFAULT_RRF_K }),
+        Err(err) => {
+            report_result_sync(Err(err), reply_tx, None);
+            return;
+        }
+    };
+    let Some(metric) = MetricType::from_repr(metric) else {
+        report_result_sync(Err("Invalid metric.".to_string()), reply_tx, None);
+        return;
+    };
+    let Some(ipc_format) = IpcFormat::from_repr(ipc_format) else {
+        report_result_sync(Err("Invalid IPC format.".to_string()), reply_tx, None);
+        return;
+    };
+    if full_text_search.is_null() {
+        report_result_sync(Err("A full-text search is required.".to_string()), reply_tx, None);
+        return;
+    }
+    let full_text_search = unsafe {
+        std::ffi::CStr::from_ptr(full_text_search)
+            .to_string_lossy()
+            .to_string()
+    };
+    let where_clause = if where_clause.is_null() {
+        None
+    } else {
+        Some(unsafe {
+            std::ffi::CStr::from_ptr(where_clause)
+                .to_string_lossy()
+                .to_string()
+        })
+    };
+
+    // Selected columns - C array of strings
+    let selected_columns = if selected_columns.is_null() {
+        None
+    } else {
+        let mut columns = Vec::new();
+        for i in 0..selected_columns_len {
+            let column = unsafe {
+                std::ffi::CStr::from_ptr(*selected_columns.offset(i as isize))
+                    .to_string_lossy()
+                    .to_string()
+            };
+            columns.push(column);
+        }
+        Some(columns)
+    };
+
+    let vector_data = VectorDataType::from_blob(
+        vector_type,
+        vector_blob,
+        vector_blob_len,
+        vector_num_elements,
+    );
+
+    command_from_ffi!(
+        LanceDbCommand::HybridQuery {
+            connection_handle: ConnectionHandle(connection_handle),
+            table_handle: TableHandle(table_handle),
+            batch_callback,
+            limit: if limit == 0 {
+                None
+            } else {
+                Some(limit as usize)
+            },
+            where_clause,
+            with_row_id,
+            selected_columns,
+            vector_data,
+            metric: metric.into(),
+            n_probes: n_probes as usize,
+            full_text_search,
+            reranker,
+            batch_size,
+            ipc_format,
+        },
+        "HybridQuery",
+        reply_tx
+    );
+}
+
+/// Register a host reranker under `name`, replacing any reranker already
+/// registered with that name. Queries use it by name.
+#[no_mangle]
+pub extern "C" fn register_reranker(
+    name: *const c_char,
+    callback: Option<RerankCallback>,
+    reply_tx: ErrorReportFn,
+) {
+    let Some(callback) = callback else {
+        report_result_sync(Err("Reranker callback must not be null.".to_string()), reply_tx, None);
+        return;
+    };
+    let name = unsafe { std::ffi::CStr::from_ptr(name).to_string_lossy().to_string() };
+    command_from_ffi!(
+        LanceDbCommand::RegisterReranker { name, callback },
+        "RegisterReranker",
+        reply_tx
+    );
+}
+
+/// Batch vector query: a separate vector search for each query vector, so
+/// that many vectors need only one call. `queries` holds Arrow IPC record
+/// batches whose first column has one query vector (a list of floats) per
+/// row. Up to `concurrency` searches (8 if 0) run at once, and the other
+/// parameters apply to every search, as for `vector_query`. `vector_column`
+/// may be null to search the column Lance picks.
+///
+/// Results are sent to `batch_callback` in query order, each with a
+/// `query_index` column (the query vector's row, counting across batches)
+/// as its first column.
+///
+/// Return values:
+/// - The number of query vectors whose results were all sent (fewer than
+///   the number of vectors if `batch_callback` stopped early), or -1 if an
+///   error occurred.
+#[no_mangle]
+pub extern "C" fn batch_vector_query(
+    connection_handle: i64,
+    table_handle: i64,
+    batch_callback: BlobCallback,
+    reply_tx: ErrorReportFn,
+    queries: *const u8,
+    queries_len: usize,
+    limit: u64,
+    where_clause: *const c_char,
+    with_row_id: bool,
+    selected_columns: *const *const c_char,
+    selected_columns_len: u64,
+    vector_column: *const c_char,
+    metric: u32,
+    n_probes: u64,
+    refine_factor: u32,
+    concurrency: u32,
+    batch_size: u32,
+    ipc_format: u32,
+) {
+    let Some(metric) = MetricType::from_repr(metric) else {
+        report_result_sync(Err("Invalid metric.".to_string()), reply_tx, None);
+        return;
+    };
+    let Some(ipc_format) = IpcFormat::from_repr(ipc_format) else {
+        report_result_sync(Err("Invalid IPC format.".to_string()), reply_tx, None);
+        return;
+    };
+    let data = unsafe { std::slice::from_raw_parts(queries, queries_len) };
+    let queries = bytes_to_batch(data).map_err(|e| format!("{e:?}")).and_then(|batches| {
+        batches
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("{e:?}"))
+    });
+    let queries = match queries {
+        Ok(queries) => queries,
+        Err(e) => {
+            report_result_sync(Err(format!("Could not parse record batch: {e}")), reply_tx, None);
+            return;
+        }
+    };
+    let where_clause = if where_clause.is_null() {
+        None
+    } else {
+        Some(unsafe {
+            std::ffi::CStr::from_ptr(where_clause)
+                .to_string_lossy()
+                .to_string()
+        })
+    };
+
+    // Selected columns - C array of strings
+    let selected_columns = if selected_columns.is_null() {
+        None
+    } else {
+        let mut columns = Vec::new();
+        for i in 0..selected_columns_len {
+            let column = unsafe {
+                std::ffi::CStr::from_ptr(*selected_columns.offset(i as isize))
+                    .to_string_lossy()
+                    .to_string()
+            };
+            columns.push(column);
+        }
+        Some(columns)
+    };
+
+    let vector_column = if vector_column.is_null() {
+        None
+    } else {
+        Some(unsafe {
+            std::ffi::CStr::from_ptr(vector_column)
+                .to_string_lossy()
+                .to_string()
+        })
+    };
+
+    command_from_ffi!(
+        LanceDbCommand::BatchVectorQuery {
+            connection_handle: ConnectionHandle(connection_handle),
+            table_handle: TableHandle(table_handle),
+            batch_callback,
+            queries,
+            search: BatchSearch {
+                limit: if limit == 0 {
+                    None
+                } else {
+                    Some(limit as usize)
+                },
+                where_clause,
+                with_row_id,
+                selected_columns,
+                vector_column,
+                metric: metric.into(),
+                n_probes: n_probes as usize,
+                refine_factor,
+            },
+            concurrency: concurrency as usize,
+            batch_size,
+            ipc_format,
+        },
+        "BatchVectorQuery",
+        reply_tx
+    );
+}
+
+/// Start a streaming write session on a table. Batches sent with
+/// `write_batch` are fed to a single add operation, and land in one commit
+/// when `commit_write` is called.
+///
+/// Return values:
+/// - A handle to the write session, or -1 if an error occurred.
+#[no_mangle]
+pub extern "C" fn begin_write(
+    connection_handle: i64,
+    table_handle: i64,
+    write_mode: u32,
+    reply_tx: ErrorReportFn,
+) {
+    let Some(write_mode) = WriteMode::from_repr(write_mode) else {
+        report_result_sync(Err("Invalid write mode.".to_string()), reply_tx, None);
+        return;
+    };
+    command_from_ffi!(
+        LanceDbCommand::BeginWrite {
+            connection_handle: ConnectionHandle(connection_handle),
+            table_handle: TableHandle(table_handle),
+            write_mode,
+        },
+        "BeginWrite",
+        reply_tx
+    );
+}
+
+/// Send one or more record batches (as Arrow IPC bytes) to a write session.
+/// The batches must match the table's schema. If the session's queue is
+/// full, this waits until the writer has caught up.
+#[no_mangle]
+pub extern "C" fn write_batch(
+    session_handle: i64,
+    data: *const u8,
+    len: usize,
+    reply_tx: ErrorReportFn,
+) {
+    let data = unsafe { std::slice::from_raw_parts(data, len) };
+    let batch = match bytes_to_batch(data) {
+        Ok(batch) => batch,
+        Err(e) => {
+            report_result_sync(
+                Err(format!("Could not parse record batch: {:?}", e)),
+                reply_tx,
+                None,
+            );
+            return;
+        }
+    };
+    command_from_ffi!(
+        LanceDbCommand::WriteBatch {
+            handle: WriteSessionHandle(session_handle),
+            batch,
+        },
+        "WriteBatch",
+        reply_tx
+    );
+}
+
+/// Finish a write session, committing everything that was written to it.
+#[no_mangle]
+pub extern "C" fn commit_write(session_handle: i64, reply_tx: ErrorReportFn) {
+    command_from_ffi!(
+        LanceDbCommand::CommitWrite {
+            handle: WriteSessionHandle(session_handle),
+        },
+        "CommitWrite",
+        reply_tx
+    );
+}
+
+/// Finish a write session without committing anything that was written to it.
+#[no_mangle]
+pub extern "C" fn abort_write(session_handle: i64, reply_tx: ErrorReportFn) {
+    command_from_ffi!(
+        LanceDbCommand::AbortWrite {
+            handle: WriteSessionHandle(session_handle),
+        },
+        "AbortWrite",
+        reply_tx
+    );
+}
+
+/// Add a record batch passed through the Arrow C Data Interface, avoiding
+/// IPC serialization. The array is moved (and left released); the schema is
+/// only borrowed, so the caller still releases it. Otherwise this behaves
+/// like `add_record_batch`.
+#[no_mangle]
+pub extern "C" fn add_record_batch_array(
+    connection_handle: i64,
+    table_handle: i64,
+    array: *mut FFI_ArrowArray,
+    schema: *const FFI_ArrowSchema,
+    write_mode: u32,
+    auto_cast: bool,
+    bad_vector_handling: u32,
+    fill_value: f32,
+    reply_tx: ErrorReportFn,
+) {
+    let batch = match import_batch(array, schema) {
+        Ok(batch) => batch,
+        Err(e) => {
+            report_result_sync(Err(e), reply_tx, None);
+            return;
+        }
+    };
+    let Some(write_mode) = WriteMode::from_repr(write_mode) else {
+        report_result_sync(Err("Invalid write mode.".to_string()), reply_tx, None);
+        return;
+    };
+    let Some(bad_vector_handling) = BadVectorHandling::from_repr(bad_vector_handling) else {
+        report_result_sync(Err("Invalid bad vector handling mode.".to_string()), reply_tx, None);
+        return;
+    };
+    command_from_ffi!(
+        LanceDbCommand::AddRecordBatch {
+            connection_handle: ConnectionHandle(connection_handle),
+            table_handle: TableHandle(table_handle),
+            write_mode,
+            batch: vec![Ok(batch)],
+            auto_cast,
+            bad_vector_handling,
+            fill_value,
+        },
+        "AddRecordBatchArray",
+        reply_tx
+    );
+}
+
+/// Add every record batch from an Arrow C stream, in a single commit. The
+/// stream is moved (and left released), and is read from a runtime thread
+/// while the data is written, so it is never held in memory all at once.
+/// The stream's schema must match the table's; if `auto_cast` is set, safe
+/// casts are applied as for `add_record_batch`.
+#[no_mangle]
+pub extern "C" fn add_record_batch_stream(
+    connection_handle: i64,
+    table_handle: i64,
+    stream: *mut FFI_ArrowArrayStream,
+    write_mode: u32,
+    auto_cast: bool,
+    reply_tx: ErrorReportFn,
+) {
+    let reader = match import_stream(stream) {
+        Ok(reader) => reader,
+        Err(e) => {
+            report_result_sync(Err(e), reply_tx, None);
+            return;
+        }
+    };
+    let Some(write_mode) = WriteMode::from_repr(write_mode) else {
+        report_result_sync(Err("Invalid write mode.".to_string()), reply_tx, None);
+        return;
+    };
+    command_from_ffi!(
+        LanceDbCommand::AddRecordBatchStream {
+            connection_handle: ConnectionHandle(connection_handle),
+            table_handle: TableHandle(table_handle),
+            write_mode,
+            reader,
+            auto_cast,
+        },
+        "AddRecordBatchStream",
+        reply_tx
+    );
+}
+
+/// Hand query results to the caller through `out_stream`, which is only
+/// written if the query starts successfully. The caller reads batches with
+/// the stream's `get_next` and must call `release` when done; releasing
+/// early stops the query. The stream must not be read from a runtime thread.
+fn write_result_stream(
+    stream_rx: tokio::sync::oneshot::Receiver<ResultStreamReader>,
+    out_stream: *mut FFI_ArrowArrayStream,
+) {
+    if let Ok(reader) = stream_rx.blocking_recv() {
+        unsafe { std::ptr::write(out_stream, FFI_ArrowArrayStream::new(Box::new(reader))) };
+    }
+}
+
+/// Query a table, returning the results as an Arrow C stream in `out_stream`
+/// instead of IPC bytes. Parameters are as for `query`.
+#[no_mangle]
+pub extern "C" fn query_stream(
+    connection_handle: i64,
+    table_handle: i64,
+    out_stream: *mut FFI_ArrowArrayStream,
+    reply_tx: ErrorReportFn,
+    limit: u64,
+    where_clause: *const c_char,
+    with_row_id: bool,
+    selected_columns: *const *const c_char,
+    selected_columns_len: u64,
+    full_text_search: *const c_char,
+    batch_size: u32,
+) {
+    if out_stream.is_null() {
+        report_result_sync(Err("Output stream must not be null.".to_string()), reply_tx, None);
+        return;
+    }
+    let where_clause = if where_clause.is_null() {
+        None
+    } else {
+        Some(unsafe {
+            std::ffi::CStr::from_ptr(where_clause)
+                .to_string_lossy()
+                .to_string()
+        })
+    };
+
+    // Selected columns - C array of strings
+    let selected_columns = if selected_columns.is_null() {
+        None
+    } else {
+        let mut columns = Vec::new();
+        for i in 0..selected_columns_len {
+            let column = unsafe {
+                std::ffi::CStr::from_ptr(*selected_columns.offset(i as isize))
+                    .to_string_lossy()
+                    .to_string()
+            };
+            columns.push(column);
+        }
+        Some(columns)
+    };
+
+    let full_text_search = if full_text_search.is_null() {
+        None
+    } else {
+        Some(unsafe {
+            std::ffi::CStr::from_ptr(full_text_search)
+                .to_string_lossy()
+                .to_string()
+        })
+    };
+
+    let (stream_tx, stream_rx) = tokio::sync::oneshot::channel();
+    command_from_ffi!(
+        LanceDbCommand::Query {
+            connection_handle: ConnectionHandle(connection_handle),
+            table_handle: TableHandle(table_handle),
+            batch_callback: None,
+            limit: if limit == 0 {
+                None
+            } else {
+                Some(limit as usize)
+            },
+            pagination: Pagination::default(),
+            where_clause,
+            with_row_id,
+            explain_callback: None,
+            selected_columns,
+            projections: None,
+            reranker: None,
+            full_text_search,
+            batch_size,
+            ipc_format: IpcFormat::File,
+            stream_sender: Some(stream_tx),
+        },
+        "QueryStream",
+        reply_tx
+    );
+    write_result_stream(stream_rx, out_stream);
+}
+
+/// Vector query, returning the results as an Arrow C stream in `out_stream`
+/// instead of IPC bytes. Parameters are as for `vector_query`.
+#[no_mangle]
+pub extern "C" fn vector_query_stream(
+    connection_handle: i64,
+    table_handle: i64,
+    out_stream: *mut FFI_ArrowArrayStream,
+    reply_tx: ErrorReportFn,
+    limit: u64,
+    where_clause: *const c_char,
+    with_row_id: bool,
+    selected_columns: *const *const c_char,
+    selected_columns_len: u64,
+    vector_type: u32,
+    vector_blob: *const u8,
+    vector_blob_len: u64,
+    vector_num_elements: u64,
+    metric: u32,
+    n_probes: u64,
+    refine_factor: u32,
+    batch_size: u32,
+    distance_range_min: f32,
+    distance_range_max: f32,
+    postfilter: bool,
+    fast_search: bool,
+) {
+    if out_stream.is_null() {
+        report_result_sync(Err("Output stream must not be null.".to_string()), reply_tx, None);
+        return;
+    }
+    let Some(metric) = MetricType::from_repr(metric) else {
+        report_result_sync(Err("Invalid metric.".to_string()), reply_tx, None);
+        return;
+    };
+    let where_clause = if where_clause.is_null() {
+        None
+    } else {
+        Some(unsafe {
+            std::ffi::CStr::from_ptr(where_clause)
+                .to_string_lossy()
+                .to_string()
+        })
+    };
+
+    // Selected columns - C array of strings
+    let selected_columns = if selected_columns.is_null() {
+        None
+    } else {
+        let mut columns = Vec::new();
+        for i in 0..selected_columns_len {
+            let column = unsafe {
+                std::ffi::CStr::from_ptr(*selected_columns.offset(i as isize))
+                    .to_string_lossy()
+                    .to_string()
+            };
+            columns.push(column);
+        }
+        Some(columns)
+    };
+
+    let vector_data = VectorDataType::from_blob(
+        vector_type,
+        vector_blob,
+        vector_blob_len,
+        vector_num_elements,
+    );
+
+    let (stream_tx, stream_rx) = tokio::sync::oneshot::channel();
+    command_from_ffi!(
+        LanceDbCommand::VectorQuery {
+            connection_handle: ConnectionHandle(connection_handle),
+            table_handle: TableHandle(table_handle),
+            batch_callback: None,
+            limit: if limit == 0 {
+                None
+            } else {
+                Some(limit as usize)
+            },
+            pagination: Pagination::default(),
+            where_clause,
+            with_row_id,
+            explain_callback: None,
+            selected_columns,
+            projections: None,
+            reranker: None,
+            vector_data,
+            metric: metric.into(),
+            n_probes: n_probes as usize,
+            refine_factor,
+            batch_size,
+            ipc_format: IpcFormat::File,
+            distance_range_min: if distance_range_min.is_nan() {
+                None
+            } else {
+                Some(distance_range_min)
+            },
+            distance_range_max: if distance_range_max.is_nan() {
+                None
+            } else {
+                Some(distance_range_max)
+            },
+            postfilter,
+            fast_search,
+            stream_sender: Some(stream_tx),
+        },
+        "VectorQueryStream",
+        reply_tx
+    );
+    write_result_stream(stream_rx, out_stream);
+}
+
+/// Import a Parquet (`format` 1), CSV (2) or JSON-lines (3) file from local
+/// disk into a table. The file is streamed, so it is never held in memory all
+/// at once, and lands in a single commit. If the table doesn't exist and
+/// `create_table` is set, it is created with the file's schema. Columns are
+/// cast to the table's types where that is safe.
+///
+/// Parameters:
+/// - `csv_delimiter`, `csv_has_header`: CSV options, ignored for other formats.
+/// - `infer_schema_max_records`: records to read when inferring a CSV or JSON schema. 0 reads them all.
+/// - `batch_size`: rows per batch read from the file. 0 uses the default.
+/// - `progress_callback`: receives the rows imported so far and the total rows in the
+///   file (-1 if unknown). Return false to cancel the import.
+///
+/// Return values:
+/// - The number of rows imported, or -1 if an error occurred.
+#[no_mangle]
+pub extern "C" fn import_file(
+    connection_handle: i64,
+    table_name: *const c_char,
+    path: *const c_char,
+    format: u32,
+    csv_delimiter: c_char,
+    csv_has_header: bool,
+    infer_schema_max_records: u64,
+    batch_size: u32,
+    write_mode: u32,
+    create_table: bool,
+    progress_callback: Option<extern "C" fn(rows: u64, total_rows: i64) -> bool>,
+    reply_tx: ErrorReportFn,
+) {
+    let Some(format) = ImportFormat::from_repr(format) else {
+        report_result_sync(Err("Invalid import format.".to_string()), reply_tx, None);
+        return;
+    };
+    let Some(write_mode) = WriteMode::from_repr(write_mode) else {
+        report_result_sync(Err("Invalid write mode.".to_string()), reply_tx, None);
+        return;
+    };
+    let table_name = unsafe { std::ffi::CStr::from_ptr(table_name).to_string_lossy().to_string() };
+    let path = unsafe { std::ffi::CStr::from_ptr(path).to_string_lossy().to_string() };
+    let options = ImportOptions {
+        csv_delimiter: csv_delimiter as u8,
+        csv_has_header,
+        infer_schema_max_records: if infer_schema_max_records == 0 {
+            None
+        } else {
+            Some(infer_schema_max_records as usize)
+        },
+        batch_size: if batch_size == 0 {
+            None
+        } else {
+            Some(batch_size as usize)
+        },
+    };
+    command_from_ffi!(
+        LanceDbCommand::ImportFile {
+            connection_handle: ConnectionHandle(connection_handle),
+            table_name,
+            path,
+            format,
+            options,
+            write_mode,
+            create_table,
+            progress_callback,
+        },
+        "ImportFile",
+        reply_tx
+    );
+}
+
+/// Build export options from FFI parameters.
+fn export_options(format: u32, compression: u32, row_group_size: u64) -> Result<ExportOptions, String> {
+    let Some(format) = ExportFormat::from_repr(format) else {
+        return Err("Invalid export format.".to_string());
+    };
+    let Some(compression) = ExportCompression::from_repr(compression) else {
+        return Err("Invalid export compression.".to_string());
+    };
+    Ok(ExportOptions {
+        format,
+        compression,
+        row_group_size: if row_group_size == 0 {
+            None
+        } else {
+            Some(row_group_size as usize)
+        },
+    })
+}
+
+/// Query a table and write the results to a Parquet (`format` 1) or Arrow
+/// IPC file (2) at `path`, without passing them through the host. Other
+/// parameters are as for `query`.
+///
+/// Parameters:
+/// - `compression`: 0 = none, 1 = Snappy, 2 = Zstd, 3 = LZ4, 4 = Gzip. Arrow IPC files
+///   only support none, Zstd and LZ4.
+/// - `row_group_size`: maximum rows per Parquet row group. 0 uses the default.
+///
+/// Return values:
+/// - The number of rows written, or -1 if an error occurred.
+#[no_mangle]
+pub extern "C" fn export_query_to_file(
+    connection_handle: i64,
+    table_handle: i64,
+    path: *const c_char,
+    format: u32,
+    compression: u32,
+    row_group_size: u64,
+    reply_tx: ErrorReportFn,
+    limit: u64,
+    where_clause: *const c_char,
+    with_row_id: bool,
+    selected_columns: *const *const c_char,
+    selected_columns_len: u64,
+    full_text_search: *const c_char,
+    batch_size: u32,
+) {
+    let options = match export_options(format, compression, row_group_size) {
+        Ok(options) => options,
+        Err(e) => {
+            report_result_sync(Err(e), reply_tx, None);
+            return;
+        }
+    };
+    let path = unsafe { std::ffi::CStr::from_ptr(path).to_string_lossy().to_string() };
+    let where_clause = if where_clause.is_null() {
+        None
+    } else {
+        Some(unsafe {
+            std::ffi::CStr::from_ptr(where_clause)
+                .to_string_lossy()
+                .to_string()
+        })
+    };
+
+    // Selected columns - C array of strings
+    let selected_columns = if selected_columns.is_null() {
+        None
+    } else {
+        let mut columns = Vec::new();
+        for i in 0..selected_columns_len {
+            let column = unsafe {
+                std::ffi::CStr::from_ptr(*selected_columns.offset(i as isize))
+                    .to_string_lossy()
+                    .to_string()
+            };
+            columns.push(column);
+        }
+        Some(columns)
+    };
+
+    let full_text_search = if full_text_search.is_null() {
+        None
+    } else {
+        Some(unsafe {
+            std::ffi::CStr::from_ptr(full_text_search)
+                .to_string_lossy()
+                .to_string()
+        })
+    };
+
+    let query = LanceDbCommand::Query {
+        connection_handle: ConnectionHandle(connection_handle),
+        table_handle: TableHandle(table_handle),
+        batch_callback: None,
+        limit: if limit == 0 {
+            None
+        } else {
+            Some(limit as usize)
+        },
+        pagination: Pagination::default(),
+        where_clause,
+        with_row_id,
+        explain_callback: None,
+        selected_columns,
+        projections: None,
+        reranker: None,
+        full_text_search,
+        batch_size,
+        ipc_format: IpcFormat::File,
+        stream_sender: None,
+    };
+    command_from_ffi!(
+        LanceDbCommand::ExportToFile {
+            query: Box::new(query),
+            path,
+            options,
+        },
+        "ExportQueryToFile",
+        reply_tx
+    );
+}
+
+/// Vector query a table and write the results to a Parquet (`format` 1) or
+/// Arrow IPC file (2) at `path`. `compression` and `row_group_size` are as
+/// for `export_query_to_file`; other parameters are as for `vector_query`.
+///
+/// Return values:
+/// - The number of rows written, or -1 if an error occurred.
+#[no_mangle]
+pub extern "C" fn export_vector_query_to_file(
+    connection_handle: i64,
+    table_handle: i64,
+    path: *const c_char,
+    format: u32,
+    compression: u32,
+    row_group_size: u64,
+    reply_tx: ErrorReportFn,
+    limit: u64,
+    where_clause: *const c_char,
+    with_row_id: bool,
+    selected_columns: *const *const c_char,
+    selected_columns_len: u64,
+    vector_type: u32,
+    vector_blob: *const u8,
+    vector_blob_len: u64,
+    vector_num_elements: u64,
+    metric: u32,
+    n_probes: u64,
+    refine_factor: u32,
+    batch_size: u32,
+    distance_range_min: f32,
+    distance_range_max: f32,
+    postfilter: bool,
+    fast_search: bool,
+) {
+    let options = match export_options(format, compression, row_group_size) {
+        Ok(options) => options,
+        Err(e) => {
+            report_result_sync(Err(e), reply_tx, None);
+            return;
+        }
+    };
+    let Some(metric) = MetricType::from_repr(metric) else {
+        report_result_sync(Err("Invalid metric.".to_string()), reply_tx, None);
+        return;
+    };
+    let path = unsafe { std::ffi::CStr::from_ptr(path).to_string_lossy().to_string() };
+    let where_clause = if where_clause.is_null() {
+        None
+    } else {
+        Some(unsafe {
+            std::ffi::CStr::from_ptr(where_clause)
+                .to_string_lossy()
+                .to_string()
+        })
+    };
+
+    // Selected columns - C array of strings
+    let selected_columns = if selected_columns.is_null() {
+        None
+    } else {
+        let mut columns = Vec::new();
+        for i in 0..selected_columns_len {
+            let column = unsafe {
+                std::ffi::CStr::from_ptr(*selected_columns.offset(i as isize))
+                    .to_string_lossy()
+                    .to_string()
+            };
+            columns.push(column);
+        }
+        Some(columns)
+    };
+
+    let vector_data = VectorDataType::from_blob(
+        vector_type,
+        vector_blob,
+        vector_blob_len,
+        vector_num_elements,
+    );
+
+    let query = LanceDbCommand::VectorQuery {
+        connection_handle: ConnectionHandle(connection_handle),
+        table_handle: TableHandle(table_handle),
+        batch_callback: None,
+        limit: if limit == 0 {
+            None
+        } else {
+            Some(limit as usize)
+        },
+        pagination: Pagination::default(),
+        where_clause,
+        with_row_id,
+        explain_callback: None,
+        selected_columns,
+        projections: None,
+        reranker: None,
+        vector_data,
+        metric: metric.into(),
+        n_probes: n_probes as usize,
+        refine_factor,
+        batch_size,
+        ipc_format: IpcFormat::File,
+        distance_range_min: if distance_range_min.is_nan() {
+            None
+        } else {
+            Some(distance_range_min)
+        },
+        distance_range_max: if distance_range_max.is_nan() {
+            None
+        } else {
+            Some(distance_range_max)
+        },
+        postfilter,
+        fast_search,
+        stream_sender: None,
+    };
+    command_from_ffi!(
+        LanceDbCommand::ExportToFile {
+            query: Box::new(query),
+            path,
+            options,
+        },
+        "ExportVectorQueryToFile",
+        reply_tx
+    );
+}
+
+/// Add rows given as JSON: either an array of objects, or one object per line
+/// (JSON-lines). Keys are column names; values are converted using the
+/// table's schema, with arrays for vector and list columns. Missing keys and
+/// nulls become nulls, and columns missing from every row are filled as for
+/// `add_record_batch` (including bound embedding columns). Errors name the
+/// row and field that couldn't be converted.
+///
+/// `write_mode`, `bad_vector_handling` and `fill_value` are as for
+/// `add_record_batch`.
+///
+/// Return values:
+/// - The number of rows with bad vectors that were dropped or replaced, or -1 if
+///   an error occurred.
+#[no_mangle]
+pub extern "C" fn add_json_rows(
+    connection_handle: i64,
+    table_handle: i64,
+    json: *const c_char,
+    write_mode: u32,
+    bad_vector_handling: u32,
+    fill_value: f32,
+    reply_tx: ErrorReportFn,
+) {
+    if json.is_null() {
+        report_result_sync(Err("JSON must not be null.".to_string()), reply_tx, None);
+        return;
+    }
+    let Some(write_mode) = WriteMode::from_repr(write_mode) else {
+        report_result_sync(Err("Invalid write mode.".to_string()), reply_tx, None);
+        return;
+    };
+    let Some(bad_vector_handling) = BadVectorHandling::from_repr(bad_vector_handling) else {
+        report_result_sync(Err("Invalid bad vector handling mode.".to_string()), reply_tx, None);
+        return;
+    };
+    let json = unsafe { std::ffi::CStr::from_ptr(json).to_string_lossy().to_string() };
+    command_from_ffi!(
+        LanceDbCommand::AddJsonRows {
+            connection_handle: ConnectionHandle(connection_handle),
+            table_handle: TableHandle(table_handle),
+            write_mode,
+            json,
+            bad_vector_handling,
+            fill_value,
+        },
+        "AddJsonRows",
+        reply_tx
+    );
+}
+
+/// Buffer appends to a table, so that many small `add_record_batch` (or
+/// `add_json_rows`) calls are committed together. Appends are validated as
+/// usual and then held in memory until the buffer reaches `max_rows` or
+/// `max_bytes`, `flush_interval_ms` passes, or `flush_write_buffer` is called.
+/// Other writes (overwrites excepted) are not buffered, so call
+/// `flush_write_buffer` first if they must see the buffered rows.
+///
+/// Parameters:
+/// - `max_rows`, `max_bytes`, `flush_interval_ms`: flush limits. 0 means "no limit", but at
+///   least one must be set.
+/// - `durability_callback`: called after each flush with the table handle, the number of
+///   rows flushed, and an error message (null if the rows were committed). Flushes retry
+///   commit conflicts under the table's retry policy; rows from a failed flush stay
+///   buffered for the next one, except after the final flush when the buffer is disabled.
+#[no_mangle]
+pub extern "C" fn enable_write_buffer(
+    connection_handle: i64,
+    table_handle: i64,
+    max_rows: u64,
+    max_bytes: u64,
+    flush_interval_ms: u64,
+    durability_callback: Option<extern "C" fn(table_handle: i64, rows: u64, error: *const c_char)>,
+    reply_tx: ErrorReportFn,
+) {
+    let policy = WriteBufferPolicy {
+        max_rows: if max_rows == 0 {
+            None
+        } else {
+            Some(max_rows as usize)
+        },
+        max_bytes: if max_bytes == 0 {
+            None
+        } else {
+            Some(max_bytes as usize)
+        },
+        flush_interval: if flush_interval_ms == 0 {
+            None
+        } else {
+            Some(std::time::Duration::from_millis(flush_interval_ms))
+        },
+        durability_callback,
+    };
+    command_from_ffi!(
+        LanceDbCommand::EnableWriteBuffer {
+            connection_handle: ConnectionHandle(connection_handle),
+            table_handle: TableHandle(table_handle),
+            policy,
+        },
+        "EnableWriteBuffer",
+        reply_tx
+    );
+}
+
+/// Flush a table's write buffer and stop buffering appends.
+///
+/// Return values:
+/// - The number of rows committed by the final flush, or -1 if an error occurred.
+#[no_mangle]
+pub extern "C" fn disable_write_buffer(connection_handle: i64, table_handle: i64, reply_tx: ErrorReportFn) {
+    command_from_ffi!(
+        LanceDbCommand::DisableWriteBuffer {
+            connection_handle: ConnectionHandle(connection_handle),
+            table_handle: TableHandle(table_handle),
+        },
+        "DisableWriteBuffer",
+        reply_tx
+    );
+}
+
+/// Commit everything in a table's write buffer now.
+///
+/// Return values:
+/// - The number of rows committed, or -1 if an error occurred.
+#[no_mangle]
+pub extern "C" fn flush_write_buffer(connection_handle: i64, table_handle: i64, reply_tx: ErrorReportFn) {
+    command_from_ffi!(
+        LanceDbCommand::FlushWriteBuffer {
+            connection_handle: ConnectionHandle(connection_handle),
+            table_handle: TableHandle(table_handle),
+        },
+        "FlushWriteBuffer",
+        reply_tx
+    );
+}
+
+/// Retry a table's writes when they fail with a commit conflict (another
+/// process committed first). Adds, merge-inserts, updates and deletes are
+/// repeated up to `max_attempts` times in all, waiting `initial_backoff_ms`
+/// before the first retry and doubling the wait each time, up to
+/// `max_backoff_ms`. `jitter` (0 to 1) is the share of each wait that is
+/// randomized. A `max_attempts` of 0 or 1 removes the policy.
+///
+/// `retry_callback` is called before each retry with the table handle, the
+/// attempt that failed, the wait in milliseconds, and the conflict's error.
+#[no_mangle]
+pub extern "C" fn set_retry_policy(
+    connection_handle: i64,
+    table_handle: i64,
+    max_attempts: u32,
+    initial_backoff_ms: u64,
+    max_backoff_ms: u64,
+    jitter: f64,
+    retry_callback: Option<extern "C" fn(table_handle: i64, attempt: u32, delay_ms: u64, error: *const c_char)>,
+    reply_tx: ErrorReportFn,
+) {
+    if !(0.0..=1.0).contains(&jitter) {
+        report_result_sync(Err("Jitter must be between 0 and 1.".to_string()), reply_tx, None);
+        return;
+    }
+    let policy = if max_attempts <= 1 {
+        None
+    } else {
+        Some(RetryPolicy {
+            max_attempts,
+            initial_backoff: std::time::Duration::from_millis(initial_backoff_ms),
+            max_backoff: std::time::Duration::from_millis(max_backoff_ms.max(initial_backoff_ms)),
+            jitter,
+            retry_callback,
+        })
+    };
+    command_from_ffi!(
+        LanceDbCommand::SetRetryPolicy {
+            connection_handle: ConnectionHandle(connection_handle),
+            table_handle: TableHandle(table_handle),
+            policy,
+        },
+        "SetRetryPolicy",
+        reply_tx
+    );
+}
+
+/// Query a table, returning a cursor that the caller pulls batches from with
+/// `next_batch`, and must free with `close_cursor`. Parameters are as for
+/// `query`; `ipc_format` applies to the batches `next_batch` returns.
+///
+/// Return values:
+/// - The cursor handle, or -1 if an error occurred.
+#[no_mangle]
+pub extern "C" fn open_query(
+    connection_handle: i64,
+    table_handle: i64,
+    reply_tx: ErrorReportFn,
+    limit: u64,
+    where_clause: *const c_char,
+    with_row_id: bool,
+    selected_columns: *const *const c_char,
+    selected_columns_len: u64,
+    full_text_search: *const c_char,
+    batch_size: u32,
+    ipc_format: u32,
+    offset: u64,
+    page_token: *const c_char,
+    next_page_callback: Option<extern "C" fn(*const c_char)>,
+    projection_aliases: *const *const c_char,
+    projection_expressions: *const *const c_char,
+    projections_len: u64,
+) {
+    let projections = projections(projection_aliases, projection_expressions, projections_len);
+    let pagination = pagination(offset, page_token, next_page_callback);
+    let Some(ipc_format) = IpcFormat::from_repr(ipc_format) else {
+        report_result_sync(Err("Invalid IPC format.".to_string()), reply_tx, None);
+        return;
+    };
+    let where_clause = if where_clause.is_null() {
+        None
+    } else {
+        Some(unsafe {
+            std::ffi::CStr::from_ptr(where_clause)
+                .to_string_lossy()
+                .to_string()
+        })
+    };
+
+    // Selected columns - C array of strings
+    let selected_columns = if selected_columns.is_null() {
+        None
+    } else {
+        let mut columns = Vec::new();
+        for i in 0..selected_columns_len {
+            let column = unsafe {
+                std::ffi::CStr::from_ptr(*selected_columns.offset(i as isize))
+                    .to_string_lossy()
+                    .to_string()
+            };
+            columns.push(column);
+        }
+        Some(columns)
+    };
+
+    let full_text_search = if full_text_search.is_null() {
+        None
+    } else {
+        Some(unsafe {
+            std::ffi::CStr::from_ptr(full_text_search)
+                .to_string_lossy()
+                .to_string()
+        })
+    };
+
+    let query = LanceDbCommand::Query {
+        connection_handle: ConnectionHandle(connection_handle),
+        table_handle: TableHandle(table_handle),
+        batch_callback: None,
+        limit: if limit == 0 {
+            None
+        } else {
+            Some(limit as usize)
+        },
+        pagination,
+        where_clause,
+        with_row_id,
+        explain_callback: None,
+        selected_columns,
+        projections,
+        reranker: None,
+        full_text_search,
+        batch_size,
+        ipc_format,
+        stream_sender: None,
+    };
+    command_from_ffi!(
+        LanceDbCommand::OpenCursor {
+            query: Box::new(query),
+        },
+        "OpenQuery",
+        reply_tx
+    );
+}
+
+/// Vector query a table, returning a cursor as for `open_query`. Parameters
+/// are as for `vector_query`.
+///
+/// Return values:
+/// - The cursor handle, or -1 if an error occurred.
+#[no_mangle]
+pub extern "C" fn open_vector_query(
+    connection_handle: i64,
+    table_handle: i64,
+    reply_tx: ErrorReportFn,
+    limit: u64,
+    where_clause: *const c_char,
+    with_row_id: bool,
+    selected_columns: *const *const c_char,
+    selected_columns_len: u64,
+    vector_type: u32,
+    vector_blob: *const u8,
+    vector_blob_len: u64,
+    vector_num_elements: u64,
+    metric: u32,
+    n_probes: u64,
+    refine_factor: u32,
+    batch_size: u32,
+    distance_range_min: f32,
+    distance_range_max: f32,
+    ipc_format: u32,
+    offset: u64,
+    page_token: *const c_char,
+    next_page_callback: Option<extern "C" fn(*const c_char)>,
+    projection_aliases: *const *const c_char,
+    projection_expressions: *const *const c_char,
+    projections_len: u64,
+    postfilter: bool,
+    fast_search: bool,
+) {
+    let projections = projections(projection_aliases, projection_expressions, projections_len);
+    let pagination = pagination(offset, page_token, next_page_callback);
+    let Some(metric) = MetricType::from_repr(metric) else {
+        report_result_sync(Err("Invalid metric.".to_string()), reply_tx, None);
+        return;
+    };
+    let Some(ipc_format) = IpcFormat::from_repr(ipc_format) else {
+        report_result_sync(Err("Invalid IPC format.".to_string()), reply_tx, None);
+        return;
+    };
+    let where_clause = if where_clause.is_null() {
+        None
+    } else {
+        Some(unsafe {
+            std::ffi::CStr::from_ptr(where_clause)
+                .to_string_lossy()
+                .to_string()
+        })
+    };
+
+    // Selected columns - C array of strings
+    let selected_columns = if selected_columns.is_null() {
+        None
+    } else {
+        let mut columns = Vec::new();
+        for i in 0..selected_columns_len {
+            let column = unsafe {
+                std::ffi::CStr::from_ptr(*selected_columns.offset(i as isize))
+                    .to_string_lossy()
+                    .to_string()
+            };
+            columns.push(column);
+        }
+        Some(columns)
+    };
+
+    let vector_data = VectorDataType::from_blob(
+        vector_type,
+        vector_blob,
+        vector_blob_len,
+        vector_num_elements,
+    );
+
+    let query = LanceDbCommand::VectorQuery {
+        connection_handle: ConnectionHandle(connection_handle),
+        table_handle: TableHandle(table_handle),
+        batch_callback: None,
+        limit: if limit == 0 {
+            None
+        } else {
+            Some(limit as usize)
+        },
+        pagination,
+        where_clause,
+        with_row_id,
+        explain_callback: None,
+        selected_columns,
+        projections,
+        reranker: None,
+        vector_data,
+        metric: metric.into(),
+        n_probes: n_probes as usize,
+        refine_factor,
+        batch_size,
+        ipc_format,
+        distance_range_min: if distance_range_min.is_nan() {
+            None
+        } else {
+            Some(distance_range_min)
+        },
+        distance_range_max: if distance_range_max.is_nan() {
+            None
+        } else {
+            Some(distance_range_max)
+        },
+        postfilter,
+        fast_search,
+        stream_sender: None,
+    };
+    command_from_ffi!(
+        LanceDbCommand::OpenCursor {
+            query: Box::new(query),
+        },
+        "OpenVectorQuery",
+        reply_tx
+    );
+}
+
+/// Pull the next batch from a cursor. The batch is passed to
+/// `batch_callback` (as Arrow IPC) on the calling thread, before this returns.
+///
+/// Return values:
+/// - 1 if a batch was returned, 0 at the end of the results, or -1 if an error occurred.
+#[no_mangle]
+pub extern "C" fn next_batch(cursor_handle: i64, batch_callback: BlobCallback, reply_tx: ErrorReportFn) {
+    let (batch_tx, batch_rx) = tokio::sync::oneshot::channel();
+    command_from_ffi!(
+        LanceDbCommand::NextBatch {
+            cursor_handle: CursorHandle(cursor_handle),
+            batch_sender: batch_tx,
+        },
+        "NextBatch",
+        reply_tx
+    );
+    if let (Some(batch_callback), Ok(bytes)) = (batch_callback, batch_rx.blocking_recv()) {
+        batch_callback(bytes.as_ptr(), bytes.len() as u64);
+    }
+}
+
+/// Close a cursor, stopping its query if it hasn't finished.
+#[no_mangle]
+pub extern "C" fn close_cursor(cursor_handle: i64, reply_tx: ErrorReportFn) {
+    command_from_ffi!(
+        LanceDbCommand::CloseCursor {
+            cursor_handle: CursorHandle(cursor_handle),
+        },
+        "CloseCursor",
+        reply_tx
+    );
+}
@@ -0,0 +1,123 @@
+//! Writes a stream of record batches (usually query results) straight to a
+//! Parquet or Arrow IPC file on local disk.
+
+use arrow_array::RecordBatchReader;
+use arrow_ipc::writer::{FileWriter, IpcWriteOptions};
+use arrow_ipc::CompressionType;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{Compression, GzipLevel, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use std::fs::File;
+use strum::FromRepr;
+
+/// File formats that results can be exported to.
+#[derive(Debug, Clone, Copy, FromRepr)]
+#[repr(u32)]
+pub(crate) enum ExportFormat {
+    Parquet = 1,
+    /// The Arrow IPC file format.
+    Arrow = 2,
+}
+
+/// Compression codecs for exported files. Arrow IPC files only support
+/// LZ4 and Zstd.
+#[derive(Debug, Clone, Copy, FromRepr)]
+#[repr(u32)]
+pub(crate) enum ExportCompression {
+    None = 0,
+    Snappy = 1,
+    Zstd = 2,
+    Lz4 = 3,
+    Gzip = 4,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ExportOptions {
+    pub(crate) format: ExportFormat,
+    pub(crate) compression: ExportCompression,
+    /// Maximum rows per Parquet row group, or `None` for the default.
+    /// Ignored for Arrow IPC files.
+    pub(crate) row_group_size: Option<usize>,
+}
+
+/// Write every batch from `reader` to `path`, returning the number of rows
+/// written. A partially written file is removed if anything fails.
+pub(crate) fn export_batches(
+    reader: impl RecordBatchReader,
+    path: &str,
+    options: &ExportOptions,
+) -> Result<u64, String> {
+    let file = File::create(path).map_err(|e| format!("Error creating {path}: {e}"))?;
+    let result = match options.format {
+        ExportFormat::Parquet => write_parquet(reader, file, options),
+        ExportFormat::Arrow => write_arrow(reader, file, options),
+    };
+    if result.is_err() {
+        let _ = std::fs::remove_file(path);
+    }
+    result
+}
+
+fn write_parquet(
+    reader: impl RecordBatchReader,
+    file: File,
+    options: &ExportOptions,
+) -> Result<u64, String> {
+    let compression = match options.compression {
+        ExportCompression::None => Compression::UNCOMPRESSED,
+        ExportCompression::Snappy => Compression::SNAPPY,
+        ExportCompression::Zstd => Compression::ZSTD(ZstdLevel::default()),
+        ExportCompression::Lz4 => Compression::LZ4_RAW,
+        ExportCompression::Gzip => Compression::GZIP(GzipLevel::default()),
+    };
+    let mut properties = WriterProperties::builder().set_compression(compression);
+    if let Some(row_group_size) = options.row_group_size {
+        properties = properties.set_max_row_group_size(row_group_size);
+    }
+
+    let mut writer = ArrowWriter::try_new(file, reader.schema(), Some(properties.build()))
+        .map_err(|e| format!("Error writing Parquet file: {e}"))?;
+    let mut rows = 0;
+    for batch in reader {
+        let batch = batch.map_err(|e| format!("Error reading results: {e}"))?;
+        rows += batch.num_rows() as u64;
+        writer
+            .write(&batch)
+            .map_err(|e| format!("Error writing Parquet file: {e}"))?;
+    }
+    writer
+        .close()
+        .map_err(|e| format!("Error writing Parquet file: {e}"))?;
+    Ok(rows)
+}
+
+fn write_arrow(
+    reader: impl RecordBatchReader,
+    file: File,
+    options: &ExportOptions,
+) -> Result<u64, String> {
+    let compression = match options.compression {
+        ExportCompression::None => None,
+        ExportCompression::Zstd => Some(CompressionType::ZSTD),
+        ExportCompression::Lz4 => Some(CompressionType::LZ4_FRAME),
+        other => return Err(format!("Arrow IPC files do not support {other:?} compression")),
+    };
+    let write_options = IpcWriteOptions::default()
+        .try_with_compression(compression)
+        .map_err(|e| format!("Error writing Arrow file: {e}"))?;
+
+    let mut writer = FileWriter::try_new_with_options(file, &reader.schema(), write_options)
+        .map_err(|e| format!("Error writing Arrow file: {e}"))?;
+    let mut rows = 0;
+    for batch in reader {
+        let batch = batch.map_err(|e| format!("Error reading results: {e}"))?;
+        rows += batch.num_rows() as u64;
+        writer
+            .write(&batch)
+            .map_err(|e| format!("Error writing Arrow file: {e}"))?;
+    }
+    writer
+        .finish()
+        .map_err(|e| format!("Error writing Arrow file: {e}"))?;
+    Ok(rows)
+}
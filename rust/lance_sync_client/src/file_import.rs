@@ -0,0 +1,201 @@
+//! Reads Parquet, CSV and JSON-lines files from local disk as a stream of
+//! record batches, so that a file can be imported into a table without
+//! being loaded into memory (or into the host) first.
+
+use arrow_array::{RecordBatch, RecordBatchReader};
+use arrow_schema::{ArrowError, Field, Schema, SchemaRef};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use std::fs::File;
+use std::io::{BufReader, Seek, SeekFrom};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use strum::FromRepr;
+
+/// The default number of rows in each batch read from a file.
+const DEFAULT_IMPORT_BATCH_SIZE: usize = 8192;
+
+/// Receives the number of rows imported so far, and the total number of rows
+/// in the file (-1 if it isn't known up-front). Return false to cancel.
+pub(crate) type ImportProgressCallback = Option<extern "C" fn(rows: u64, total_rows: i64) -> bool>;
+
+/// File formats that can be imported.
+#[derive(Debug, Clone, Copy, FromRepr)]
+#[repr(u32)]
+pub(crate) enum ImportFormat {
+    Parquet = 1,
+    Csv = 2,
+    JsonLines = 3,
+}
+
+/// Options for reading an import file. The CSV options are ignored for
+/// other formats.
+#[derive(Debug, Clone)]
+pub(crate) struct ImportOptions {
+    pub(crate) csv_delimiter: u8,
+    pub(crate) csv_has_header: bool,
+    /// How many records to read when inferring a CSV or JSON schema. `None`
+    /// reads the whole file.
+    pub(crate) infer_schema_max_records: Option<usize>,
+    /// Rows per batch, or `None` for the default.
+    pub(crate) batch_size: Option<usize>,
+}
+
+/// An open import file: the batches, and the total row count if the file
+/// format records it.
+pub(crate) struct ImportFile {
+    pub(crate) reader: Box<dyn RecordBatchReader + Send>,
+    pub(crate) total_rows: Option<u64>,
+}
+
+/// Open a file for import. If the table already exists, its schema is used
+/// to read CSV and JSON columns with the table's types where the names match;
+/// otherwise the schema is inferred from the file.
+pub(crate) fn open_import_file(
+    path: &str,
+    format: ImportFormat,
+    options: &ImportOptions,
+    table_schema: Option<&Schema>,
+) -> Result<ImportFile, String> {
+    let file = File::open(path).map_err(|e| format!("Error opening {path}: {e}"))?;
+    let batch_size = options.batch_size.unwrap_or(DEFAULT_IMPORT_BATCH_SIZE);
+
+    match format {
+        ImportFormat::Parquet => {
+            let builder = ParquetRecordBatchReaderBuilder::try_new(file)
+                .map_err(|e| format!("Error reading Parquet file {path}: {e}"))?;
+            let total_rows = builder.metadata().file_metadata().num_rows().max(0) as u64;
+            let reader = builder
+                .with_batch_size(batch_size)
+                .build()
+                .map_err(|e| format!("Error reading Parquet file {path}: {e}"))?;
+            Ok(ImportFile {
+                reader: Box::new(reader),
+                total_rows: Some(total_rows),
+            })
+        }
+        ImportFormat::Csv => {
+            let mut file = file;
+            let csv_format = arrow_csv::reader::Format::default()
+                .with_header(options.csv_has_header)
+                .with_delimiter(options.csv_delimiter);
+            let (inferred, _) = csv_format
+                .infer_schema(&mut file, options.infer_schema_max_records)
+                .map_err(|e| format!("Error inferring CSV schema for {path}: {e}"))?;
+            file.seek(SeekFrom::Start(0))
+                .map_err(|e| format!("Error reading {path}: {e}"))?;
+            let schema = match table_schema {
+                Some(table_schema) => with_table_types(&inferred, table_schema),
+                None => Arc::new(inferred),
+            };
+            let reader = arrow_csv::ReaderBuilder::new(schema)
+                .with_format(csv_format)
+                .with_batch_size(batch_size)
+                .build(file)
+                .map_err(|e| format!("Error reading CSV file {path}: {e}"))?;
+            Ok(ImportFile {
+                reader: Box::new(reader),
+                total_rows: None,
+            })
+        }
+        ImportFormat::JsonLines => {
+            let mut file = BufReader::new(file);
+            let schema = match table_schema {
+                Some(table_schema) => Arc::new(table_schema.clone()),
+                None => {
+                    let (inferred, _) = arrow_json::reader::infer_json_schema_from_seekable(
+                        &mut file,
+                        options.infer_schema_max_records,
+                    )
+                    .map_err(|e| format!("Error inferring JSON schema for {path}: {e}"))?;
+                    Arc::new(inferred)
+                }
+            };
+            let reader = arrow_json::ReaderBuilder::new(schema)
+                .with_batch_size(batch_size)
+                .build(file)
+                .map_err(|e| format!("Error reading JSON file {path}: {e}"))?;
+            Ok(ImportFile {
+                reader: Box::new(reader),
+                total_rows: None,
+            })
+        }
+    }
+}
+
+/// Use the table's type for every inferred column that the table also has.
+/// CSV columns are positional, so the inferred column order is kept.
+fn with_table_types(inferred: &Schema, table: &Schema) -> SchemaRef {
+    let fields = inferred
+        .fields()
+        .iter()
+        .map(|field| match table.field_with_name(field.name()) {
+            Ok(table_field) => Arc::new(Field::new(
+                field.name(),
+                table_field.data_type().clone(),
+                true,
+            )),
+            Err(_) => field.clone(),
+        })
+        .collect::<Vec<_>>();
+    Arc::new(Schema::new(fields))
+}
+
+/// Wraps an import reader to count rows and report progress. If the
+/// progress callback asks to cancel, the stream ends with an error so that
+/// nothing is committed.
+pub(crate) struct ProgressReader<R> {
+    inner: R,
+    schema: SchemaRef,
+    rows: Arc<AtomicU64>,
+    total_rows: i64,
+    progress_callback: ImportProgressCallback,
+    cancelled: bool,
+}
+
+impl<R: RecordBatchReader> ProgressReader<R> {
+    pub(crate) fn new(
+        inner: R,
+        schema: SchemaRef,
+        total_rows: Option<u64>,
+        progress_callback: ImportProgressCallback,
+    ) -> (Self, Arc<AtomicU64>) {
+        let rows = Arc::new(AtomicU64::new(0));
+        let reader = Self {
+            inner,
+            schema,
+            rows: rows.clone(),
+            total_rows: total_rows.map_or(-1, |t| t as i64),
+            progress_callback,
+            cancelled: false,
+        };
+        (reader, rows)
+    }
+}
+
+impl<R: RecordBatchReader> Iterator for ProgressReader<R> {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.cancelled {
+            return None;
+        }
+        let batch = self.inner.next()?;
+        if let Ok(batch) = &batch {
+            let rows = self.rows.fetch_add(batch.num_rows() as u64, Ordering::SeqCst)
+                + batch.num_rows() as u64;
+            if let Some(cb) = self.progress_callback {
+                if !cb(rows, self.total_rows) {
+                    self.cancelled = true;
+                    return Some(Err(ArrowError::ExternalError("Import cancelled".into())));
+                }
+            }
+        }
+        Some(batch)
+    }
+}
+
+impl<R: RecordBatchReader> RecordBatchReader for ProgressReader<R> {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
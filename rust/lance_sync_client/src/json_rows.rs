@@ -0,0 +1,391 @@
+//! Converts row-oriented JSON (an array of objects, or JSON-lines) into a
+//! record batch using a table's schema, so that callers can add rows as
+//! dictionaries instead of building Arrow data themselves.
+//!
+//! Only the table columns that appear in at least one row are built; the
+//! rest are left for `conform_batch` (or the embedding functions) to fill.
+//! Within a column, missing keys and JSON nulls become nulls. String columns
+//! only take JSON strings, rather than silently storing a number's or a
+//! boolean's text. Float vectors
+//! are built as variable-length lists, so that vectors of the wrong
+//! dimension are dealt with by the bad-vector policy.
+
+use arrow_array::{
+    new_empty_array, Array, ArrayRef, FixedSizeListArray, Float64Array, GenericListArray,
+    OffsetSizeTrait, RecordBatch, StringArray, StructArray,
+};
+use arrow_buffer::{NullBuffer, OffsetBuffer};
+use arrow_cast::{cast, cast_with_options, CastOptions};
+use arrow_schema::{DataType, Field, FieldRef, Fields, Schema};
+use serde_json::{Map, Value};
+use std::sync::Arc;
+
+pub(crate) type JsonRow = Map<String, Value>;
+
+/// Parse a JSON array of objects, or one object per line (JSON-lines).
+pub(crate) fn parse_json_rows(text: &str) -> Result<Vec<JsonRow>, String> {
+    let values = if text.trim_start().starts_with('[') {
+        serde_json::from_str::<Vec<Value>>(text).map_err(|e| format!("Invalid JSON: {e}"))?
+    } else {
+        text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .enumerate()
+            .map(|(row, line)| {
+                serde_json::from_str(line).map_err(|e| format!("Row {row}: invalid JSON: {e}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    if values.is_empty() {
+        return Err("No rows to add.".to_string());
+    }
+    values
+        .into_iter()
+        .enumerate()
+        .map(|(row, value)| match value {
+            Value::Object(row) => Ok(row),
+            other => Err(format!("Row {row}: expected an object, found {}", describe(&other))),
+        })
+        .collect()
+}
+
+/// Build a record batch from parsed rows, using the table's column types.
+pub(crate) fn json_rows_to_batch(rows: &[JsonRow], table: &Schema) -> Result<RecordBatch, String> {
+    for (row, values) in rows.iter().enumerate() {
+        if let Some(key) = values.keys().find(|key| table.field_with_name(key).is_err()) {
+            return Err(format!("Row {row}: field `{key}` is not in the table schema"));
+        }
+    }
+
+    let row_numbers = (0..rows.len()).collect::<Vec<_>>();
+    let mut fields = Vec::new();
+    let mut columns = Vec::new();
+    for field in table.fields() {
+        if !rows.iter().any(|row| row.contains_key(field.name())) {
+            continue;
+        }
+        let values = rows
+            .iter()
+            .map(|row| row.get(field.name()).filter(|value| !value.is_null()))
+            .collect::<Vec<_>>();
+        let column = to_array(&values, &row_numbers, field.name(), field)?;
+        fields.push(Field::new(field.name(), column.data_type().clone(), field.is_nullable()));
+        columns.push(column);
+    }
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)
+        .map_err(|e| format!("Error building record batch: {e}"))
+}
+
+fn row_error(row: usize, path: &str, problem: impl std::fmt::Display) -> String {
+    format!("Row {row}: field `{path}` {problem}")
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Array(_) => "an array".to_string(),
+        Value::Object(_) => "an object".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Convert one column's values. `rows` holds the input row of each value,
+/// for error messages; nested values share their parent's row.
+fn to_array(
+    values: &[Option<&Value>],
+    rows: &[usize],
+    path: &str,
+    field: &FieldRef,
+) -> Result<ArrayRef, String> {
+    if !field.is_nullable() {
+        if let Some(i) = values.iter().position(Option::is_none) {
+            return Err(row_error(rows[i], path, "is missing, and the column isn't nullable"));
+        }
+    }
+
+    match field.data_type() {
+        DataType::Float16 | DataType::Float32 | DataType::Float64 => {
+            let floats = values
+                .iter()
+                .zip(rows)
+                .map(|(value, row)| match value {
+                    None => Ok(None),
+                    Some(value) => value.as_f64().map(Some).ok_or_else(|| {
+                        row_error(*row, path, format!("expected a number, found {}", describe(value)))
+                    }),
+                })
+                .collect::<Result<Float64Array, _>>()?;
+            cast(&floats, field.data_type()).map_err(|e| format!("`{path}`: {e}"))
+        }
+        DataType::FixedSizeList(item, _) if item.data_type().is_floating() => {
+            // Built as a list, so that the dimension is checked by the bad-vector policy.
+            let item = Arc::new(Field::new_list_field(item.data_type().clone(), true));
+            to_list::<i32>(values, rows, path, &item, None)
+        }
+        DataType::FixedSizeList(item, size) => {
+            let list = to_list::<i32>(values, rows, path, item, Some(*size as usize))?;
+            let Some(list) = list.as_any().downcast_ref::<GenericListArray<i32>>() else {
+                return Err(format!("`{path}`: expected a list of {}", item.data_type()));
+            };
+            FixedSizeListArray::try_new(
+                item.clone(),
+                *size,
+                list.values().clone(),
+                list.nulls().cloned(),
+            )
+            .map(|array| Arc::new(array) as ArrayRef)
+            .map_err(|e| format!("`{path}`: {e}"))
+        }
+        DataType::List(item) => to_list::<i32>(values, rows, path, item, None),
+        DataType::LargeList(item) => to_list::<i64>(values, rows, path, item, None),
+        DataType::Struct(fields) => to_struct(values, rows, path, fields),
+        data_type => to_scalar(values, rows, path, data_type),
+    }
+}
+
+/// Build a list column. With `size` set, every non-null list must have that
+/// many items, and null lists get that many null items.
+fn to_list<O: OffsetSizeTrait>(
+    values: &[Option<&Value>],
+    rows: &[usize],
+    path: &str,
+    item: &FieldRef,
+    size: Option<usize>,
+) -> Result<ArrayRef, String> {
+    let mut items = Vec::new();
+    let mut item_rows = Vec::new();
+    let mut lengths = Vec::with_capacity(values.len());
+    for (value, row) in values.iter().zip(rows) {
+        match value {
+            None => {
+                let length = size.unwrap_or(0);
+                items.resize(items.len() + length, None);
+                item_rows.resize(item_rows.len() + length, *row);
+                lengths.push(length);
+            }
+            Some(Value::Array(array)) => {
+                if let Some(size) = size.filter(|size| *size != array.len()) {
+                    let problem = format!("expected {size} values, found {}", array.len());
+                    return Err(row_error(*row, path, problem));
+                }
+                items.extend(array.iter().map(|item| Some(item).filter(|item| !item.is_null())));
+                item_rows.resize(item_rows.len() + array.len(), *row);
+                lengths.push(array.len());
+            }
+            Some(value) => {
+                let problem = format!("expected an array, found {}", describe(value));
+                return Err(row_error(*row, path, problem));
+            }
+        }
+    }
+
+    let item_values = if items.is_empty() {
+        new_empty_array(item.data_type())
+    } else {
+        to_array(&items, &item_rows, &format!("{path}[]"), item)?
+    };
+    let nulls = NullBuffer::from(values.iter().map(Option::is_some).collect::<Vec<_>>());
+    GenericListArray::<O>::try_new(
+        item.clone(),
+        OffsetBuffer::from_lengths(lengths),
+        item_values,
+        Some(nulls),
+    )
+    .map(|array| Arc::new(array) as ArrayRef)
+    .map_err(|e| format!("`{path}`: {e}"))
+}
+
+fn to_struct(
+    values: &[Option<&Value>],
+    rows: &[usize],
+    path: &str,
+    fields: &Fields,
+) -> Result<ArrayRef, String> {
+    let mut objects = Vec::with_capacity(values.len());
+    for (value, row) in values.iter().zip(rows) {
+        match value {
+            None => objects.push(None),
+            Some(Value::Object(object)) => {
+                if let Some(key) = object.keys().find(|key| fields.find(key).is_none()) {
+                    return Err(row_error(*row, &format!("{path}.{key}"), "is not in the table schema"));
+                }
+                objects.push(Some(object));
+            }
+            Some(value) => {
+                let problem = format!("expected an object, found {}", describe(value));
+                return Err(row_error(*row, path, problem));
+            }
+        }
+    }
+
+    let nulls = NullBuffer::from(objects.iter().map(Option::is_some).collect::<Vec<_>>());
+    let columns = fields
+        .iter()
+        .map(|field| {
+            let child_path = format!("{path}.{}", field.name());
+            let children = objects
+                .iter()
+                .map(|object| object.and_then(|o| o.get(field.name())).filter(|v| !v.is_null()))
+                .collect::<Vec<_>>();
+            if field.is_nullable() {
+                return to_array(&children, rows, &child_path, field);
+            }
+            // A null struct's children are masked by the struct's own nulls, so
+            // only present objects must have the field.
+            if let Some(i) = (0..objects.len()).find(|i| objects[*i].is_some() && children[*i].is_none()) {
+                return Err(row_error(rows[i], &child_path, "is missing, and the column isn't nullable"));
+            }
+            let field = Arc::new(field.as_ref().clone().with_nullable(true));
+            to_array(&children, rows, &child_path, &field)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    StructArray::try_new(fields.clone(), columns, Some(nulls))
+        .map(|array| Arc::new(array) as ArrayRef)
+        .map_err(|e| format!("`{path}`: {e}"))
+}
+
+/// Scalars are converted by their text form (so strings such as dates and
+/// timestamps parse the way Arrow casts them), and any value that doesn't
+/// convert is reported by row. String columns need JSON strings.
+fn to_scalar(
+    values: &[Option<&Value>],
+    rows: &[usize],
+    path: &str,
+    data_type: &DataType,
+) -> Result<ArrayRef, String> {
+    let is_string = matches!(data_type, DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View);
+    let strings = values
+        .iter()
+        .zip(rows)
+        .map(|(value, row)| match value {
+            None => Ok(None),
+            Some(Value::String(s)) => Ok(Some(s.clone())),
+            Some(value @ (Value::Number(_) | Value::Bool(_))) if !is_string => Ok(Some(value.to_string())),
+            Some(value) => {
+                let problem = format!("expected {data_type}, found {}", describe(value));
+                Err(row_error(*row, path, problem))
+            }
+        })
+        .collect::<Result<StringArray, _>>()?;
+    if matches!(data_type, DataType::Utf8) {
+        return Ok(Arc::new(strings));
+    }
+
+    let cast_options = CastOptions {
+        safe: true,
+        ..Default::default()
+    };
+    let array = cast_with_options(&strings, data_type, &cast_options)
+        .map_err(|_| format!("`{path}`: columns of type {data_type} can't be added from JSON"))?;
+    if let Some(i) = (0..strings.len()).find(|i| strings.is_valid(*i) && array.is_null(*i)) {
+        let value = values[i].map_or_else(|| "null".to_string(), ToString::to_string);
+        let problem = format!("cannot convert {value} to {data_type}");
+        return Err(row_error(rows[i], path, problem));
+    }
+    Ok(array)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Int64Type, TimestampMicrosecondType};
+    use arrow_schema::TimeUnit;
+
+    fn table() -> Schema {
+        Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+            Field::new("created", DataType::Timestamp(TimeUnit::Microsecond, None), true),
+            Field::new(
+                "vector",
+                DataType::FixedSizeList(Arc::new(Field::new_list_field(DataType::Float32, true)), 2),
+                true,
+            ),
+            Field::new("tags", DataType::List(Arc::new(Field::new_list_field(DataType::Utf8, true))), true),
+            Field::new(
+                "meta",
+                DataType::Struct(Fields::from(vec![Field::new("size", DataType::Int64, false)])),
+                true,
+            ),
+        ])
+    }
+
+    fn to_batch(json: &str) -> Result<RecordBatch, String> {
+        json_rows_to_batch(&parse_json_rows(json)?, &table())
+    }
+
+    #[test]
+    fn parses_arrays_and_json_lines() {
+        assert_eq!(parse_json_rows(r#"[{"id": 1}, {"id": 2}]"#).unwrap().len(), 2);
+        assert_eq!(parse_json_rows("{\"id\": 1}\n\n{\"id\": 2}\n").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn reports_rows_that_are_not_objects() {
+        assert_eq!(parse_json_rows("[{\"id\": 1}, 2]").unwrap_err(), "Row 1: expected an object, found 2");
+        let err = parse_json_rows("{\"id\": 1}\n{\"id\": ").unwrap_err();
+        assert!(err.starts_with("Row 1: invalid JSON"), "{err}");
+        assert_eq!(parse_json_rows("[]").unwrap_err(), "No rows to add.");
+    }
+
+    #[test]
+    fn builds_only_the_columns_that_appear() {
+        let batch = to_batch(r#"[{"id": 1, "name": "a"}, {"id": 2}]"#).unwrap();
+        assert_eq!(batch.schema().fields().len(), 2);
+        assert_eq!(batch.column(0).as_primitive::<Int64Type>().values().to_vec(), vec![1, 2]);
+    }
+
+    #[test]
+    fn missing_and_null_fields_become_nulls() {
+        let batch = to_batch(r#"[{"id": 1, "name": "a"}, {"id": 2, "name": null}, {"id": 3}]"#).unwrap();
+        let names = batch.column_by_name("name").unwrap().as_string::<i32>();
+        assert_eq!(names.iter().collect::<Vec<_>>(), vec![Some("a"), None, None]);
+    }
+
+    #[test]
+    fn missing_fields_of_non_nullable_columns_are_errors() {
+        let err = to_batch(r#"[{"id": 1}, {"name": "b"}]"#).unwrap_err();
+        assert_eq!(err, "Row 1: field `id` is missing, and the column isn't nullable");
+        let err = to_batch(r#"[{"id": 1, "meta": {}}]"#).unwrap_err();
+        assert_eq!(err, "Row 0: field `meta.size` is missing, and the column isn't nullable");
+    }
+
+    #[test]
+    fn errors_name_the_row_and_field() {
+        let err = to_batch(r#"[{"id": 1}, {"id": 2, "colour": "red"}]"#).unwrap_err();
+        assert_eq!(err, "Row 1: field `colour` is not in the table schema");
+        let err = to_batch(r#"[{"id": "one"}]"#).unwrap_err();
+        assert_eq!(err, "Row 0: field `id` cannot convert \"one\" to Int64");
+        let err = to_batch(r#"[{"id": 1, "tags": ["a", ["b"]]}]"#).unwrap_err();
+        assert_eq!(err, "Row 0: field `tags[]` expected Utf8, found an array");
+        let err = to_batch(r#"[{"id": 1, "meta": {"size": 1, "colour": "red"}}]"#).unwrap_err();
+        assert_eq!(err, "Row 0: field `meta.colour` is not in the table schema");
+    }
+
+    #[test]
+    fn parses_timestamps() {
+        let json = r#"[{"id": 1, "created": "2024-01-02T03:04:05Z"}, {"id": 2, "created": null}]"#;
+        let batch = to_batch(json).unwrap();
+        let created = batch.column_by_name("created").unwrap().as_primitive::<TimestampMicrosecondType>();
+        assert_eq!(created.value(0), 1_704_164_645_000_000);
+        assert!(created.is_null(1));
+
+        let err = to_batch(r#"[{"id": 1, "created": "yesterday"}]"#).unwrap_err();
+        assert!(err.starts_with("Row 0: field `created` cannot convert"), "{err}");
+    }
+
+    #[test]
+    fn rejects_numbers_and_booleans_for_string_columns() {
+        let err = to_batch(r#"[{"id": 1, "name": 5}]"#).unwrap_err();
+        assert_eq!(err, "Row 0: field `name` expected Utf8, found 5");
+        let err = to_batch(r#"[{"id": 1, "tags": [true]}]"#).unwrap_err();
+        assert_eq!(err, "Row 0: field `tags[]` expected Utf8, found true");
+    }
+
+    #[test]
+    fn float_vectors_are_left_to_the_bad_vector_policy() {
+        let batch = to_batch(r#"[{"id": 1, "vector": [1.0, 2.0]}, {"id": 2, "vector": [1.0]}]"#).unwrap();
+        let vectors = batch.column_by_name("vector").unwrap().as_list::<i32>();
+        assert_eq!(vectors.value_length(0), 2);
+        assert_eq!(vectors.value_length(1), 1);
+    }
+}
@@ -14,10 +14,24 @@
 /// This should be moved to a configuration item in the future.
 const MAX_COMMANDS: usize = 100;
 
+mod bad_vectors;
+mod c_data;
 mod connection_handler;
+mod cursor_handler;
+mod embedding_handler;
 mod event_loop;
 mod exports;
+mod file_export;
+mod file_import;
+mod json_rows;
+mod maintenance_handler;
+mod rerankers;
+mod retry_handler;
+mod schema_compat;
 mod serialization;
+mod sql_update;
 mod table_handler;
+mod write_buffer_handler;
+mod write_session_handler;
 
 pub use exports::*;
@@ -0,0 +1,288 @@
+//! Background table maintenance. Tables can be registered with a policy,
+//! and each registration gets its own Tokio task that wakes up on the
+//! check interval, evaluates the policy and runs whichever optimization
+//! steps are due. This replaces the per-service timers that call
+//! `optimize_table` from the host.
+
+use crate::connection_handler::ConnectionHandle;
+use crate::event_loop::{get_table, report_result, CompletionSender, ErrorReportFn};
+use crate::table_handler::{TableCommand, TableHandle};
+use chrono::{DateTime, Utc};
+use lancedb::table::{OptimizeAction, OptimizeOptions};
+use lancedb::Table;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::Sender;
+use tokio::time::MissedTickBehavior;
+
+/// Strongly typed maintenance registration handle.
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub(crate) struct MaintenanceHandle(pub(crate) i64);
+
+/// What the scheduler should look for on each check. Any threshold that
+/// is `None` is not evaluated.
+#[derive(Debug, Clone)]
+pub(crate) struct MaintenancePolicy {
+    /// How often the table is checked.
+    pub(crate) check_interval: std::time::Duration,
+    /// Compact the table when it has more than this many fragments.
+    pub(crate) max_fragments: Option<usize>,
+    /// Prune versions older than this.
+    pub(crate) prune_older_than: Option<chrono::Duration>,
+    /// Re-optimize indices when any index has more than this many unindexed rows.
+    pub(crate) max_unindexed_rows: Option<u64>,
+}
+
+/// The last known state of a maintenance registration.
+#[derive(Debug, Clone)]
+pub(crate) struct MaintenanceStatus {
+    /// When maintenance last did some work (not just checked).
+    pub(crate) last_run: Option<DateTime<Utc>>,
+    /// When the next check is due.
+    pub(crate) next_run: DateTime<Utc>,
+    /// How many times maintenance has done some work.
+    pub(crate) run_count: u64,
+    /// The error from the most recent check, cleared on success.
+    pub(crate) last_error: Option<String>,
+}
+
+pub(crate) enum MaintenanceCommand {
+    Register {
+        connection_handle: ConnectionHandle,
+        table_handle: TableHandle,
+        policy: MaintenancePolicy,
+        tables: Sender<TableCommand>,
+        reply_sender: ErrorReportFn,
+        completion_sender: CompletionSender,
+    },
+    Deregister {
+        handle: MaintenanceHandle,
+        reply_sender: ErrorReportFn,
+        completion_sender: CompletionSender,
+    },
+    GetStatus {
+        handle: MaintenanceHandle,
+        reply_sender: tokio::sync::oneshot::Sender<Option<MaintenanceStatus>>,
+    },
+    Quit,
+}
+
+/// A running registration: the shared status and a way to stop its task.
+struct Registration {
+    status: Arc<Mutex<MaintenanceStatus>>,
+    stop: tokio::sync::oneshot::Sender<()>,
+}
+
+pub(crate) struct MaintenanceActor;
+
+impl MaintenanceActor {
+    pub(crate) async fn start() -> Sender<MaintenanceCommand> {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(128);
+        tokio::spawn(async move {
+            let mut next_id = 1_i64;
+            let mut registrations = HashMap::<MaintenanceHandle, Registration>::new();
+
+            while let Some(command) = rx.recv().await {
+                match command {
+                    MaintenanceCommand::Register {
+                        connection_handle,
+                        table_handle,
+                        policy,
+                        tables,
+                        reply_sender,
+                        completion_sender,
+                    } => {
+                        if policy.check_interval.is_zero() {
+                            report_result(
+                                Err("Check interval must be greater than zero.".to_string()),
+                                reply_sender,
+                                Some(completion_sender),
+                            ).await;
+                            continue;
+                        }
+                        if get_table(tables.clone(), connection_handle, table_handle).await.is_none() {
+                            let err = format!("Table not found: {table_handle:?}");
+                            report_result(Err(err), reply_sender, Some(completion_sender)).await;
+                            continue;
+                        }
+
+                        let status = Arc::new(Mutex::new(MaintenanceStatus {
+                            last_run: None,
+                            next_run: next_check(&policy),
+                            run_count: 0,
+                            last_error: None,
+                        }));
+                        let (stop_tx, stop_rx) = tokio::sync::oneshot::channel();
+                        tokio::spawn(maintenance_task(
+                            tables,
+                            connection_handle,
+                            table_handle,
+                            policy,
+                            status.clone(),
+                            stop_rx,
+                        ));
+
+                        let new_id = next_id;
+                        next_id += 1;
+                        registrations.insert(
+                            MaintenanceHandle(new_id),
+                            Registration { status, stop: stop_tx },
+                        );
+                        report_result(Ok(new_id), reply_sender, Some(completion_sender)).await;
+                    }
+                    MaintenanceCommand::Deregister {
+                        handle,
+                        reply_sender,
+                        completion_sender,
+                    } => {
+                        if let Some(registration) = registrations.remove(&handle) {
+                            let _ = registration.stop.send(());
+                            report_result(Ok(0), reply_sender, Some(completion_sender)).await;
+                        } else {
+                            let err = format!("Maintenance registration not found: {handle:?}");
+                            report_result(Err(err), reply_sender, Some(completion_sender)).await;
+                        }
+                    }
+                    MaintenanceCommand::GetStatus {
+                        handle,
+                        reply_sender,
+                    } => {
+                        let status = registrations
+                            .get(&handle)
+                            .and_then(|r| r.status.lock().ok().map(|s| s.clone()));
+                        let _ = reply_sender.send(status);
+                    }
+                    MaintenanceCommand::Quit => {
+                        for (_, registration) in registrations.drain() {
+                            let _ = registration.stop.send(());
+                        }
+                        break;
+                    }
+                }
+            }
+        });
+        tx
+    }
+}
+
+fn next_check(policy: &MaintenancePolicy) -> DateTime<Utc> {
+    let interval = chrono::Duration::from_std(policy.check_interval).unwrap_or(chrono::Duration::MAX);
+    Utc::now().checked_add_signed(interval).unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+/// Runs until the stop signal arrives (or its sender is dropped), checking
+/// the table once per interval.
+async fn maintenance_task(
+    tables: Sender<TableCommand>,
+    connection_handle: ConnectionHandle,
+    table_handle: TableHandle,
+    policy: MaintenancePolicy,
+    status: Arc<Mutex<MaintenanceStatus>>,
+    mut stop: tokio::sync::oneshot::Receiver<()>,
+) {
+    let mut interval = tokio::time::interval(policy.check_interval);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    // The first tick completes immediately; the first check waits a full interval.
+    interval.tick().await;
+
+    loop {
+        tokio::select! {
+            _ = &mut stop => break,
+            _ = interval.tick() => {
+                let result = match get_table(tables.clone(), connection_handle, table_handle).await {
+                    Some(table) => run_maintenance(&table, &policy).await,
+                    None => Err(format!("Table not found: {table_handle:?}")),
+                };
+                let Ok(mut status) = status.lock() else {
+                    break;
+                };
+                match result {
+                    Ok(true) => {
+                        status.last_run = Some(Utc::now());
+                        status.run_count += 1;
+                        status.last_error = None;
+                    }
+                    Ok(false) => {
+                        status.last_error = None;
+                    }
+                    Err(e) => {
+                        status.last_error = Some(e);
+                    }
+                }
+                status.next_run = next_check(&policy);
+            }
+        }
+    }
+}
+
+/// Evaluate the policy against the table, running any steps that are due.
+/// Returns `true` if any work was done.
+async fn run_maintenance(table: &Table, policy: &MaintenancePolicy) -> Result<bool, String> {
+    let mut did_work = false;
+
+    if let Some(max_fragments) = policy.max_fragments {
+        let fragments = match table.as_native() {
+            Some(native) => native.count_fragments().await,
+            None => 0,
+        };
+        if fragments > max_fragments {
+            table
+                .optimize(OptimizeAction::Compact { options: Default::default(), remap_options: None })
+                .await
+                .map_err(|e| format!("Error compacting table: {e:?}"))?;
+            did_work = true;
+        }
+    }
+
+    if let Some(older_than) = policy.prune_older_than {
+        let versions = table
+            .list_versions()
+            .await
+            .map_err(|e| format!("Error listing table versions: {e:?}"))?;
+        let latest = versions.iter().map(|v| v.version).max();
+        let cutoff = Utc::now() - older_than;
+        let has_old_versions = versions
+            .iter()
+            .any(|v| Some(v.version) != latest && v.timestamp < cutoff);
+        if has_old_versions {
+            table
+                .optimize(OptimizeAction::Prune {
+                    older_than: Some(older_than),
+                    delete_unverified: None,
+                    error_if_tagged_old_versions: None,
+                })
+                .await
+                .map_err(|e| format!("Error pruning table: {e:?}"))?;
+            did_work = true;
+        }
+    }
+
+    if let Some(max_unindexed_rows) = policy.max_unindexed_rows {
+        let indices = table
+            .list_indices()
+            .await
+            .map_err(|e| format!("Error listing table indices: {e:?}"))?;
+        let mut needs_optimize = false;
+        for index in indices {
+            let stats = table
+                .index_stats(&index.name)
+                .await
+                .map_err(|e| format!("Error getting index stats: {e:?}"))?;
+            if let Some(stats) = stats {
+                if stats.num_unindexed_rows as u64 > max_unindexed_rows {
+                    needs_optimize = true;
+                    break;
+                }
+            }
+        }
+        if needs_optimize {
+            table
+                .optimize(OptimizeAction::Index(OptimizeOptions::default()))
+                .await
+                .map_err(|e| format!("Error optimizing indices: {e:?}"))?;
+            did_work = true;
+        }
+    }
+
+    Ok(did_work)
+}
@@ -0,0 +1,499 @@
+//! Reranking of search results. A hybrid query runs a vector search and a
+//! full-text search over the same table, and a reranker fuses the two result
+//! sets into one; vector and full-text queries can also be reranked on their
+//! own. Results come back ordered by a `_relevance_score`, and keep the
+//! vector search's `_distance` and the full-text search's `_score` (null if
+//! that search didn't find the row).
+//!
+//! Reciprocal Rank Fusion and linear combination are built in. Other
+//! rerankers (such as a cross-encoder) can be implemented by the host: it
+//! registers a named callback, which scores candidate rows sent to it as
+//! Arrow IPC.
+
+use crate::serialization::{batch_to_bytes, IpcFormat};
+use crate::RerankCallback;
+use arrow_array::cast::AsArray;
+use arrow_array::types::{Float32Type, UInt64Type};
+use arrow_array::{ArrayRef, Float32Array, RecordBatch, UInt32Array, UInt64Array};
+use arrow_cast::cast;
+use arrow_schema::{ArrowError, DataType, Field, Schema};
+use arrow_select::interleave::interleave;
+use arrow_select::take::take_record_batch;
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::sync::Arc;
+use strum::FromRepr;
+use tokio::sync::mpsc::Sender;
+use tokio::task::spawn_blocking;
+
+pub(crate) const DISTANCE: &str = "_distance";
+pub(crate) const SCORE: &str = "_score";
+pub(crate) const RELEVANCE_SCORE: &str = "_relevance_score";
+pub(crate) const ROW_ID: &str = "_rowid";
+
+/// The `k` Reciprocal Rank Fusion uses unless told otherwise.
+pub(crate) const DEFAULT_RRF_K: f32 = 60.0;
+
+/// The weight linear combination gives the vector search unless told
+/// otherwise; the full-text search gets the rest.
+pub(crate) const DEFAULT_VECTOR_WEIGHT: f32 = 0.7;
+
+/// Reranker types, as passed over FFI.
+#[derive(Debug, Copy, Clone, FromRepr)]
+#[repr(u32)]
+pub(crate) enum RerankerKind {
+    ReciprocalRankFusion = 1,
+    LinearCombination = 2,
+    Host = 3,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Reranker {
+    /// Each row scores `1 / (k + rank)` (ranks start at 1) in every result
+    /// set it appears in, and the scores are summed. A larger `k` gives the
+    /// top few ranks less of an edge.
+    ReciprocalRankFusion { k: f32 },
+    /// A weighted sum of the vector and full-text scores, each scaled to
+    /// 0..1 over the candidates (distances are inverted, so nearer is
+    /// higher). A search that didn't find a row scores it 0.
+    LinearCombination { vector_weight: f32 },
+    /// A reranker registered by the host with `register_reranker`.
+    Host { name: String },
+}
+
+pub(crate) enum RerankerCommand {
+    Register {
+        name: String,
+        callback: RerankCallback,
+    },
+    Get {
+        name: String,
+        reply_sender: tokio::sync::oneshot::Sender<Option<RerankCallback>>,
+    },
+    Quit,
+}
+
+pub(crate) struct RerankerActor;
+
+impl RerankerActor {
+    pub(crate) async fn start() -> Sender<RerankerCommand> {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(128);
+        tokio::spawn(async move {
+            let mut callbacks = HashMap::<String, RerankCallback>::new();
+
+            while let Some(command) = rx.recv().await {
+                match command {
+                    RerankerCommand::Register { name, callback } => {
+                        callbacks.insert(name, callback);
+                    }
+                    RerankerCommand::Get { name, reply_sender } => {
+                        let _ = reply_sender.send(callbacks.get(&name).copied());
+                    }
+                    RerankerCommand::Quit => break,
+                }
+            }
+        });
+        tx
+    }
+}
+
+/// Get a host reranker by name.
+async fn get_reranker(rerankers: Sender<RerankerCommand>, name: &str) -> Option<RerankCallback> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    rerankers
+        .send(RerankerCommand::Get {
+            name: name.to_string(),
+            reply_sender: tx,
+        })
+        .await
+        .ok()?;
+    rx.await.ok().flatten()
+}
+
+/// Rows to rerank, with where each appeared in the vector and full-text
+/// results (in rank order, starting at 0).
+struct Candidates {
+    batch: RecordBatch,
+    vector_ranks: Vec<Option<usize>>,
+    fts_ranks: Vec<Option<usize>>,
+}
+
+/// Read a result column as `f32`s, or `None` if it isn't there.
+fn f32_column(batch: &RecordBatch, name: &str) -> Result<Option<Float32Array>, ArrowError> {
+    let Some(column) = batch.column_by_name(name) else {
+        return Ok(None);
+    };
+    Ok(Some(cast(column, &DataType::Float32)?.as_primitive::<Float32Type>().clone()))
+}
+
+/// Read the row IDs of a result set.
+fn row_ids(batch: &RecordBatch) -> Result<UInt64Array, ArrowError> {
+    let column = batch
+        .column_by_name(ROW_ID)
+        .ok_or_else(|| ArrowError::SchemaError(format!("Results have no {ROW_ID} column")))?;
+    Ok(cast(column, &DataType::UInt64)?.as_primitive::<UInt64Type>().clone())
+}
+
+/// Gather the rows found by either search. Results from a single search are
+/// used as they are; results from both are matched up by `_rowid` (which
+/// both must have), taking each row's columns from the vector results if
+/// it's in both.
+fn merge_results(
+    vector: Option<&RecordBatch>,
+    fts: Option<&RecordBatch>,
+) -> Result<Candidates, ArrowError> {
+    let (vector, fts) = match (vector, fts) {
+        (Some(vector), Some(fts)) => (vector, fts),
+        (Some(vector), None) => {
+            return Ok(Candidates {
+                batch: vector.clone(),
+                vector_ranks: (0..vector.num_rows()).map(Some).collect(),
+                fts_ranks: vec![None; vector.num_rows()],
+            })
+        }
+        (None, Some(fts)) => {
+            return Ok(Candidates {
+                batch: fts.clone(),
+                vector_ranks: vec![None; fts.num_rows()],
+                fts_ranks: (0..fts.num_rows()).map(Some).collect(),
+            })
+        }
+        (None, None) => {
+            return Err(ArrowError::InvalidArgumentError("No results to rerank".to_string()))
+        }
+    };
+
+    let mut row_id_order = Vec::<u64>::new();
+    let mut ranks = HashMap::<u64, (Option<usize>, Option<usize>)>::new();
+    for (rank, row_id) in row_ids(vector)?.values().iter().enumerate() {
+        row_id_order.push(*row_id);
+        ranks.entry(*row_id).or_default().0 = Some(rank);
+    }
+    for (rank, row_id) in row_ids(fts)?.values().iter().enumerate() {
+        let entry = ranks.entry(*row_id).or_default();
+        if entry.0.is_none() && entry.1.is_none() {
+            row_id_order.push(*row_id);
+        }
+        entry.1 = Some(rank);
+    }
+    let (vector_ranks, fts_ranks): (Vec<_>, Vec<_>) =
+        row_id_order.iter().map(|row_id| ranks[row_id]).unzip();
+
+    let indices: Vec<(usize, usize)> = vector_ranks
+        .iter()
+        .zip(&fts_ranks)
+        .map(|ranks| match ranks {
+            (Some(i), _) => (0, *i),
+            (None, Some(i)) => (1, *i),
+            (None, None) => unreachable!("merged rows come from a result set"),
+        })
+        .collect();
+    let mut fields = Vec::new();
+    let mut columns: Vec<ArrayRef> = Vec::new();
+    for field in vector.schema().fields() {
+        if [DISTANCE, SCORE, RELEVANCE_SCORE, ROW_ID].contains(&field.name().as_str()) {
+            continue;
+        }
+        let fts_column = fts.column_by_name(field.name()).ok_or_else(|| {
+            ArrowError::SchemaError(format!("Full-text results have no {} column", field.name()))
+        })?;
+        let vector_column = vector.column_by_name(field.name()).ok_or_else(|| {
+            ArrowError::SchemaError(format!("Vector results have no {} column", field.name()))
+        })?;
+        columns.push(interleave(&[vector_column.as_ref(), fts_column.as_ref()], &indices)?);
+        fields.push(field.as_ref().clone());
+    }
+
+    let distances = f32_column(vector, DISTANCE)?
+        .ok_or_else(|| ArrowError::SchemaError(format!("Vector results have no {DISTANCE} column")))?;
+    let scores = f32_column(fts, SCORE)?
+        .ok_or_else(|| ArrowError::SchemaError(format!("Full-text results have no {SCORE} column")))?;
+    let distances: Float32Array = vector_ranks.iter().map(|rank| rank.map(|i| distances.value(i))).collect();
+    let scores: Float32Array = fts_ranks.iter().map(|rank| rank.map(|i| scores.value(i))).collect();
+    fields.push(Field::new(DISTANCE, DataType::Float32, true));
+    columns.push(Arc::new(distances));
+    fields.push(Field::new(SCORE, DataType::Float32, true));
+    columns.push(Arc::new(scores));
+    fields.push(Field::new(ROW_ID, DataType::UInt64, false));
+    columns.push(Arc::new(UInt64Array::from(row_id_order)));
+
+    Ok(Candidates {
+        batch: RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)?,
+        vector_ranks,
+        fts_ranks,
+    })
+}
+
+fn reciprocal_rank_fusion(candidates: &Candidates, k: f32) -> Vec<f32> {
+    let score = |rank: Option<usize>| rank.map_or(0.0, |rank| 1.0 / (k + rank as f32 + 1.0));
+    candidates
+        .vector_ranks
+        .iter()
+        .zip(&candidates.fts_ranks)
+        .map(|(vector, fts)| score(*vector) + score(*fts))
+        .collect()
+}
+
+/// Scale a column's values to 0..1, or all to 1 if they're all the same.
+/// Nulls (rows the search didn't find) come back as `None`.
+fn normalize(column: Option<Float32Array>, rows: usize, invert: bool) -> Vec<Option<f32>> {
+    let Some(column) = column else {
+        return vec![None; rows];
+    };
+    let (min, max) = column
+        .iter()
+        .flatten()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), v| (min.min(v), max.max(v)));
+    column
+        .iter()
+        .map(|value| {
+            value.map(|v| {
+                let scaled = if max > min { (v - min) / (max - min) } else { 1.0 };
+                if invert && max > min {
+                    1.0 - scaled
+                } else {
+                    scaled
+                }
+            })
+        })
+        .collect()
+}
+
+fn linear_combination(candidates: &Candidates, vector_weight: f32) -> Result<Vec<f32>, ArrowError> {
+    let rows = candidates.batch.num_rows();
+    let vector = normalize(f32_column(&candidates.batch, DISTANCE)?, rows, true);
+    let fts = normalize(f32_column(&candidates.batch, SCORE)?, rows, false);
+    Ok(vector
+        .iter()
+        .zip(&fts)
+        .map(|(v, t)| vector_weight * v.unwrap_or(0.0) + (1.0 - vector_weight) * t.unwrap_or(0.0))
+        .collect())
+}
+
+/// Have a host reranker score the candidates.
+async fn host_scores(
+    callback: RerankCallback,
+    query_text: Option<String>,
+    candidates: &RecordBatch,
+    ipc_format: IpcFormat,
+) -> Result<Vec<f32>, String> {
+    let rows = candidates.num_rows();
+    if rows == 0 {
+        return Ok(Vec::new());
+    }
+    let bytes = batch_to_bytes(candidates, &candidates.schema(), ipc_format)
+        .map_err(|e| format!("Unable to convert candidates to bytes: {e:?}"))?;
+    let query_text = query_text
+        .map(CString::new)
+        .transpose()
+        .map_err(|e| format!("Query text contains a null character: {e}"))?;
+    spawn_blocking(move || {
+        let mut scores = vec![0.0_f32; rows];
+        let ok = callback(
+            query_text.as_ref().map_or(std::ptr::null(), |text| text.as_ptr()),
+            bytes.as_ptr(),
+            bytes.len() as u64,
+            scores.as_mut_ptr(),
+            rows as u64,
+        );
+        if ok {
+            Ok(scores)
+        } else {
+            Err("Reranker reported an error.".to_string())
+        }
+    })
+    .await
+    .map_err(|e| format!("Error running reranker: {e:?}"))?
+}
+
+/// Order the candidates by score, best first, keeping at most `limit` rows
+/// and adding the scores as `_relevance_score`. `_rowid` stays (as the last
+/// column) only if `with_row_id` is set.
+fn rank(
+    candidates: &RecordBatch,
+    scores: Vec<f32>,
+    limit: Option<usize>,
+    with_row_id: bool,
+) -> Result<RecordBatch, ArrowError> {
+    let mut order: Vec<u32> = (0..scores.len() as u32).collect();
+    // A stable sort, so ties keep the vector search's order.
+    order.sort_by(|a, b| scores[*b as usize].total_cmp(&scores[*a as usize]));
+    if let Some(limit) = limit {
+        order.truncate(limit);
+    }
+    let relevance: Float32Array = order.iter().map(|i| scores[*i as usize]).collect();
+    let ranked = take_record_batch(candidates, &UInt32Array::from(order))?;
+
+    let schema = ranked.schema();
+    let mut fields = Vec::new();
+    let mut columns = Vec::new();
+    for (field, column) in schema.fields().iter().zip(ranked.columns()) {
+        if field.name() != ROW_ID && field.name() != RELEVANCE_SCORE {
+            fields.push(field.clone());
+            columns.push(column.clone());
+        }
+    }
+    fields.push(Arc::new(Field::new(RELEVANCE_SCORE, DataType::Float32, false)));
+    columns.push(Arc::new(relevance));
+    if let (true, Ok(index)) = (with_row_id, schema.index_of(ROW_ID)) {
+        fields.push(schema.field(index).clone().into());
+        columns.push(ranked.column(index).clone());
+    }
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)
+}
+
+/// Rerank vector results, full-text results, or both (which are fused into
+/// one set). Each set must be in rank order; fusing needs both to have a
+/// `_rowid` column. `query_text` is passed to host rerankers, and host
+/// rerankers receive the candidates in `ipc_format`.
+pub(crate) async fn rerank(
+    rerankers: Sender<RerankerCommand>,
+    reranker: &Reranker,
+    query_text: Option<String>,
+    vector: Option<&RecordBatch>,
+    fts: Option<&RecordBatch>,
+    limit: Option<usize>,
+    with_row_id: bool,
+    ipc_format: IpcFormat,
+) -> Result<RecordBatch, String> {
+    let candidates = merge_results(vector, fts).map_err(|e| format!("Error merging results: {e}"))?;
+    let scores = match reranker {
+        Reranker::ReciprocalRankFusion { k } => reciprocal_rank_fusion(&candidates, *k),
+        Reranker::LinearCombination { vector_weight } => linear_combination(&candidates, *vector_weight)
+            .map_err(|e| format!("Error reading scores: {e}"))?,
+        Reranker::Host { name } => {
+            let Some(callback) = get_reranker(rerankers, name).await else {
+                return Err(format!("Reranker not found: {name}"));
+            };
+            host_scores(callback, query_text, &candidates.batch, ipc_format).await?
+        }
+    };
+    rank(&candidates.batch, scores, limit, with_row_id).map_err(|e| format!("Error ranking results: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::StringArray;
+
+    fn results(ids: &[&str], score_column: &str, scores: &[f32], row_ids: &[u64]) -> RecordBatch {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Utf8, false),
+            Field::new(score_column, DataType::Float32, false),
+            Field::new(ROW_ID, DataType::UInt64, false),
+        ]);
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(ids.to_vec())),
+            Arc::new(Float32Array::from(scores.to_vec())),
+            Arc::new(UInt64Array::from(row_ids.to_vec())),
+        ];
+        RecordBatch::try_new(Arc::new(schema), columns).unwrap()
+    }
+
+    /// Vector results a, b, c and full-text results c, d.
+    fn vector_results() -> RecordBatch {
+        results(&["a", "b", "c"], DISTANCE, &[0.1, 0.2, 0.3], &[1, 2, 3])
+    }
+
+    fn fts_results() -> RecordBatch {
+        results(&["c", "d"], SCORE, &[5.0, 3.0], &[3, 4])
+    }
+
+    fn rerank_with(
+        reranker: &Reranker,
+        vector: Option<&RecordBatch>,
+        fts: Option<&RecordBatch>,
+        limit: Option<usize>,
+        with_row_id: bool,
+    ) -> RecordBatch {
+        let candidates = merge_results(vector, fts).unwrap();
+        let scores = match reranker {
+            Reranker::ReciprocalRankFusion { k } => reciprocal_rank_fusion(&candidates, *k),
+            Reranker::LinearCombination { vector_weight } => {
+                linear_combination(&candidates, *vector_weight).unwrap()
+            }
+            Reranker::Host { .. } => unreachable!("host rerankers need a callback"),
+        };
+        rank(&candidates.batch, scores, limit, with_row_id).unwrap()
+    }
+
+    fn ids(batch: &RecordBatch) -> Vec<&str> {
+        batch.column_by_name("id").unwrap().as_string::<i32>().iter().flatten().collect()
+    }
+
+    fn floats(batch: &RecordBatch, name: &str) -> Vec<Option<f32>> {
+        batch.column_by_name(name).unwrap().as_primitive::<Float32Type>().iter().collect()
+    }
+
+    fn column_names(batch: &RecordBatch) -> Vec<String> {
+        batch.schema().fields().iter().map(|field| field.name().clone()).collect()
+    }
+
+    #[test]
+    fn rrf_puts_rows_found_by_both_searches_first() {
+        let rrf = Reranker::ReciprocalRankFusion { k: DEFAULT_RRF_K };
+        let ranked = rerank_with(&rrf, Some(&vector_results()), Some(&fts_results()), None, false);
+        // b and d tie (both second in one search); b keeps its place ahead of d.
+        assert_eq!(ids(&ranked), vec!["c", "a", "b", "d"]);
+        let relevance = floats(&ranked, RELEVANCE_SCORE);
+        assert_eq!(relevance[0], Some(1.0 / 63.0 + 1.0 / 61.0));
+        assert_eq!(relevance[1], Some(1.0 / 61.0));
+        assert_eq!(relevance[2], relevance[3]);
+    }
+
+    #[test]
+    fn ties_keep_the_vector_search_order() {
+        let vector = results(&["a", "b", "c"], DISTANCE, &[0.5, 0.5, 0.5], &[1, 2, 3]);
+        let linear = Reranker::LinearCombination { vector_weight: 1.0 };
+        let ranked = rerank_with(&linear, Some(&vector), None, None, false);
+        assert_eq!(ids(&ranked), vec!["a", "b", "c"]);
+        assert_eq!(floats(&ranked, RELEVANCE_SCORE), vec![Some(1.0); 3]);
+    }
+
+    #[test]
+    fn linear_combination_weights_normalized_scores() {
+        let linear = Reranker::LinearCombination {
+            vector_weight: DEFAULT_VECTOR_WEIGHT,
+        };
+        let ranked = rerank_with(&linear, Some(&vector_results()), Some(&fts_results()), Some(3), false);
+        assert_eq!(ids(&ranked), vec!["a", "b", "c"]);
+        let relevance: Vec<f32> = floats(&ranked, RELEVANCE_SCORE).into_iter().flatten().collect();
+        for (actual, expected) in relevance.iter().zip([0.7, 0.35, 0.3]) {
+            assert!((actual - expected).abs() < 1e-6, "{actual} != {expected}");
+        }
+    }
+
+    #[test]
+    fn fused_results_keep_each_searchs_scores() {
+        let rrf = Reranker::ReciprocalRankFusion { k: DEFAULT_RRF_K };
+        let ranked = rerank_with(&rrf, Some(&vector_results()), Some(&fts_results()), None, false);
+        assert_eq!(column_names(&ranked), vec!["id", DISTANCE, SCORE, RELEVANCE_SCORE]);
+        assert_eq!(floats(&ranked, DISTANCE), vec![Some(0.3), Some(0.1), Some(0.2), None]);
+        assert_eq!(floats(&ranked, SCORE), vec![Some(5.0), None, None, Some(3.0)]);
+    }
+
+    #[test]
+    fn row_ids_are_kept_last_only_if_asked_for() {
+        let rrf = Reranker::ReciprocalRankFusion { k: DEFAULT_RRF_K };
+        let ranked = rerank_with(&rrf, Some(&vector_results()), Some(&fts_results()), None, true);
+        assert_eq!(column_names(&ranked), vec!["id", DISTANCE, SCORE, RELEVANCE_SCORE, ROW_ID]);
+        let row_ids = ranked.column_by_name(ROW_ID).unwrap().as_primitive::<UInt64Type>();
+        assert_eq!(row_ids.values().to_vec(), vec![3, 1, 2, 4]);
+
+        let ranked = rerank_with(&rrf, Some(&vector_results()), None, None, false);
+        assert_eq!(column_names(&ranked), vec!["id", DISTANCE, RELEVANCE_SCORE]);
+    }
+
+    #[test]
+    fn fusing_needs_matching_columns() {
+        let mut fts = fts_results();
+        fts.remove_column(0);
+        let err = merge_results(Some(&vector_results()), Some(&fts)).err().unwrap();
+        assert!(matches!(err, ArrowError::SchemaError(_)), "{err}");
+
+        let mut vector = vector_results();
+        vector.remove_column(2);
+        let err = merge_results(Some(&vector), Some(&fts_results())).err().unwrap();
+        assert!(matches!(err, ArrowError::SchemaError(_)), "{err}");
+    }
+}
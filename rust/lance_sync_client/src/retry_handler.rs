@@ -0,0 +1,171 @@
+//! Retries for writes that fail with a commit conflict. When two processes
+//! write to the same table, one of them can lose the race to commit; the
+//! write is safe to repeat, so a table with a retry policy has its adds,
+//! merge-inserts, updates and deletes retried (with backoff) instead of
+//! failing. Policies are kept per table by a small actor, and a callback
+//! tells the host about each retry.
+
+use crate::connection_handler::ConnectionHandle;
+use crate::table_handler::TableHandle;
+use rand::Rng;
+use std::collections::HashMap;
+use std::ffi::{c_char, CString};
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::mpsc::Sender;
+use tokio::task::spawn_blocking;
+
+/// Called before each retry with the table handle, the attempt that failed
+/// (starting at 1), the delay before the next attempt in milliseconds, and
+/// the conflict's error message.
+pub(crate) type RetryCallback =
+    Option<extern "C" fn(table_handle: i64, attempt: u32, delay_ms: u64, error: *const c_char)>;
+
+/// How commit conflicts are retried.
+#[derive(Debug, Clone)]
+pub(crate) struct RetryPolicy {
+    /// The most attempts made, including the first.
+    pub(crate) max_attempts: u32,
+    /// The delay before the first retry. It doubles for each retry after that.
+    pub(crate) initial_backoff: Duration,
+    /// The longest delay between attempts.
+    pub(crate) max_backoff: Duration,
+    /// How much of each delay is random, from 0 (none) to 1 (anywhere
+    /// between zero and the full delay), so that writers don't retry in step.
+    pub(crate) jitter: f64,
+    pub(crate) retry_callback: RetryCallback,
+}
+
+impl RetryPolicy {
+    /// The delay after a failed `attempt` (starting at 1).
+    fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+            .min(self.max_backoff);
+        if self.jitter <= 0.0 {
+            return backoff;
+        }
+        let jitter = rand::thread_rng().gen_range(0.0..=self.jitter.min(1.0));
+        backoff.mul_f64(1.0 - jitter)
+    }
+}
+
+pub(crate) enum RetryCommand {
+    /// Set a table's policy; `None` removes it, so conflicts fail at once.
+    SetPolicy {
+        connection_handle: ConnectionHandle,
+        table_handle: TableHandle,
+        policy: Option<RetryPolicy>,
+    },
+    GetPolicy {
+        connection_handle: ConnectionHandle,
+        table_handle: TableHandle,
+        reply_sender: tokio::sync::oneshot::Sender<Option<RetryPolicy>>,
+    },
+    Quit,
+}
+
+pub(crate) struct RetryActor;
+
+impl RetryActor {
+    pub(crate) async fn start() -> Sender<RetryCommand> {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(128);
+        tokio::spawn(async move {
+            let mut policies = HashMap::<(ConnectionHandle, TableHandle), RetryPolicy>::new();
+
+            while let Some(command) = rx.recv().await {
+                match command {
+                    RetryCommand::SetPolicy {
+                        connection_handle,
+                        table_handle,
+                        policy,
+                    } => {
+                        let key = (connection_handle, table_handle);
+                        match policy {
+                            Some(policy) => policies.insert(key, policy),
+                            None => policies.remove(&key),
+                        };
+                    }
+                    RetryCommand::GetPolicy {
+                        connection_handle,
+                        table_handle,
+                        reply_sender,
+                    } => {
+                        let _ = reply_sender.send(policies.get(&(connection_handle, table_handle)).cloned());
+                    }
+                    RetryCommand::Quit => break,
+                }
+            }
+        });
+        tx
+    }
+}
+
+/// Get the retry policy for a table, if it has one.
+pub(crate) async fn get_retry_policy(
+    retries: Sender<RetryCommand>,
+    connection_handle: ConnectionHandle,
+    table_handle: TableHandle,
+) -> Option<RetryPolicy> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    retries
+        .send(RetryCommand::GetPolicy {
+            connection_handle,
+            table_handle,
+            reply_sender: tx,
+        })
+        .await
+        .ok()?;
+    rx.await.ok().flatten()
+}
+
+/// Run a write, repeating it while it fails with a commit conflict and the
+/// policy allows another attempt. `write` is called once per attempt, so it
+/// must build the whole operation each time. Errors are described with
+/// `context`, and say how many attempts a conflict survived.
+pub(crate) async fn retry_on_conflict<T, F, Fut>(
+    policy: Option<&RetryPolicy>,
+    table_handle: TableHandle,
+    context: &str,
+    mut write: F,
+) -> Result<T, String>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = lancedb::Result<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        let e = match write().await {
+            Ok(result) => return Ok(result),
+            Err(e) if is_commit_conflict(&e) => e,
+            Err(e) => return Err(format!("{context}: {e:?}")),
+        };
+        let Some(policy) = policy.filter(|policy| attempt < policy.max_attempts) else {
+            return Err(format!("{context}: commit conflict after {attempt} attempt(s): {e:?}"));
+        };
+
+        let delay = policy.delay(attempt);
+        if let Some(callback) = policy.retry_callback {
+            let error = CString::new(format!("{e:?}")).unwrap_or_default();
+            spawn_blocking(move || {
+                callback(table_handle.0, attempt, delay.as_millis() as u64, error.as_ptr())
+            })
+            .await
+            .ok();
+        }
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// Lance reports a lost commit race as `CommitConflict` (or
+/// `RetryableCommitConflict`), wrapped in LanceDB's own error.
+fn is_commit_conflict(e: &lancedb::Error) -> bool {
+    matches!(
+        e,
+        lancedb::Error::Lance {
+            source: lance::Error::CommitConflict { .. } | lance::Error::RetryableCommitConflict { .. },
+        }
+    )
+}
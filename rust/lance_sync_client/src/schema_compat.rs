@@ -0,0 +1,137 @@
+//! Checks incoming record batches against a table's schema before they are
+//! written, so that mismatches are reported field-by-field instead of failing
+//! deep inside Lance. Optionally casts columns to the table's types, when the
+//! conversion is a safe one.
+
+use arrow_array::{new_null_array, ArrayRef, RecordBatch};
+use arrow_cast::{cast_with_options, CastOptions};
+use arrow_schema::{ArrowError, DataType, Schema, SchemaRef};
+
+/// Returns true if values of type `from` can be converted to `to` without
+/// changing their meaning. Anything that can fail for individual values
+/// (e.g. a list of the wrong length) is rejected when the cast runs.
+fn is_safe_cast(from: &DataType, to: &DataType) -> bool {
+    use DataType::*;
+    if from.equals_datatype(to) {
+        return true;
+    }
+    match (from, to) {
+        (Float16 | Float32 | Float64, Float16 | Float32 | Float64) => true,
+        (Int8, Int16 | Int32 | Int64) | (Int16, Int32 | Int64) | (Int32, Int64) => true,
+        (UInt8, UInt16 | UInt32 | UInt64 | Int16 | Int32 | Int64)
+        | (UInt16, UInt32 | UInt64 | Int32 | Int64)
+        | (UInt32, UInt64 | Int64) => true,
+        (Utf8, LargeUtf8) | (LargeUtf8, Utf8) => true,
+        (Binary, LargeBinary) | (LargeBinary, Binary) => true,
+        // Vector columns often arrive as variable-length lists.
+        (List(from_item) | LargeList(from_item), FixedSizeList(to_item, _)) => {
+            is_safe_cast(from_item.data_type(), to_item.data_type())
+        }
+        (FixedSizeList(from_item, from_size), FixedSizeList(to_item, to_size)) => {
+            from_size == to_size && is_safe_cast(from_item.data_type(), to_item.data_type())
+        }
+        (List(from_item) | LargeList(from_item), List(to_item) | LargeList(to_item)) => {
+            is_safe_cast(from_item.data_type(), to_item.data_type())
+        }
+        _ => false,
+    }
+}
+
+/// Compare an incoming schema with a table's schema, returning one line per
+/// problem. An empty list means the data can be written (after
+/// `conform_batch`, if `auto_cast` is set).
+pub(crate) fn schema_differences(incoming: &Schema, table: &Schema, auto_cast: bool) -> Vec<String> {
+    let mut differences = Vec::new();
+
+    for table_field in table.fields() {
+        let name = table_field.name();
+        match incoming.field_with_name(name) {
+            Err(_) => {
+                if !(auto_cast && table_field.is_nullable()) {
+                    differences.push(format!(
+                        "`{name}`: missing (table expects {})",
+                        table_field.data_type()
+                    ));
+                }
+            }
+            Ok(field) if !field.data_type().equals_datatype(table_field.data_type()) => {
+                if !auto_cast {
+                    differences.push(format!(
+                        "`{name}`: type {}, table expects {}",
+                        field.data_type(),
+                        table_field.data_type()
+                    ));
+                } else if !is_safe_cast(field.data_type(), table_field.data_type()) {
+                    differences.push(format!(
+                        "`{name}`: cannot convert {} to {}",
+                        field.data_type(),
+                        table_field.data_type()
+                    ));
+                }
+            }
+            Ok(_) => {}
+        }
+    }
+
+    for field in incoming.fields() {
+        if table.field_with_name(field.name()).is_err() {
+            differences.push(format!("`{}`: not in the table schema", field.name()));
+        }
+    }
+
+    differences
+}
+
+/// Rebuild a batch with the table's schema: columns are put in the table's
+/// order, cast to the table's types, and missing (nullable) columns are
+/// filled with nulls. The batch should already have passed `schema_differences`.
+pub(crate) fn conform_batch(batch: &RecordBatch, table: &SchemaRef) -> Result<RecordBatch, ArrowError> {
+    let cast_options = CastOptions {
+        safe: false,
+        ..Default::default()
+    };
+    let columns = table
+        .fields()
+        .iter()
+        .map(|table_field| {
+            let Some(column) = batch.column_by_name(table_field.name()) else {
+                return Ok(new_null_array(table_field.data_type(), batch.num_rows()));
+            };
+            if column.data_type() == table_field.data_type() {
+                return Ok(column.clone());
+            }
+            cast_with_options(column, table_field.data_type(), &cast_options).map_err(|e| {
+                ArrowError::CastError(format!("`{}`: {e}", table_field.name()))
+            })
+        })
+        .collect::<Result<Vec<ArrayRef>, ArrowError>>()?;
+    RecordBatch::try_new(table.clone(), columns)
+}
+
+/// Check and conform every batch in an incoming set, reporting the first
+/// problem found.
+pub(crate) fn conform_batches(
+    batches: Vec<Result<RecordBatch, ArrowError>>,
+    table: &SchemaRef,
+    auto_cast: bool,
+) -> Result<Vec<RecordBatch>, String> {
+    let mut checked: Option<SchemaRef> = None;
+    let mut result = Vec::with_capacity(batches.len());
+    for batch in batches {
+        let batch = batch.map_err(|e| format!("Error reading record batch: {e}"))?;
+        if checked.as_ref() != Some(&batch.schema()) {
+            let differences = schema_differences(&batch.schema(), table, auto_cast);
+            if !differences.is_empty() {
+                return Err(format!(
+                    "Record batch does not match the table schema:\n{}",
+                    differences.join("\n")
+                ));
+            }
+            checked = Some(batch.schema());
+        }
+        let batch = conform_batch(&batch, table)
+            .map_err(|e| format!("Record batch does not match the table schema: {e}"))?;
+        result.push(batch);
+    }
+    Ok(result)
+}
@@ -1,8 +1,29 @@
-//! Provides FileReader/FileWriter Arrow IPC format conversion to/from byte arrays.
+//! Provides Arrow IPC format conversion to/from byte arrays. Incoming bytes
+//! may use either the IPC file format or the IPC stream format; outgoing
+//! batches use whichever format the caller asked for.
 
 use arrow_array::RecordBatch;
 use arrow_schema::{ArrowError, SchemaRef};
 use std::io::Cursor;
+use strum::FromRepr;
+
+/// The IPC file format starts (and ends) with these magic bytes. Anything
+/// else is treated as the stream format.
+const ARROW_FILE_MAGIC: &[u8] = b"ARROW1";
+
+/// Which Arrow IPC format to use for outgoing record batches.
+#[derive(Debug, Clone, Copy, FromRepr)]
+#[repr(u32)]
+pub(crate) enum IpcFormat {
+    /// The IPC file format, with a footer. Each buffer can be read randomly.
+    File = 1,
+    /// The IPC stream format, without a footer.
+    Stream = 2,
+}
+
+fn is_file_format(bytes: &[u8]) -> bool {
+    bytes.starts_with(ARROW_FILE_MAGIC)
+}
 
 pub(crate) fn schema_to_bytes(schema: &SchemaRef) -> Vec<u8> {
     let mut buf = vec![];
@@ -14,23 +35,40 @@ pub(crate) fn schema_to_bytes(schema: &SchemaRef) -> Vec<u8> {
 }
 
 pub(crate) fn bytes_to_schema(bytes: &[u8]) -> anyhow::Result<SchemaRef> {
-    let reader = arrow_ipc::reader::FileReader::try_new(Cursor::new(bytes), None)?;
-    let schema = reader.schema();
+    let schema = if is_file_format(bytes) {
+        arrow_ipc::reader::FileReader::try_new(Cursor::new(bytes), None)?.schema()
+    } else {
+        arrow_ipc::reader::StreamReader::try_new(Cursor::new(bytes), None)?.schema()
+    };
     Ok(schema)
 }
 
-pub(crate) fn batch_to_bytes(batch: &RecordBatch, schema: &SchemaRef) -> anyhow::Result<Vec<u8>> {
+pub(crate) fn batch_to_bytes(
+    batch: &RecordBatch,
+    schema: &SchemaRef,
+    format: IpcFormat,
+) -> anyhow::Result<Vec<u8>> {
     let mut buf = vec![];
-    {
-        let mut fw = arrow_ipc::writer::FileWriter::try_new(&mut buf, schema)?;
-        fw.write(batch)?;
-        fw.finish()?;
-    } // Scope to ensure that fw is dropped
+    match format {
+        IpcFormat::File => {
+            let mut fw = arrow_ipc::writer::FileWriter::try_new(&mut buf, schema)?;
+            fw.write(batch)?;
+            fw.finish()?;
+        }
+        IpcFormat::Stream => {
+            let mut sw = arrow_ipc::writer::StreamWriter::try_new(&mut buf, schema)?;
+            sw.write(batch)?;
+            sw.finish()?;
+        }
+    }
     Ok(buf)
 }
 
 pub(crate) fn bytes_to_batch(bytes: &[u8]) -> anyhow::Result<Vec<Result<RecordBatch, ArrowError>>> {
-    let reader = arrow_ipc::reader::FileReader::try_new(Cursor::new(bytes), None)?;
-    let batches: Vec<_> = reader.collect::<Vec<_>>().into_iter().collect::<Vec<_>>();
+    let batches: Vec<_> = if is_file_format(bytes) {
+        arrow_ipc::reader::FileReader::try_new(Cursor::new(bytes), None)?.collect()
+    } else {
+        arrow_ipc::reader::StreamReader::try_new(Cursor::new(bytes), None)?.collect()
+    };
     Ok(batches)
 }
@@ -0,0 +1,226 @@
+//! Parses SQL `UPDATE` statements, so that hosts can run
+//! `UPDATE items SET price = price * 2, label = 'sale' WHERE id < 10`
+//! as-is. The statement is broken into the table name, one expression per
+//! column, and the `WHERE` clause, which map directly onto LanceDB's update
+//! builder; the expressions themselves are left for LanceDB to evaluate,
+//! once any `table.column` references have had the table name removed.
+
+use sqlparser::ast::{visit_expressions_mut, AssignmentTarget, Expr, ObjectName, Statement, TableFactor};
+use sqlparser::dialect::GenericDialect;
+use sqlparser::parser::Parser;
+use std::ops::ControlFlow;
+
+/// A parsed `UPDATE` statement.
+#[derive(Debug)]
+pub(crate) struct SqlUpdate {
+    pub(crate) table_name: String,
+    /// `(column, expression)` pairs, in statement order.
+    pub(crate) updates: Vec<(String, String)>,
+    pub(crate) where_clause: Option<String>,
+}
+
+/// Parse a single `UPDATE <table> SET <column> = <expression>, ... [WHERE ...]`
+/// statement. Anything that can't be expressed as one LanceDB update (joins,
+/// `FROM`, `RETURNING`, tuple assignments) is rejected.
+pub(crate) fn parse_sql_update(sql: &str) -> Result<SqlUpdate, String> {
+    let mut statements =
+        Parser::parse_sql(&GenericDialect {}, sql).map_err(|e| format!("Invalid SQL: {e}"))?;
+    if statements.len() != 1 {
+        return Err(format!("Expected one UPDATE statement, found {}", statements.len()));
+    }
+    let Statement::Update {
+        table,
+        assignments,
+        from,
+        selection,
+        returning,
+        or,
+    } = statements.remove(0)
+    else {
+        return Err("Only UPDATE statements are supported.".to_string());
+    };
+
+    if !table.joins.is_empty() || from.is_some() {
+        return Err("UPDATE with joins or FROM is not supported.".to_string());
+    }
+    if returning.is_some() {
+        return Err("UPDATE ... RETURNING is not supported.".to_string());
+    }
+    if or.is_some() {
+        return Err("UPDATE OR ... is not supported.".to_string());
+    }
+    let TableFactor::Table { name, alias: None, args: None, .. } = table.relation else {
+        return Err("UPDATE must name a single table, without an alias.".to_string());
+    };
+    let table_name = last_part(&name);
+    if assignments.is_empty() {
+        return Err("UPDATE has no SET clause.".to_string());
+    }
+
+    let mut updates: Vec<(String, String)> = Vec::with_capacity(assignments.len());
+    for assignment in assignments {
+        let AssignmentTarget::ColumnName(column) = assignment.target else {
+            return Err(format!("Tuple assignments are not supported: {}", assignment.target));
+        };
+        let column = match column.0.as_slice() {
+            [column] => column.value.clone(),
+            [qualifier, column] if qualifier.value.eq_ignore_ascii_case(&table_name) => column.value.clone(),
+            _ => return Err(format!("Column `{column}` does not belong to table `{table_name}`")),
+        };
+        if updates.iter().any(|(existing, _)| *existing == column) {
+            return Err(format!("Column `{column}` is assigned more than once."));
+        }
+        let mut value = assignment.value;
+        unqualify_columns(&mut value, &table_name);
+        updates.push((column, value.to_string()));
+    }
+
+    Ok(SqlUpdate {
+        where_clause: selection.map(|mut selection| {
+            unqualify_columns(&mut selection, &table_name);
+            selection.to_string()
+        }),
+        table_name,
+        updates,
+    })
+}
+
+/// Turn `table.column` references into plain `column`s (and `table.column.field`
+/// into `column.field`), since LanceDB would read the table name as a column.
+fn unqualify_columns(expr: &mut Expr, table_name: &str) {
+    let _ = visit_expressions_mut(expr, |expr| {
+        if let Expr::CompoundIdentifier(parts) = expr {
+            if parts.len() > 1 && parts[0].value.eq_ignore_ascii_case(table_name) {
+                parts.remove(0);
+                if parts.len() == 1 {
+                    *expr = Expr::Identifier(parts.remove(0));
+                }
+            }
+        }
+        ControlFlow::<()>::Continue(())
+    });
+}
+
+/// The unquoted table name, ignoring any schema or database qualifier.
+fn last_part(name: &ObjectName) -> String {
+    name.0.last().map(|ident| ident.value.clone()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rejected(sql: &str) -> String {
+        parse_sql_update(sql).expect_err("statement should be rejected")
+    }
+
+    #[test]
+    fn parses_a_single_update() {
+        let update = parse_sql_update("UPDATE items SET price = price * 2, label = 'sale' WHERE id < 10").unwrap();
+        assert_eq!(update.table_name, "items");
+        assert_eq!(
+            update.updates,
+            vec![
+                ("price".to_string(), "price * 2".to_string()),
+                ("label".to_string(), "'sale'".to_string()),
+            ]
+        );
+        assert_eq!(update.where_clause.as_deref(), Some("id < 10"));
+    }
+
+    #[test]
+    fn where_clause_is_optional() {
+        let update = parse_sql_update("UPDATE items SET price = 0").unwrap();
+        assert_eq!(update.where_clause, None);
+    }
+
+    #[test]
+    fn ignores_schema_qualifiers_on_the_table() {
+        let update = parse_sql_update("UPDATE store.items SET price = 0").unwrap();
+        assert_eq!(update.table_name, "items");
+    }
+
+    #[test]
+    fn accepts_columns_qualified_by_the_table() {
+        let update = parse_sql_update("UPDATE items SET ITEMS.price = 0").unwrap();
+        assert_eq!(update.updates, vec![("price".to_string(), "0".to_string())]);
+    }
+
+    #[test]
+    fn removes_the_table_from_column_references() {
+        let update = parse_sql_update("UPDATE items SET price = items.price * 2 WHERE Items.id < 10").unwrap();
+        assert_eq!(update.updates, vec![("price".to_string(), "price * 2".to_string())]);
+        assert_eq!(update.where_clause.as_deref(), Some("id < 10"));
+    }
+
+    #[test]
+    fn keeps_struct_fields_when_removing_the_table() {
+        let update = parse_sql_update("UPDATE items SET size = items.meta.size WHERE meta.size > 1").unwrap();
+        assert_eq!(update.updates, vec![("size".to_string(), "meta.size".to_string())]);
+        assert_eq!(update.where_clause.as_deref(), Some("meta.size > 1"));
+    }
+
+    #[test]
+    fn rejects_multiple_statements() {
+        let err = rejected("UPDATE items SET price = 0; UPDATE items SET price = 1");
+        assert!(err.contains("found 2"), "{err}");
+    }
+
+    #[test]
+    fn rejects_other_statements() {
+        let err = rejected("DELETE FROM items WHERE id < 10");
+        assert!(err.contains("Only UPDATE"), "{err}");
+    }
+
+    #[test]
+    fn rejects_invalid_sql() {
+        let err = rejected("UPDATE items SET");
+        assert!(err.starts_with("Invalid SQL"), "{err}");
+    }
+
+    #[test]
+    fn rejects_from_and_joins() {
+        let err = rejected("UPDATE items SET price = prices.price FROM prices WHERE items.id = prices.id");
+        assert!(err.contains("FROM"), "{err}");
+        let err = rejected("UPDATE items JOIN prices ON items.id = prices.id SET items.price = prices.price");
+        assert!(err.contains("joins"), "{err}");
+    }
+
+    #[test]
+    fn rejects_returning() {
+        let err = rejected("UPDATE items SET price = 0 RETURNING id");
+        assert!(err.contains("RETURNING"), "{err}");
+    }
+
+    #[test]
+    fn rejects_update_or() {
+        let err = rejected("UPDATE OR REPLACE items SET price = 0");
+        assert!(err.contains("UPDATE OR"), "{err}");
+    }
+
+    #[test]
+    fn rejects_table_aliases() {
+        let err = rejected("UPDATE items AS i SET price = 0");
+        assert!(err.contains("alias"), "{err}");
+    }
+
+    #[test]
+    fn rejects_tuple_assignments() {
+        let err = rejected("UPDATE items SET (price, label) = (0, 'sale')");
+        assert!(err.contains("Tuple"), "{err}");
+    }
+
+    #[test]
+    fn rejects_columns_of_other_tables() {
+        let err = rejected("UPDATE items SET prices.price = 0");
+        assert!(err.contains("does not belong"), "{err}");
+        let err = rejected("UPDATE items SET store.items.price = 0");
+        assert!(err.contains("does not belong"), "{err}");
+    }
+
+    #[test]
+    fn rejects_duplicate_columns() {
+        let err = rejected("UPDATE items SET price = 0, items.price = 1");
+        assert!(err.contains("more than once"), "{err}");
+    }
+}
@@ -0,0 +1,360 @@
+//! Per-table write buffers. When a table has a buffer enabled, appends are
+//! held in memory and committed together once the buffer reaches its row
+//! count or byte size, when the flush interval passes, or when the host
+//! flushes it, so that many small appends become one commit (and one
+//! fragment) instead of many. Each buffer is a Tokio task that owns its
+//! pending batches; a durability callback tells the host when buffered rows
+//! have been committed. A failed flush keeps the rows buffered, to be
+//! committed by the next one, and flushes retry commit conflicts under the
+//! table's retry policy.
+
+use crate::connection_handler::ConnectionHandle;
+use crate::retry_handler::{get_retry_policy, retry_on_conflict, RetryCommand};
+use crate::table_handler::TableHandle;
+use arrow_array::{RecordBatch, RecordBatchIterator};
+use lancedb::table::AddDataMode;
+use lancedb::Table;
+use std::collections::HashMap;
+use std::ffi::{c_char, CString};
+use std::time::Duration;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::oneshot;
+use tokio::task::spawn_blocking;
+use tokio::time::MissedTickBehavior;
+
+/// Called after each flush with the table handle, the number of rows in the
+/// flush, and an error message (null if the rows were committed).
+pub(crate) type DurabilityCallback =
+    Option<extern "C" fn(table_handle: i64, rows: u64, error: *const c_char)>;
+
+/// When a buffer is flushed. Any limit that is `None` is not checked.
+#[derive(Debug, Clone)]
+pub(crate) struct WriteBufferPolicy {
+    /// Flush once this many rows are buffered.
+    pub(crate) max_rows: Option<usize>,
+    /// Flush once the buffered batches use this many bytes.
+    pub(crate) max_bytes: Option<usize>,
+    /// Flush whatever is buffered this often.
+    pub(crate) flush_interval: Option<Duration>,
+    pub(crate) durability_callback: DurabilityCallback,
+}
+
+enum BufferCommand {
+    Append {
+        batches: Vec<RecordBatch>,
+        reply_sender: oneshot::Sender<Result<(), String>>,
+    },
+    /// Flush now, replying with the number of rows committed.
+    Flush {
+        reply_sender: oneshot::Sender<Result<u64, String>>,
+    },
+    /// Flush and stop the buffer's task.
+    Stop {
+        reply_sender: oneshot::Sender<Result<u64, String>>,
+    },
+}
+
+/// A handle to a running buffer task.
+#[derive(Clone)]
+pub(crate) struct WriteBuffer {
+    sender: Sender<BufferCommand>,
+}
+
+impl WriteBuffer {
+    /// Add batches (already conformed to the table schema) to the buffer. If
+    /// this pushes the buffer over a limit it is flushed, and a failed flush
+    /// is returned as an error.
+    pub(crate) async fn append(&self, batches: Vec<RecordBatch>) -> Result<(), String> {
+        let (tx, rx) = oneshot::channel();
+        self.send(BufferCommand::Append { batches, reply_sender: tx }).await?;
+        rx.await.map_err(|_| "Write buffer stopped.".to_string())?
+    }
+
+    /// Commit everything buffered, returning the number of rows committed.
+    pub(crate) async fn flush(&self) -> Result<u64, String> {
+        let (tx, rx) = oneshot::channel();
+        self.send(BufferCommand::Flush { reply_sender: tx }).await?;
+        rx.await.map_err(|_| "Write buffer stopped.".to_string())?
+    }
+
+    async fn stop(&self) -> Result<u64, String> {
+        let (tx, rx) = oneshot::channel();
+        self.send(BufferCommand::Stop { reply_sender: tx }).await?;
+        rx.await.map_err(|_| "Write buffer stopped.".to_string())?
+    }
+
+    async fn send(&self, command: BufferCommand) -> Result<(), String> {
+        self.sender
+            .send(command)
+            .await
+            .map_err(|_| "Write buffer stopped.".to_string())
+    }
+}
+
+pub(crate) enum WriteBufferCommand {
+    Enable {
+        connection_handle: ConnectionHandle,
+        table_handle: TableHandle,
+        table: Table,
+        policy: WriteBufferPolicy,
+        retries: Sender<RetryCommand>,
+        reply_sender: oneshot::Sender<Result<(), String>>,
+    },
+    /// Flush the buffer and remove it, replying with the number of rows
+    /// committed by the final flush.
+    Disable {
+        connection_handle: ConnectionHandle,
+        table_handle: TableHandle,
+        reply_sender: oneshot::Sender<Result<u64, String>>,
+    },
+    GetBuffer {
+        connection_handle: ConnectionHandle,
+        table_handle: TableHandle,
+        reply_sender: oneshot::Sender<Option<WriteBuffer>>,
+    },
+    /// Flush every buffer, then stop. The reply is sent once all the
+    /// buffered rows have been committed (or have failed).
+    Quit {
+        reply_sender: oneshot::Sender<()>,
+    },
+}
+
+pub(crate) struct WriteBufferActor;
+
+impl WriteBufferActor {
+    pub(crate) async fn start() -> Sender<WriteBufferCommand> {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(128);
+        tokio::spawn(async move {
+            let mut buffers = HashMap::<(ConnectionHandle, TableHandle), WriteBuffer>::new();
+
+            while let Some(command) = rx.recv().await {
+                match command {
+                    WriteBufferCommand::Enable {
+                        connection_handle,
+                        table_handle,
+                        table,
+                        policy,
+                        retries,
+                        reply_sender,
+                    } => {
+                        let key = (connection_handle, table_handle);
+                        if buffers.contains_key(&key) {
+                            let err = format!("A write buffer is already enabled for {table_handle:?}");
+                            let _ = reply_sender.send(Err(err));
+                            continue;
+                        }
+                        let (sender, receiver) = tokio::sync::mpsc::channel(128);
+                        let target = FlushTarget {
+                            table,
+                            connection_handle,
+                            table_handle,
+                            retries,
+                        };
+                        tokio::spawn(buffer_task(target, policy, receiver));
+                        buffers.insert(key, WriteBuffer { sender });
+                        let _ = reply_sender.send(Ok(()));
+                    }
+                    WriteBufferCommand::Disable {
+                        connection_handle,
+                        table_handle,
+                        reply_sender,
+                    } => {
+                        let Some(buffer) = buffers.remove(&(connection_handle, table_handle)) else {
+                            let err = format!("No write buffer is enabled for {table_handle:?}");
+                            let _ = reply_sender.send(Err(err));
+                            continue;
+                        };
+                        // The final flush runs on its own, so other tables aren't held up.
+                        tokio::spawn(async move {
+                            let _ = reply_sender.send(buffer.stop().await);
+                        });
+                    }
+                    WriteBufferCommand::GetBuffer {
+                        connection_handle,
+                        table_handle,
+                        reply_sender,
+                    } => {
+                        let _ = reply_sender.send(buffers.get(&(connection_handle, table_handle)).cloned());
+                    }
+                    WriteBufferCommand::Quit { reply_sender } => {
+                        // Buffered rows were accepted, so they are committed before shutting down.
+                        let stops = buffers.drain().map(|(_, buffer)| async move {
+                            let _ = buffer.stop().await;
+                        });
+                        futures::future::join_all(stops).await;
+                        let _ = reply_sender.send(());
+                        break;
+                    }
+                }
+            }
+        });
+        tx
+    }
+}
+
+/// Get the write buffer for a table, if one is enabled.
+pub(crate) async fn get_write_buffer(
+    write_buffers: Sender<WriteBufferCommand>,
+    connection_handle: ConnectionHandle,
+    table_handle: TableHandle,
+) -> Option<WriteBuffer> {
+    let (tx, rx) = oneshot::channel();
+    write_buffers
+        .send(WriteBufferCommand::GetBuffer {
+            connection_handle,
+            table_handle,
+            reply_sender: tx,
+        })
+        .await
+        .ok()?;
+    rx.await.ok().flatten()
+}
+
+/// Batches waiting to be committed.
+#[derive(Default)]
+struct Pending {
+    batches: Vec<RecordBatch>,
+    rows: usize,
+    bytes: usize,
+}
+
+impl Pending {
+    fn push(&mut self, batch: RecordBatch) {
+        self.rows += batch.num_rows();
+        self.bytes += batch.get_array_memory_size();
+        self.batches.push(batch);
+    }
+
+    /// Drop the batches after the first `len`.
+    fn truncate(&mut self, len: usize) {
+        for batch in self.batches.drain(len..) {
+            self.rows -= batch.num_rows();
+            self.bytes -= batch.get_array_memory_size();
+        }
+    }
+
+    fn is_full(&self, policy: &WriteBufferPolicy) -> bool {
+        policy.max_rows.is_some_and(|max| self.rows >= max)
+            || policy.max_bytes.is_some_and(|max| self.bytes >= max)
+    }
+}
+
+/// The table a buffer commits to, and where its retry policy is kept.
+struct FlushTarget {
+    table: Table,
+    connection_handle: ConnectionHandle,
+    table_handle: TableHandle,
+    retries: Sender<RetryCommand>,
+}
+
+/// Runs until stopped (or every `WriteBuffer` is dropped), committing the
+/// pending batches whenever the policy says so. Rows still pending when a
+/// final flush fails are lost; the durability callback reports them.
+async fn buffer_task(
+    target: FlushTarget,
+    policy: WriteBufferPolicy,
+    mut commands: tokio::sync::mpsc::Receiver<BufferCommand>,
+) {
+    let mut pending = Pending::default();
+    let mut interval = policy.flush_interval.map(|period| {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        interval
+    });
+    // The first tick completes immediately; the first timed flush waits a full interval.
+    if let Some(interval) = interval.as_mut() {
+        interval.tick().await;
+    }
+
+    loop {
+        tokio::select! {
+            command = commands.recv() => match command {
+                Some(BufferCommand::Append { batches, reply_sender }) => {
+                    let accepted = pending.batches.len();
+                    for batch in batches {
+                        pending.push(batch);
+                    }
+                    let result = if pending.is_full(&policy) {
+                        flush(&target, &policy, &mut pending).await.map(|_| ())
+                    } else {
+                        Ok(())
+                    };
+                    // A failed append isn't buffered, so the host can retry it; rows
+                    // from earlier appends were already accepted and stay buffered.
+                    if result.is_err() {
+                        pending.truncate(accepted);
+                    }
+                    let _ = reply_sender.send(result);
+                }
+                Some(BufferCommand::Flush { reply_sender }) => {
+                    let _ = reply_sender.send(flush(&target, &policy, &mut pending).await);
+                }
+                Some(BufferCommand::Stop { reply_sender }) => {
+                    let _ = reply_sender.send(flush(&target, &policy, &mut pending).await);
+                    break;
+                }
+                None => {
+                    let _ = flush(&target, &policy, &mut pending).await;
+                    break;
+                }
+            },
+            _ = next_tick(&mut interval) => {
+                let _ = flush(&target, &policy, &mut pending).await;
+            }
+        }
+    }
+}
+
+/// Wait for the next flush interval, or forever if there isn't one.
+async fn next_tick(interval: &mut Option<tokio::time::Interval>) {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Commit the pending batches as a single append, retrying commit conflicts
+/// under the table's retry policy, and tell the host. The batches are only
+/// dropped once they are committed: if the commit fails, the callback (and
+/// the caller) get the error, and the rows stay buffered for the next flush.
+async fn flush(
+    target: &FlushTarget,
+    policy: &WriteBufferPolicy,
+    pending: &mut Pending,
+) -> Result<u64, String> {
+    if pending.batches.is_empty() {
+        return Ok(0);
+    }
+    let rows = pending.rows;
+    let context = format!("Error flushing write buffer ({rows} rows)");
+
+    let retry_policy =
+        get_retry_policy(target.retries.clone(), target.connection_handle, target.table_handle).await;
+    let result = retry_on_conflict(retry_policy.as_ref(), target.table_handle, &context, || {
+        let schema = pending.batches[0].schema();
+        let reader = RecordBatchIterator::new(pending.batches.clone().into_iter().map(Ok), schema);
+        target.table.add(reader).mode(AddDataMode::Append).execute()
+    })
+    .await
+    .map(|_| rows as u64);
+    if result.is_ok() {
+        *pending = Pending::default();
+    }
+
+    if let Some(callback) = policy.durability_callback {
+        let table_handle = target.table_handle;
+        let error = result.as_ref().err().map(|e| CString::new(e.as_str()).unwrap_or_default());
+        spawn_blocking(move || {
+            callback(
+                table_handle.0,
+                rows as u64,
+                error.as_ref().map_or(std::ptr::null(), |e| e.as_ptr()),
+            );
+        })
+        .await
+        .ok();
+    }
+    result
+}
@@ -0,0 +1,171 @@
+//! Streaming write sessions. A session owns a single `table.add` call that
+//! reads from a bounded channel, so the host can send a large dataset one
+//! batch at a time. Memory use is bounded by the channel capacity, and the
+//! whole dataset lands in one commit.
+
+use arrow_array::{RecordBatch, RecordBatchReader};
+use arrow_schema::{ArrowError, SchemaRef};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, SyncSender};
+use std::sync::Arc;
+use tokio::sync::mpsc::Sender;
+use tokio::task::JoinHandle;
+
+/// How many batches can be queued for a session before `write_batch` waits
+/// for the writer to catch up.
+pub(crate) const WRITE_SESSION_QUEUE: usize = 4;
+
+/// Strongly typed write session handle.
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub(crate) struct WriteSessionHandle(pub(crate) i64);
+
+/// Feeds batches from a channel to Lance. The stream ends when every sender
+/// has been dropped; if the session was aborted, it ends with an error
+/// instead, so that nothing is committed.
+pub(crate) struct ChannelReader {
+    schema: SchemaRef,
+    receiver: Receiver<RecordBatch>,
+    aborted: Arc<AtomicBool>,
+    finished: bool,
+}
+
+impl Iterator for ChannelReader {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let batch = self.receiver.recv().ok();
+        self.finished = batch.is_none();
+        if self.aborted.load(Ordering::SeqCst) {
+            self.finished = true;
+            return Some(Err(ArrowError::ExternalError("Write session aborted".into())));
+        }
+        batch.map(Ok)
+    }
+}
+
+impl RecordBatchReader for ChannelReader {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+/// An open write session.
+pub(crate) struct WriteSession {
+    /// The table schema; every batch is conformed to it before sending.
+    pub(crate) schema: SchemaRef,
+    pub(crate) sender: SyncSender<RecordBatch>,
+    aborted: Arc<AtomicBool>,
+    /// The `table.add` call, which finishes once the channel closes.
+    task: JoinHandle<Result<(), String>>,
+}
+
+impl WriteSession {
+    /// Create the channel for a new session. The reader is passed to
+    /// `table.add`, and the returned function builds the session from the
+    /// spawned task.
+    pub(crate) fn channel(
+        schema: SchemaRef,
+    ) -> (ChannelReader, impl FnOnce(JoinHandle<Result<(), String>>) -> WriteSession) {
+        let (sender, receiver) = std::sync::mpsc::sync_channel(WRITE_SESSION_QUEUE);
+        let aborted = Arc::new(AtomicBool::new(false));
+        let reader = ChannelReader {
+            schema: schema.clone(),
+            receiver,
+            aborted: aborted.clone(),
+            finished: false,
+        };
+        let session = move |task| WriteSession {
+            schema,
+            sender,
+            aborted,
+            task,
+        };
+        (reader, session)
+    }
+
+    /// Close the channel, so the writer finishes and commits.
+    pub(crate) async fn commit(self) -> Result<(), String> {
+        drop(self.sender);
+        self.task
+            .await
+            .map_err(|e| format!("Error running write session: {e:?}"))?
+    }
+
+    /// Close the channel with an error, so the writer fails without committing.
+    pub(crate) async fn abort(self) {
+        self.aborted.store(true, Ordering::SeqCst);
+        drop(self.sender);
+        let _ = self.task.await;
+    }
+}
+
+pub(crate) enum WriteSessionCommand {
+    Begin {
+        session: WriteSession,
+        reply_sender: tokio::sync::oneshot::Sender<WriteSessionHandle>,
+    },
+    /// Get what's needed to send a batch to the session.
+    GetSender {
+        handle: WriteSessionHandle,
+        reply_sender: tokio::sync::oneshot::Sender<Option<(SchemaRef, SyncSender<RecordBatch>)>>,
+    },
+    /// Remove the session, so it can be committed or aborted.
+    Finish {
+        handle: WriteSessionHandle,
+        reply_sender: tokio::sync::oneshot::Sender<Option<WriteSession>>,
+    },
+    Quit,
+}
+
+pub(crate) struct WriteSessionActor;
+
+impl WriteSessionActor {
+    pub(crate) async fn start() -> Sender<WriteSessionCommand> {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(128);
+        tokio::spawn(async move {
+            let mut next_id = 1_i64;
+            let mut sessions = HashMap::<WriteSessionHandle, WriteSession>::new();
+
+            while let Some(command) = rx.recv().await {
+                match command {
+                    WriteSessionCommand::Begin {
+                        session,
+                        reply_sender,
+                    } => {
+                        let handle = WriteSessionHandle(next_id);
+                        next_id += 1;
+                        sessions.insert(handle, session);
+                        let _ = reply_sender.send(handle);
+                    }
+                    WriteSessionCommand::GetSender {
+                        handle,
+                        reply_sender,
+                    } => {
+                        let sender = sessions
+                            .get(&handle)
+                            .map(|s| (s.schema.clone(), s.sender.clone()));
+                        let _ = reply_sender.send(sender);
+                    }
+                    WriteSessionCommand::Finish {
+                        handle,
+                        reply_sender,
+                    } => {
+                        let _ = reply_sender.send(sessions.remove(&handle));
+                    }
+                    WriteSessionCommand::Quit => {
+                        // Sessions that were never committed must not commit on shutdown.
+                        for (_, session) in sessions.drain() {
+                            session.abort().await;
+                        }
+                        break;
+                    }
+                }
+            }
+        });
+        tx
+    }
+}